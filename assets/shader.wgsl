@@ -12,10 +12,26 @@ struct GradientStop {
 struct GradientInfo {
     start_pos: vec2<f32>,
     end_pos: vec2<f32>,
+    focal_pos: vec2<f32>,
     use_gradient: u32,
-    stops: array<GradientStop, 2>
+    // 1 for linear, 2 for radial
+    gradient_ty: u32,
+    stop_count: u32,
+    stops: array<GradientStop, 16>
 };
 
+struct EffectData {
+    // 1 for fill, 2 for tint, 3 for tritone, 4 for color overlay
+    ty: u32,
+    amount: f32,
+    colors: array<vec4<f32>, 3>,
+}
+
+struct EffectInfo {
+    effect_count: u32,
+    effects: array<EffectData, 4>,
+}
+
 struct MaskInfo {
     masks: array<vec4<u32>, 4>,
     mask_count: u32,
@@ -37,6 +53,21 @@ var<uniform> mask_info: MaskInfo;
 @group(2) @binding(4)
 var<uniform> gradient: GradientInfo;
 
+@group(2) @binding(5)
+var<uniform> stroke_gradient: GradientInfo;
+
+@group(2) @binding(6)
+var backdrop: texture_2d<f32>;
+
+@group(2) @binding(7)
+var backdrop_sampler: sampler;
+
+@group(2) @binding(8)
+var<uniform> blend_mode: u32;
+
+@group(2) @binding(9)
+var<uniform> effects: EffectInfo;
+
 
 // The structure of the vertex buffer is as specified in `specialize()`
 struct Vertex {
@@ -53,6 +84,8 @@ struct VertexOutput {
     // We pass the vertex color to the fragment shader in location 0
     @location(0) color: vec4<f32>,
     @location(1) instance_index: u32,
+    // 0 for solid colors, 1 for the fill gradient, 2 for the stroke gradient
+    @location(2) gradient: f32,
 };
 
 
@@ -69,61 +102,265 @@ fn vertex(vertex: Vertex) -> VertexOutput {
     // Unpack the `u32` from the vertex buffer into the `vec4<f32>` used by the fragment shader
     out.color = vec4<f32>((vec4<u32>(vertex.color) >> vec4<u32>(0u, 8u, 16u, 24u)) & vec4<u32>(255u)) / 255.0;
     out.instance_index = vertex.instance_index;
+    out.gradient = vertex.uv.x;
     return out;
 }
 
-// https://dawn.googlesource.com/tint/+/refs/heads/chromium/4846/test/benchmark/skinned-shadowed-pbr-fragment.wgsl.expected.wgsl
-const GAMMA = 2.200000048;
-
+// Colors reach the fragment shader in linear light while lottie-web blends
+// them as sRGB values, https://www.w3.org/Graphics/Color/srgb
 fn linearTosRGB(color: vec3<f32>) -> vec3<f32> {
-    let INV_GAMMA = (1.0 / GAMMA);
-    return pow(color, vec3(INV_GAMMA));
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3(0.0031308));
 }
 
 fn sRGBToLinear(color: vec3<f32>) -> vec3<f32> {
-    return pow(color, vec3(GAMMA));
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return select(high, low, color <= vec3(0.04045));
 }
 
-// https://docs.rs/lyon_geom/latest/src/lyon_geom/line.rs.html#650-655
-fn point_projection(pt: vec2<f32>, start: vec2<f32>, end: vec2<f32>) -> vec2<f32> {
+// Position of `pos` along a gradient, 0 at the start (or focal point) and
+// 1 at the end
+fn gradient_offset(info: GradientInfo, pos: vec2<f32>, model: mat4x4<f32>) -> f32 {
+    let start = (model * vec4<f32>(info.start_pos, 0.0, 1.0)).xy;
+    let end = (model * vec4<f32>(info.end_pos, 0.0, 1.0)).xy;
+    if info.gradient_ty == 2u {
+        // Two point conical gradient, from the focal point to the circle
+        // around `start` going through `end`
+        let focal = (model * vec4<f32>(info.focal_pos, 0.0, 1.0)).xy;
+        let radius = distance(start, end);
+        let d = start - focal;
+        let q = pos - focal;
+        let a = dot(d, d) - radius * radius;
+        let qd = dot(q, d);
+        if abs(a) < 0.0001 {
+            return dot(q, q) / (2.0 * qd);
+        }
+        return (qd - sqrt(max(qd * qd - a * dot(q, q), 0.0))) / a;
+    }
     let v = end - start;
-    var a = -v.y;
-    var b = v.x;
-    var c = -(a * start.x + b * start.y);
-    let div = 1.0 / length(vec2(a, b));
-    a = a * div;
-    b = b * div;
-    c = c * div;
-    let x = b * (b * pt.x - a * pt.y) - a * c;
-    let y = a * (a * pt.y - b * pt.x) - b * c;
-    return vec2(x, y);
+    return dot(pos - start, v) / dot(v, v);
+}
+
+fn gradient_color(info: GradientInfo, t: f32) -> vec4<f32> {
+    let t = clamp(t, 0.0, 1.0);
+    var color = info.stops[0].color;
+    for (var i: u32 = 1u; i < info.stop_count; i++) {
+        let prev = info.stops[i - 1u];
+        let stop = info.stops[i];
+        if t >= stop.offset {
+            color = stop.color;
+        } else if t > prev.offset {
+            let ratio = (t - prev.offset) / (stop.offset - prev.offset);
+            color = mix(prev.color, stop.color, ratio);
+        }
+    }
+    return color;
+}
+
+// Same as `MaskMode::initial_coverage`, `mode` is 1 for add, 2 subtract,
+// 3 intersect, 4 lighten, 5 darken and 6 difference
+fn initial_coverage(mode: u32) -> f32 {
+    if mode == 1u || mode == 4u || mode == 6u {
+        return 0.0;
+    }
+    return 1.0;
+}
+
+// Same as `MaskMode::combine`
+fn combine_mask(mode: u32, coverage: f32, mask: f32) -> f32 {
+    switch mode {
+        case 1u: { return coverage + mask - coverage * mask; }
+        case 2u: { return coverage * (1.0 - mask); }
+        case 3u: { return coverage * mask; }
+        case 4u: { return max(coverage, mask); }
+        case 5u: { return min(coverage, mask); }
+        case 6u: { return abs(coverage - mask); }
+        default: { return coverage; }
+    }
+}
+
+fn hard_light(b: f32, s: f32) -> f32 {
+    if s <= 0.5 {
+        return b * 2.0 * s;
+    }
+    let s2 = 2.0 * s - 1.0;
+    return b + s2 - b * s2;
+}
+
+// Blend functions from https://www.w3.org/TR/compositing-1/#blending,
+// `mode` is the value of `BlendMode`
+fn blend_channel(mode: u32, b: f32, s: f32) -> f32 {
+    switch mode {
+        // multiply
+        case 1u: { return b * s; }
+        // screen
+        case 2u: { return b + s - b * s; }
+        // overlay
+        case 3u: { return hard_light(s, b); }
+        // darken
+        case 4u: { return min(b, s); }
+        // lighten
+        case 5u: { return max(b, s); }
+        // color dodge
+        case 6u: {
+            if b == 0.0 {
+                return 0.0;
+            } else if s == 1.0 {
+                return 1.0;
+            }
+            return min(1.0, b / (1.0 - s));
+        }
+        // color burn
+        case 7u: {
+            if b == 1.0 {
+                return 1.0;
+            } else if s == 0.0 {
+                return 0.0;
+            }
+            return 1.0 - min(1.0, (1.0 - b) / s);
+        }
+        // hard light
+        case 8u: { return hard_light(b, s); }
+        // soft light
+        case 9u: {
+            if s <= 0.5 {
+                return b - (1.0 - 2.0 * s) * b * (1.0 - b);
+            }
+            var d = sqrt(b);
+            if b <= 0.25 {
+                d = ((16.0 * b - 12.0) * b + 4.0) * b;
+            }
+            return b + (2.0 * s - 1.0) * (d - b);
+        }
+        // difference
+        case 10u: { return abs(b - s); }
+        // exclusion
+        case 11u: { return b + s - 2.0 * b * s; }
+        // add
+        case 16u: { return min(1.0, b + s); }
+        default: { return s; }
+    }
+}
+
+fn lum(c: vec3<f32>) -> f32 {
+    return dot(c, vec3(0.3, 0.59, 0.11));
+}
+
+fn clip_color(c: vec3<f32>) -> vec3<f32> {
+    let l = lum(c);
+    let n = min(min(c.r, c.g), c.b);
+    let x = max(max(c.r, c.g), c.b);
+    var result = c;
+    if n < 0.0 {
+        result = l + (result - l) * l / (l - n);
+    }
+    if x > 1.0 {
+        result = l + (result - l) * (1.0 - l) / (x - l);
+    }
+    return result;
+}
+
+fn set_lum(c: vec3<f32>, l: f32) -> vec3<f32> {
+    return clip_color(c + (l - lum(c)));
+}
+
+fn sat(c: vec3<f32>) -> f32 {
+    return max(max(c.r, c.g), c.b) - min(min(c.r, c.g), c.b);
+}
+
+fn set_sat(c: vec3<f32>, s: f32) -> vec3<f32> {
+    let n = min(min(c.r, c.g), c.b);
+    let x = max(max(c.r, c.g), c.b);
+    if x <= n {
+        return vec3(0.0);
+    }
+    return (c - n) * s / (x - n);
+}
+
+fn blend(mode: u32, b: vec3<f32>, s: vec3<f32>) -> vec3<f32> {
+    switch mode {
+        // hue
+        case 12u: { return set_lum(set_sat(s, sat(b)), lum(b)); }
+        // saturation
+        case 13u: { return set_lum(set_sat(b, sat(s)), lum(b)); }
+        // color
+        case 14u: { return set_lum(s, lum(b)); }
+        // luminosity
+        case 15u: { return set_lum(b, lum(s)); }
+        default: {
+            return vec3(
+                blend_channel(mode, b.r, s.r),
+                blend_channel(mode, b.g, s.g),
+                blend_channel(mode, b.b, s.b),
+            );
+        }
+    }
 }
 
 /// Entry point for the fragment shader
+// Same as the color effects and color overlays of the skia renderer, colors are not
+// premultiplied
+fn apply_effect(effect: EffectData, color: vec4<f32>) -> vec4<f32> {
+    let luma = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+    if effect.ty == 1u {
+        return vec4(effect.colors[0].rgb, color.a * effect.amount);
+    } else if effect.ty == 2u {
+        let mapped = mix(effect.colors[0].rgb, effect.colors[1].rgb, luma);
+        return vec4(mix(color.rgb, mapped, effect.amount), color.a);
+    } else if effect.ty == 3u {
+        var mapped: vec3<f32>;
+        if luma < 0.5 {
+            mapped = mix(effect.colors[0].rgb, effect.colors[1].rgb, luma * 2.0);
+        } else {
+            mapped = mix(effect.colors[1].rgb, effect.colors[2].rgb, luma * 2.0 - 1.0);
+        }
+        return vec4(mix(mapped, color.rgb, effect.amount), color.a);
+    } else if effect.ty == 4u {
+        return vec4(mix(color.rgb, effect.colors[0].rgb, effect.amount), color.a);
+    }
+    return color;
+}
+
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     var out: vec4<f32>;
     let scale = scene_size.z;
     let pos = in.position.xy / scale;
     let model = get_model_matrix(in.instance_index);
-    if gradient.use_gradient == 1u {
-        // let start = vec2(0.0, 0.0);
-        let start = (model * vec4<f32>(gradient.start_pos, 0.0, 1.0)).xy;
-        // let end = vec2(0.0, 0.0);
-        let end = (model * vec4<f32>(gradient.end_pos, 0.0, 1.0)).xy;
-        let proj = point_projection(pos.xy, start, end);
-        let inv = smoothstep(start, end, proj);
-        var t = inv.x;
-        if end.x == start.x {
-            t = inv.y;
-        }
-        let color = mix(gradient.stops[0].color.xyz, gradient.stops[1].color.xyz, t);
-        out = vec4(color, 1.0);
+    if in.gradient > 1.5 {
+        let t = gradient_offset(stroke_gradient, pos.xy, model);
+        out = gradient_color(stroke_gradient, t) * in.color;
+    } else if in.gradient > 0.5 {
+        let t = gradient_offset(gradient, pos.xy, model);
+        out = gradient_color(gradient, t) * in.color;
     } else {
         out = in.color;
     }
+    if effects.effect_count > 0u {
+        // Effect colors are sRGB values
+        out = vec4(linearTosRGB(out.rgb), out.a);
+        for (var i: u32 = 0u; i < effects.effect_count; i++) {
+            out = apply_effect(effects.effects[i], out);
+        }
+        out = vec4(sRGBToLinear(out.rgb), out.a);
+    }
+    // Blend with the layers below, the result is then composited over them
+    // as usual
+    let backdrop_uv = in.position.xy / vec2<f32>(textureDimensions(backdrop));
+    let backdrop_pixel = textureSample(backdrop, backdrop_sampler, backdrop_uv);
+    if blend_mode != 0u {
+        let blended = sRGBToLinear(
+            blend(blend_mode, linearTosRGB(backdrop_pixel.rgb), linearTosRGB(out.rgb)),
+        );
+        out = vec4(mix(out.rgb, blended, backdrop_pixel.a), out.a);
+    }
     let mask_size = vec2<f32>(textureDimensions(mask));
     let count = mask_info.mask_count;
+    // Coverage of the layer masks combined so far, negative when there is
+    // none pending
+    var coverage = -1.0;
     for (var i: u32 = 0u; i < count; i++) {
         let info = mask_info.masks[i];
         let mask_index = f32(info.x);
@@ -131,11 +368,40 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
         let stride = vec2(mask_size.x / mask_count, 0.0);
         let sample_pos = (pos.xy + stride * mask_index) / mask_size;
         var mask_pixel = textureSample(mask, mask_sampler, sample_pos);
-        if info.y == 2u {
+        if info.z != 0u {
+            // The first mask of a layer flushes masks of its ancestors
+            if (info.w & 2u) != 0u {
+                if coverage >= 0.0 {
+                    out.a *= coverage;
+                }
+                coverage = initial_coverage(info.z);
+            }
+            var value = mask_pixel.a;
+            if (info.w & 1u) != 0u {
+                value = 1.0 - value;
+            }
+            coverage = combine_mask(info.z, coverage, value);
+            continue;
+        }
+        if coverage >= 0.0 {
+            out.a *= coverage;
+            coverage = -1.0;
+        }
+        // Luma mattes use the luminance of the matte layer, transparent
+        // areas count as black
+        let luma = dot(mask_pixel.rgb, vec3(0.2126, 0.7152, 0.0722)) * mask_pixel.a;
+        if info.y == 4u {
+            out.a *= (1.0 - luma);
+        } else if info.y == 3u {
+            out.a *= luma;
+        } else if info.y == 2u {
             out.a *= (1.0 - mask_pixel.a);
         } else if info.y == 1u {
             out.a *= mask_pixel.a;
         }
     }
+    if coverage >= 0.0 {
+        out.a *= coverage;
+    }
     return out;
 }
\ No newline at end of file
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::layer::media::Media;
+use crate::model::{Asset, Model};
+use crate::{AssetResolver, Error};
+
+const MANIFEST: &str = "manifest.json";
+const ANIMATIONS_DIR: &str = "animations";
+const IMAGES_DIR: &str = "images";
+/// Largest file accepted in an archive by [DotLottie::from_reader]
+const ENTRY_LIMIT: usize = 16 * 1_024 * 1_024;
+/// Largest total size of the files of an archive
+const ARCHIVE_LIMIT: usize = 64 * 1_024 * 1_024;
+
+/// Content of `manifest.json` in a dotLottie archive
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub animations: Vec<ManifestAnimation>,
+    #[serde(
+        rename = "activeAnimationId",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_animation_id: Option<String>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Manifest {
+            version: default_version(),
+            generator: Some(format!("lottie-rs {}", env!("CARGO_PKG_VERSION"))),
+            author: None,
+            animations: vec![],
+            active_animation_id: None,
+        }
+    }
+}
+
+fn default_version() -> String {
+    String::from("1.0")
+}
+
+/// An animation entry of the [Manifest], the animation itself is stored as
+/// `animations/{id}.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestAnimation {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    #[serde(rename = "loop", default, skip_serializing_if = "Option::is_none")]
+    pub looping: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autoplay: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<i8>,
+}
+
+impl ManifestAnimation {
+    pub fn new(id: &str) -> Self {
+        ManifestAnimation {
+            id: id.to_string(),
+            speed: None,
+            looping: None,
+            autoplay: None,
+            direction: None,
+        }
+    }
+}
+
+/// A dotLottie (`.lottie`) container, which is a zip archive holding a
+/// `manifest.json`, animations under `animations/` and their images under
+/// `images/`
+#[derive(Debug, Clone, Default)]
+pub struct DotLottie {
+    pub manifest: Manifest,
+    animations: HashMap<String, Vec<u8>>,
+    images: HashMap<String, Vec<u8>>,
+}
+
+impl DotLottie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the whole archive into memory. Files larger than 16 MB, or
+    /// archives larger than 64 MB once extracted, are rejected
+    pub fn from_reader<R: Read + Seek>(r: R) -> Result<Self, Error> {
+        let mut archive = ZipArchive::new(r)?;
+        let mut manifest = None;
+        let mut animations = HashMap::new();
+        let mut images = HashMap::new();
+        let mut total = 0;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let name = file.name().trim_start_matches('/').to_string();
+            // Sizes in the zip headers can't be trusted, the content is
+            // read up to the limit instead
+            let limit = ENTRY_LIMIT.min(ARCHIVE_LIMIT - total);
+            let mut content = vec![];
+            (&mut file)
+                .take((limit + 1) as u64)
+                .read_to_end(&mut content)?;
+            if content.len() > ENTRY_LIMIT {
+                return Err(Error::DotLottieEntryTooLarge(name, ENTRY_LIMIT));
+            } else if content.len() > limit {
+                return Err(Error::DotLottieTooLarge(ARCHIVE_LIMIT));
+            }
+            total += content.len();
+            if name == MANIFEST {
+                manifest = Some(serde_json::from_slice::<Manifest>(&content)?);
+            } else if let Some(id) = name
+                .strip_prefix("animations/")
+                .and_then(|name| name.strip_suffix(".json"))
+            {
+                animations.insert(id.to_string(), content);
+            } else if let Some(image) = name.strip_prefix("images/") {
+                images.insert(image.to_string(), content);
+            }
+        }
+        let manifest = manifest.ok_or(Error::DotLottieManifestMissing)?;
+        Ok(DotLottie {
+            manifest,
+            animations,
+            images,
+        })
+    }
+
+    /// Ids of animations listed in the manifest
+    pub fn animation_ids(&self) -> impl Iterator<Item = &str> {
+        self.manifest.animations.iter().map(|a| a.id.as_str())
+    }
+
+    /// The animation to play by default, which is the active animation in the
+    /// manifest, or the first one if not specified
+    pub fn default_animation_id(&self) -> Option<&str> {
+        self.manifest
+            .active_animation_id
+            .as_deref()
+            .or_else(|| self.animation_ids().next())
+    }
+
+    /// Parse the animation with the given `id`. Its media assets are resolved
+    /// by the archive itself, see the [AssetResolver] implementation
+    pub fn model(&self, id: &str) -> Result<Model, Error> {
+        let content = self
+            .animations
+            .get(id)
+            .ok_or_else(|| Error::DotLottieAnimationNotFound(id.to_string()))?;
+        Ok(Model::from_reader(&mut content.as_slice())?)
+    }
+
+    /// Add `model` as animation `id`, replacing any animation with the same
+    /// id. Media assets are loaded with `resolver` and moved into `images/`,
+    /// data urls included
+    pub fn add_animation(
+        &mut self,
+        id: &str,
+        model: &Model,
+        resolver: &dyn AssetResolver,
+    ) -> Result<(), Error> {
+        let mut model = model.clone();
+        for asset in &mut model.assets {
+            let media = match asset {
+                Asset::Media(media) => media,
+                _ => continue,
+            };
+            let content = Media::new(media.clone(), resolver)?.content;
+            let mut name = image_name(media);
+            if self
+                .images
+                .get(&name)
+                .is_some_and(|image| *image != content)
+            {
+                name = format!("{}_{}", id, name);
+            }
+            self.images.insert(name.clone(), content);
+            media.pwd = format!("/{}/", IMAGES_DIR);
+            media.filename = name;
+            media.embedded = false;
+        }
+        let mut content = vec![];
+        model.to_writer(&mut content)?;
+        self.animations.insert(id.to_string(), content);
+        if !self.manifest.animations.iter().any(|a| a.id == id) {
+            self.manifest.animations.push(ManifestAnimation::new(id));
+        }
+        Ok(())
+    }
+
+    /// Write the archive out as a `.lottie` file
+    pub fn to_writer<W: Write + Seek>(&self, w: W) -> Result<(), Error> {
+        let mut zip = ZipWriter::new(w);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file(MANIFEST, options)?;
+        serde_json::to_writer(&mut zip, &self.manifest)?;
+        for animation in &self.manifest.animations {
+            if let Some(content) = self.animations.get(&animation.id) {
+                zip.start_file(format!("{}/{}.json", ANIMATIONS_DIR, animation.id), options)?;
+                zip.write_all(content)?;
+            }
+        }
+        // Images are compressed already
+        let options = options.compression_method(CompressionMethod::Stored);
+        for (name, content) in &self.images {
+            zip.start_file(format!("{}/{}", IMAGES_DIR, name), options)?;
+            zip.write_all(content)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// Bundled images are served from `images/`, the filesystem and the network
+/// are never accessed
+impl AssetResolver for DotLottie {
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error> {
+        path.trim_start_matches('/')
+            .strip_prefix("images/")
+            .and_then(|name| self.images.get(name))
+            .cloned()
+            .ok_or_else(|| Error::AssetNotFound(path.to_string()))
+    }
+}
+
+/// File name of an image in `images/`, data urls are named after the asset id
+fn image_name(media: &crate::model::Media) -> String {
+    match media.filename.strip_prefix("data:") {
+        Some(data) => {
+            let ext = data
+                .split([';', ','])
+                .next()
+                .and_then(|mime| mime.strip_prefix("image/"))
+                .map(|ext| match ext {
+                    "jpeg" => "jpg",
+                    "svg+xml" => "svg",
+                    ext => ext,
+                })
+                .unwrap_or("png");
+            format!("{}.{}", media.id, ext)
+        }
+        None => Path::new(&media.filename)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| media.id.to_string()),
+    }
+}
@@ -12,16 +12,52 @@ pub enum Error {
     FontNotLoaded(String),
     #[error("Font family {0} doesn't contain the glyph for {1}")]
     FontGlyphNotFound(String, char),
+    #[cfg(feature = "text")]
     #[error(transparent)]
-    Network(#[from] ureq::Error),
-    #[error("Url {0} response contains no Content-Length header")]
-    NetworkMissingContentLength(String),
-    #[error("Url {0} response contains invalid Content-Length header")]
-    NetworkMalformedContentLength(String),
+    FontKit(Box<fontkit::Error>),
+    #[error(transparent)]
+    Network(Box<ureq::Error>),
+    #[error("Asset {0} cannot be resolved")]
+    AssetNotFound(String),
+    #[error("Network access to {0} is denied")]
+    NetworkDenied(String),
+    #[error("Url {0} response is larger than {1} bytes")]
+    NetworkResponseTooLarge(String, usize),
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Url(#[from] url::ParseError),
     #[error(transparent)]
     Base64Decode(#[from] base64::DecodeError),
+    #[error(transparent)]
+    Zip(Box<zip::result::ZipError>),
+    #[error("dotLottie archive contains no manifest.json")]
+    DotLottieManifestMissing,
+    #[error("Animation {0} not found in dotLottie archive")]
+    DotLottieAnimationNotFound(String),
+    #[error("File {0} of dotLottie archive is larger than {1} bytes")]
+    DotLottieEntryTooLarge(String, usize),
+    #[error("dotLottie archive is larger than {0} bytes")]
+    DotLottieTooLarge(usize),
+}
+
+// Large errors are boxed to keep `Result<_, Error>` small
+
+#[cfg(feature = "text")]
+impl From<fontkit::Error> for Error {
+    fn from(error: fontkit::Error) -> Self {
+        Error::FontKit(Box::new(error))
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(error: ureq::Error) -> Self {
+        Error::Network(Box::new(error))
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(error: zip::result::ZipError) -> Self {
+        Error::Zip(Box::new(error))
+    }
 }
@@ -0,0 +1,391 @@
+//! Evaluation of After Effects expressions exported by Bodymovin.
+//!
+//! Expressions are run once when the timeline is built: every animated
+//! property with an expression is evaluated at each frame of its layer, and
+//! the results are baked into linear keyframes. This keeps rendering free of
+//! any JavaScript, at the cost of not supporting expressions that depend on
+//! anything other than time.
+//!
+//! Expression selectors of text animators are evaluated the same way, once
+//! for each unit of the text they select.
+
+use boa_engine::{js_string, Context, JsValue, Source};
+use serde_json::{json, Value};
+
+use crate::layer::text::text_units;
+use crate::model::{Animated, LayerContent, Model, TextBased};
+use crate::Error;
+
+const PRELUDE: &str = include_str!("expression/prelude.js");
+
+/// Upper bound of frames baked for a single property
+const MAX_FRAMES: i64 = 10_000;
+
+/// Iterations allowed to a single loop, above the frames baked by the prelude
+const LOOP_ITERATION_LIMIT: u64 = 100_000;
+
+/// Depth of nested function calls allowed to expressions
+const RECURSION_LIMIT: usize = 256;
+
+/// A property driven by an expression
+struct Property {
+    /// Index of the precomposition asset holding the layer, or `-1` for the
+    /// root composition
+    asset: i64,
+    layer: usize,
+    /// JSON pointer of the property, relative to its layer
+    pointer: String,
+    expression: String,
+    start: i64,
+    end: i64,
+    /// Start time of the layer, keyframes of its properties are relative to it
+    offset: f64,
+}
+
+impl Property {
+    fn absolute_pointer(&self) -> String {
+        if self.asset < 0 {
+            format!("/layers/{}{}", self.layer, self.pointer)
+        } else {
+            format!(
+                "/assets/{}/layers/{}{}",
+                self.asset, self.layer, self.pointer
+            )
+        }
+    }
+}
+
+/// An expression selector of a text animator
+struct TextSelector {
+    asset: i64,
+    layer: usize,
+    /// Index of the animator in the text layer
+    animator: usize,
+    expression: String,
+    /// Number of units of the text, `textTotal` of the expression
+    total: usize,
+    start: i64,
+    end: i64,
+    offset: f64,
+}
+
+/// Evaluate all expressions in `model`, returning a copy where they are
+/// replaced with keyframes. Returns `None` if the model has no expressions.
+/// Expressions failing to evaluate are logged and left unbaked
+pub(crate) fn bake(model: &Model) -> Result<Option<Model>, Error> {
+    let mut root = serde_json::to_value(model)?;
+    let mut selectors = vec![];
+    collect_text_selectors(&mut root["layers"], -1, &mut selectors);
+    if let Some(assets) = root["assets"].as_array_mut() {
+        for (index, asset) in assets.iter_mut().enumerate() {
+            collect_text_selectors(&mut asset["layers"], index as i64, &mut selectors);
+        }
+    }
+    let mut properties = vec![];
+    collect_layers(&root["layers"], -1, &mut properties);
+    if let Some(assets) = root["assets"].as_array() {
+        for (index, asset) in assets.iter().enumerate() {
+            collect_layers(&asset["layers"], index as i64, &mut properties);
+        }
+    }
+    if properties.is_empty() && selectors.is_empty() {
+        return Ok(None);
+    }
+
+    // Files may come from anywhere, expressions which never end fail like any
+    // other error instead of hanging the player
+    let mut context = Context::default();
+    let limits = context.runtime_limits_mut();
+    limits.set_loop_iteration_limit(LOOP_ITERATION_LIMIT);
+    limits.set_recursion_limit(RECURSION_LIMIT);
+    if let Err(e) = init(&mut context, &root) {
+        log::warn!("failed to initialize expressions: {}", e);
+        return Ok(None);
+    }
+    for property in properties {
+        match evaluate(&mut context, &property) {
+            Ok(values) => {
+                if let Some(target) = root.pointer_mut(&property.absolute_pointer()) {
+                    write_keyframes(target, property.start as f64 - property.offset, values);
+                }
+            }
+            Err(e) => log::warn!(
+                "failed to evaluate expression {:?}: {}",
+                property.expression,
+                e
+            ),
+        }
+    }
+    let mut baked: Model = serde_json::from_value(root)?;
+    for selector in selectors {
+        match evaluate_text_selector(&mut context, &selector) {
+            Ok(amounts) => {
+                let layers = match usize::try_from(selector.asset) {
+                    Ok(asset) => match baked.assets.get_mut(asset) {
+                        Some(crate::model::Asset::Precomposition(p)) => &mut p.layers,
+                        _ => continue,
+                    },
+                    Err(_) => &mut baked.layers,
+                };
+                if let Some(LayerContent::Text(text)) =
+                    layers.get_mut(selector.layer).map(|l| &mut l.content)
+                {
+                    if let Some(range) = text.ranges.get_mut(selector.animator) {
+                        range.selector.expression_amounts = amounts;
+                    }
+                }
+            }
+            Err(e) => log::warn!(
+                "failed to evaluate expression selector {:?}: {}",
+                selector.expression,
+                e
+            ),
+        }
+    }
+    Ok(Some(baked))
+}
+
+fn init(context: &mut Context, root: &Value) -> Result<(), String> {
+    context
+        .eval(Source::from_bytes(PRELUDE))
+        .map_err(|e| e.to_string())?;
+    let model = JsValue::from_json(root, context).map_err(|e| e.to_string())?;
+    let init = context
+        .global_object()
+        .get(js_string!("__init"), context)
+        .map_err(|e| e.to_string())?;
+    init.as_callable()
+        .ok_or_else(|| String::from("__init is not a function"))?
+        .call(&JsValue::undefined(), &[model], context)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn evaluate(context: &mut Context, property: &Property) -> Result<Vec<Value>, String> {
+    let source = format!(
+        "__bake({}, {}, {}, (function(time, value, thisComp, thisLayer) {{\n\
+         var content = thisLayer.content, effect = thisLayer.effect, transform = thisLayer.transform;\n\
+         var $bm_rt;\n{}\n;\nreturn $bm_rt;\n}}), {}, {})",
+        property.asset,
+        Value::from(property.pointer.as_str()),
+        property.layer,
+        property.expression,
+        property.start,
+        property.end
+    );
+    let result = context
+        .eval(Source::from_bytes(&source))
+        .map_err(|e| e.to_string())?
+        .to_json(context)
+        .map_err(|e| e.to_string())?;
+    let values = match result {
+        Value::Array(values) => values,
+        _ => return Err(String::from("unexpected result")),
+    };
+    if values.iter().any(Value::is_null) {
+        return Err(String::from("expression returned a non numeric value"));
+    }
+    Ok(values)
+}
+
+/// Amount of each unit of the text selected by an expression selector, as
+/// keyframes
+fn evaluate_text_selector(
+    context: &mut Context,
+    selector: &TextSelector,
+) -> Result<Vec<Animated<f32>>, String> {
+    let source = format!(
+        "__bakeText({}, {}, {}, (function(time, value, thisComp, thisLayer, textIndex, textTotal, selectorValue) {{\n\
+         var content = thisLayer.content, effect = thisLayer.effect, transform = thisLayer.transform;\n\
+         var $bm_rt;\n{}\n;\nreturn $bm_rt;\n}}), {}, {}, {})",
+        selector.asset,
+        Value::from(format!("/t/a/{}/s/a", selector.animator)),
+        selector.layer,
+        selector.expression,
+        selector.start,
+        selector.end,
+        selector.total
+    );
+    let result = context
+        .eval(Source::from_bytes(&source))
+        .map_err(|e| e.to_string())?
+        .to_json(context)
+        .map_err(|e| e.to_string())?;
+    let units = match result {
+        Value::Array(units) => units,
+        _ => return Err(String::from("unexpected result")),
+    };
+    units
+        .into_iter()
+        .map(|values| {
+            let values = match values {
+                Value::Array(values) if !values.iter().any(Value::is_null) => values,
+                _ => return Err(String::from("expression returned a non numeric value")),
+            };
+            let mut target = json!({});
+            write_keyframes(&mut target, selector.start as f64 - selector.offset, values);
+            serde_json::from_value(target).map_err(|e| e.to_string())
+        })
+        .collect()
+}
+
+/// Replace the value of `target` with one linear keyframe per frame
+fn write_keyframes(target: &mut Value, start: f64, values: Vec<Value>) {
+    let len = values.len();
+    let keyframes = values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let value = match value {
+                Value::Array(_) => value,
+                value => Value::Array(vec![value]),
+            };
+            let frame = start + index as f64;
+            if index + 1 == len {
+                json!({ "t": frame, "s": value })
+            } else {
+                json!({
+                    "t": frame,
+                    "s": value,
+                    "o": { "x": [0.0], "y": [0.0] },
+                    "i": { "x": [1.0], "y": [1.0] },
+                })
+            }
+        })
+        .collect::<Vec<_>>();
+    if let Some(target) = target.as_object_mut() {
+        target.remove("x");
+        if keyframes.len() > 1 {
+            target.insert(String::from("a"), json!(1));
+            target.insert(String::from("k"), Value::Array(keyframes));
+        } else if let Some(keyframe) = keyframes.into_iter().next() {
+            target.insert(String::from("a"), json!(0));
+            target.insert(String::from("k"), keyframe["s"].clone());
+        }
+    }
+}
+
+fn collect_layers(layers: &Value, asset: i64, result: &mut Vec<Property>) {
+    let layers = match layers.as_array() {
+        Some(layers) => layers,
+        None => return,
+    };
+    for (index, layer) in layers.iter().enumerate() {
+        let start = layer["ip"].as_f64().unwrap_or(0.0).floor() as i64;
+        let end = layer["op"].as_f64().unwrap_or(0.0).ceil() as i64;
+        let end = end.clamp(start, start + MAX_FRAMES);
+        let offset = layer["st"].as_f64().unwrap_or(0.0);
+        let mut found = vec![];
+        collect_properties(layer, String::new(), &mut found);
+        result.extend(found.into_iter().map(|(pointer, expression)| Property {
+            asset,
+            layer: index,
+            pointer,
+            expression,
+            start,
+            end,
+            offset,
+        }));
+    }
+}
+
+/// Find expression selectors of the text layers in `layers`. Amounts with an
+/// expression are taken out, so they aren't baked as numeric properties
+fn collect_text_selectors(layers: &mut Value, asset: i64, result: &mut Vec<TextSelector>) {
+    let layers = match layers.as_array_mut() {
+        Some(layers) => layers,
+        None => return,
+    };
+    for (index, layer) in layers.iter_mut().enumerate() {
+        let start = layer["ip"].as_f64().unwrap_or(0.0).floor() as i64;
+        let end = layer["op"].as_f64().unwrap_or(0.0).ceil() as i64;
+        let end = end.clamp(start, start + MAX_FRAMES);
+        let offset = layer["st"].as_f64().unwrap_or(0.0);
+        // Units are counted in the first text of the layer
+        let text = layer
+            .pointer("/t/d/k/0/s/t")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let lines = text
+            .split(['\r', '\n', '\u{3}'])
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let animators = match layer.pointer_mut("/t/a").and_then(Value::as_array_mut) {
+            Some(animators) => animators,
+            None => continue,
+        };
+        for (animator, value) in animators.iter_mut().enumerate() {
+            let selector = &mut value["s"];
+            if !matches!(selector["t"].as_u64(), Some(1)) && selector["t"] != json!(true) {
+                continue;
+            }
+            let expression = match selector["x"].as_str() {
+                Some(expression) => Some(expression.to_string()),
+                None => selector["a"]
+                    .as_object_mut()
+                    .and_then(|amount| amount.remove("x"))
+                    .and_then(|x| x.as_str().map(str::to_string)),
+            };
+            let expression = match expression {
+                Some(expression) => expression,
+                None => continue,
+            };
+            let based_on =
+                serde_json::from_value(selector["b"].clone()).unwrap_or(TextBased::Characters);
+            result.push(TextSelector {
+                asset,
+                layer: index,
+                animator,
+                expression,
+                total: text_units(&lines, based_on).1,
+                start,
+                end,
+                offset,
+            });
+        }
+    }
+}
+
+/// Find numeric properties with an expression under `value`
+fn collect_properties(value: &Value, pointer: String, result: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            if let (Some(Value::String(expression)), Some(k)) = (map.get("x"), map.get("k")) {
+                if is_numeric(k) {
+                    result.push((pointer, expression.clone()));
+                    return;
+                }
+            }
+            for (key, value) in map {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                collect_properties(value, format!("{}/{}", pointer, key), result);
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                collect_properties(value, format!("{}/{}", pointer, index), result);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_numeric(k: &Value) -> bool {
+    fn is_number_or_numbers(v: &Value) -> bool {
+        match v {
+            Value::Number(_) => true,
+            Value::Array(values) => !values.is_empty() && values.iter().all(Value::is_number),
+            _ => false,
+        }
+    }
+    match k {
+        Value::Array(keyframes) if keyframes.first().is_some_and(Value::is_object) => {
+            keyframes.iter().all(|keyframe| {
+                keyframe.get("s").is_none_or(is_number_or_numbers)
+                    && keyframe.get("e").is_none_or(is_number_or_numbers)
+            })
+        }
+        k => is_number_or_numbers(k),
+    }
+}
@@ -0,0 +1,544 @@
+// Runtime of the Bodymovin expression API. Properties are read from the raw
+// lottie JSON passed to `__init`, and evaluated at the time of the frame being
+// baked by `__bake`
+var __model, __fps, __ctx;
+
+function __init(model) {
+    __model = model;
+    __fps = model.fr;
+}
+
+// Keyframes
+
+function __first(v) {
+    return Array.isArray(v) ? v[0] : v;
+}
+
+// Cubic bezier easing from (0, 0) to (1, 1), solved for `x` by bisection
+function __bezier(x1, y1, x2, y2, x) {
+    function sample(a1, a2, t) {
+        var u = 1 - t;
+        return 3 * u * u * t * a1 + 3 * u * t * t * a2 + t * t * t;
+    }
+    var lo = 0, hi = 1, t = x;
+    for (var i = 0; i < 40; i++) {
+        var s = sample(x1, x2, t);
+        if (Math.abs(s - x) < 1e-6) {
+            break;
+        }
+        if (s < x) {
+            lo = t;
+        } else {
+            hi = t;
+        }
+        t = (lo + hi) / 2;
+    }
+    return sample(y1, y2, t);
+}
+
+function __lerp(a, b, r) {
+    if (!Array.isArray(a)) {
+        return a + (b - a) * r;
+    }
+    return a.map(function (v, i) {
+        var end = Array.isArray(b) && b[i] !== undefined ? b[i] : v;
+        return v + (end - v) * r;
+    });
+}
+
+function __keyframed(k, frame) {
+    if (!Array.isArray(k) || k.length === 0 || typeof k[0] !== 'object') {
+        return k;
+    }
+    if (frame <= k[0].t) {
+        return k[0].s;
+    }
+    for (var i = 0; i < k.length - 1; i++) {
+        var key = k[i], next = k[i + 1];
+        if (frame < next.t) {
+            var end = key.e !== undefined ? key.e : next.s;
+            if (key.h === 1 || end === undefined) {
+                return key.s;
+            }
+            var x = (frame - key.t) / (next.t - key.t);
+            var r = key.o && key.i
+                ? __bezier(__first(key.o.x), __first(key.o.y), __first(key.i.x), __first(key.i.y), x)
+                : x;
+            return __lerp(key.s, end, r);
+        }
+    }
+    var last = k[k.length - 1];
+    if (last.s !== undefined || k.length < 2) {
+        return last.s;
+    }
+    var prev = k[k.length - 2];
+    return prev.e !== undefined ? prev.e : prev.s;
+}
+
+function __value(prop, time) {
+    if (prop === undefined || prop === null) {
+        return undefined;
+    }
+    // Split position
+    if (prop.s === true) {
+        return [__value(prop.x, time), __value(prop.y, time)];
+    }
+    var v = __keyframed(prop.k, time * __fps);
+    return Array.isArray(v) && v.length === 1 ? v[0] : v;
+}
+
+// Times of the keyframes of `prop`, in composition time
+function __keyTimes(prop, offset) {
+    var k = prop.k;
+    if (!Array.isArray(k) || k.length === 0 || typeof k[0] !== 'object') {
+        return [];
+    }
+    return k.map(function (key) {
+        return key.t / __fps + offset;
+    });
+}
+
+// Layers and shapes
+
+var __NAMES = {
+    anchorPoint: 'a',
+    position: 'p',
+    scale: 's',
+    rotation: 'r',
+    opacity: 'o',
+    skew: 'sk',
+    skewAxis: 'sa',
+    size: 's',
+    roundness: 'r',
+    strokeWidth: 'w',
+    color: 'c',
+    start: 's',
+    end: 'e',
+    offset: 'o',
+    points: 'pt',
+    innerRadius: 'ir',
+    outerRadius: 'or',
+    innerRoundness: 'is',
+    outerRoundness: 'os',
+    path: 'ks'
+};
+
+// Expose animated properties of a lottie object under their AE names.
+// Keyframes are relative to the start time `offset` of their layer
+function __properties(json, offset) {
+    var obj = {};
+    Object.keys(__NAMES).forEach(function (name) {
+        var prop = json[__NAMES[name]];
+        if (prop !== null && typeof prop === 'object' && !Array.isArray(prop)) {
+            Object.defineProperty(obj, name, {
+                get: function () {
+                    return __value(prop, __ctx.time - offset);
+                }
+            });
+        }
+    });
+    return obj;
+}
+
+function __find(list, name, what) {
+    for (var i = 0; i < list.length; i++) {
+        if (list[i].nm === name || list[i].mn === name || i + 1 === name) {
+            return list[i];
+        }
+    }
+    throw new Error(what + ' ' + name + ' not found');
+}
+
+function __shape(json, offset) {
+    var obj = __properties(json, offset);
+    obj.name = json.nm;
+    if (json.ty === 'gr') {
+        var items = json.it || [];
+        obj.content = function (name) {
+            return __shape(__find(items, name, 'content'), offset);
+        };
+        var transform = items.filter(function (item) {
+            return item.ty === 'tr';
+        })[0];
+        if (transform) {
+            obj.transform = __properties(transform, offset);
+        }
+    }
+    return obj;
+}
+
+function __effect(json, name, offset) {
+    var effect = __find(json.ef || [], name, 'effect');
+    var param = function (name) {
+        var prop = __find(effect.ef || [], name, 'effect parameter');
+        return __value(prop.v, __ctx.time - offset);
+    };
+    param.param = param;
+    return param;
+}
+
+function __layer(json) {
+    var offset = (json.st || 0) / __fps;
+    var obj = __properties(json.ks || {}, offset);
+    obj.name = json.nm;
+    obj.index = json.ind;
+    obj.inPoint = json.ip / __fps;
+    obj.outPoint = json.op / __fps;
+    obj.startTime = offset;
+    obj.transform = __properties(json.ks || {}, offset);
+    obj.content = function (name) {
+        return __shape(__find(json.shapes || [], name, 'content'), offset);
+    };
+    obj.effect = function (name) {
+        return __effect(json, name, offset);
+    };
+    return obj;
+}
+
+function __comp(layers, name) {
+    return {
+        name: name,
+        numLayers: layers.length,
+        width: __model.w,
+        height: __model.h,
+        frameDuration: 1 / __fps,
+        layer: function (name) {
+            for (var i = 0; i < layers.length; i++) {
+                if (layers[i].nm === name || layers[i].ind === name) {
+                    return __layer(layers[i]);
+                }
+            }
+            throw new Error('layer ' + name + ' not found');
+        }
+    };
+}
+
+function comp(name) {
+    if (__model.nm === name) {
+        return __comp(__model.layers, name);
+    }
+    var assets = __model.assets || [];
+    for (var i = 0; i < assets.length; i++) {
+        if (assets[i].layers && (assets[i].nm === name || assets[i].id === name)) {
+            return __comp(assets[i].layers, name);
+        }
+    }
+    throw new Error('comp ' + name + ' not found');
+}
+
+// Vector math used by exported expressions
+
+function __map(a, b, f) {
+    var aa = Array.isArray(a), ba = Array.isArray(b);
+    if (!aa && !ba) {
+        return f(a, b);
+    }
+    if (aa && ba) {
+        var result = [];
+        for (var i = 0; i < Math.max(a.length, b.length); i++) {
+            result.push(f(a[i] === undefined ? 0 : a[i], b[i] === undefined ? 0 : b[i]));
+        }
+        return result;
+    }
+    if (aa) {
+        return a.map(function (v) {
+            return f(v, b);
+        });
+    }
+    return b.map(function (v) {
+        return f(a, v);
+    });
+}
+
+function $bm_sum(a, b) {
+    if (typeof a === 'string' || typeof b === 'string') {
+        return a + b;
+    }
+    return __map(a, b, function (x, y) {
+        return x + y;
+    });
+}
+var sum = $bm_sum, add = $bm_sum;
+
+function $bm_sub(a, b) {
+    return __map(a, b, function (x, y) {
+        return x - y;
+    });
+}
+var sub = $bm_sub;
+
+function $bm_mul(a, b) {
+    return __map(a, b, function (x, y) {
+        return x * y;
+    });
+}
+var mul = $bm_mul;
+
+function $bm_div(a, b) {
+    return __map(a, b, function (x, y) {
+        return x / y;
+    });
+}
+var div = $bm_div;
+
+function $bm_mod(a, b) {
+    return __map(a, b, function (x, y) {
+        return x % y;
+    });
+}
+var mod = $bm_mod;
+
+function $bm_neg(a) {
+    return $bm_mul(a, -1);
+}
+
+function $bm_isInstanceOfArray(a) {
+    return Array.isArray(a);
+}
+
+function clamp(v, min, max) {
+    return __map(__map(v, min, Math.max), max, Math.min);
+}
+
+function length(a, b) {
+    var v = b === undefined ? a : $bm_sub(a, b);
+    if (!Array.isArray(v)) {
+        return Math.abs(v);
+    }
+    return Math.sqrt(v.reduce(function (s, x) {
+        return s + x * x;
+    }, 0));
+}
+
+function normalize(v) {
+    return $bm_div(v, length(v));
+}
+
+function degreesToRadians(v) {
+    return v * Math.PI / 180;
+}
+
+function radiansToDegrees(v) {
+    return v * 180 / Math.PI;
+}
+
+function timeToFrames(t) {
+    return Math.round((t === undefined ? __ctx.time : t) * __fps);
+}
+
+function framesToTime(f) {
+    return f / __fps;
+}
+
+// Interpolation
+
+function __interpolate(curve, t, tMin, tMax, v1, v2) {
+    if (v1 === undefined) {
+        v1 = tMin;
+        v2 = tMax;
+        tMin = 0;
+        tMax = 1;
+    }
+    var r = tMax === tMin ? (t >= tMax ? 1 : 0) : (t - tMin) / (tMax - tMin);
+    return __lerp(v1, v2, curve(Math.min(1, Math.max(0, r))));
+}
+
+function linear(t, tMin, tMax, v1, v2) {
+    return __interpolate(function (r) {
+        return r;
+    }, t, tMin, tMax, v1, v2);
+}
+
+function ease(t, tMin, tMax, v1, v2) {
+    return __interpolate(function (r) {
+        return __bezier(0.33, 0, 0.667, 1, r);
+    }, t, tMin, tMax, v1, v2);
+}
+
+function easeIn(t, tMin, tMax, v1, v2) {
+    return __interpolate(function (r) {
+        return __bezier(0.33, 0, 1, 1, r);
+    }, t, tMin, tMax, v1, v2);
+}
+
+function easeOut(t, tMin, tMax, v1, v2) {
+    return __interpolate(function (r) {
+        return __bezier(0, 0, 0.667, 1, r);
+    }, t, tMin, tMax, v1, v2);
+}
+
+// Current property
+
+function valueAtTime(t) {
+    return __value(__ctx.prop, t - __ctx.offset);
+}
+
+function numKeys() {
+    return __keyTimes(__ctx.prop, __ctx.offset).length;
+}
+
+function key(i) {
+    var t = __keyTimes(__ctx.prop, __ctx.offset)[i - 1];
+    return { time: t, value: valueAtTime(t) };
+}
+
+function __loopRange(n, out) {
+    var keys = __keyTimes(__ctx.prop, __ctx.offset);
+    if (keys.length < 2) {
+        return null;
+    }
+    var first = keys[0], last = keys[keys.length - 1];
+    if (n > 0 && n < keys.length) {
+        if (out) {
+            first = keys[keys.length - 1 - n];
+        } else {
+            last = keys[n];
+        }
+    }
+    return last > first ? { first: first, last: last, duration: last - first } : null;
+}
+
+function loopOut(type, n) {
+    var time = __ctx.time, range = __loopRange(n, true);
+    if (range === null || time <= range.last) {
+        return valueAtTime(time);
+    }
+    type = (type || 'cycle').toLowerCase();
+    var first = range.first, last = range.last;
+    var elapsed = time - first;
+    var iteration = Math.floor(elapsed / range.duration);
+    var m = elapsed % range.duration;
+    if (type === 'pingpong') {
+        return valueAtTime(iteration % 2 === 0 ? first + m : last - m);
+    } else if (type === 'offset') {
+        var delta = $bm_sub(valueAtTime(last), valueAtTime(first));
+        return $bm_sum(valueAtTime(first + m), $bm_mul(delta, iteration));
+    } else if (type === 'continue') {
+        var end = valueAtTime(last);
+        var velocity = $bm_div($bm_sub(end, valueAtTime(last - 0.001)), 0.001);
+        return $bm_sum(end, $bm_mul(velocity, time - last));
+    }
+    return valueAtTime(first + m);
+}
+
+function loopIn(type, n) {
+    var time = __ctx.time, range = __loopRange(n, false);
+    if (range === null || time >= range.first) {
+        return valueAtTime(time);
+    }
+    type = (type || 'cycle').toLowerCase();
+    var first = range.first, last = range.last;
+    var elapsed = last - time;
+    var iteration = Math.floor(elapsed / range.duration);
+    var m = elapsed % range.duration;
+    if (type === 'pingpong') {
+        return valueAtTime(iteration % 2 === 0 ? last - m : first + m);
+    } else if (type === 'offset') {
+        var delta = $bm_sub(valueAtTime(last), valueAtTime(first));
+        return $bm_sub(valueAtTime(last - m), $bm_mul(delta, iteration));
+    } else if (type === 'continue') {
+        var start = valueAtTime(first);
+        var velocity = $bm_div($bm_sub(valueAtTime(first + 0.001), start), 0.001);
+        return $bm_sub(start, $bm_mul(velocity, first - time));
+    }
+    return valueAtTime(last - m);
+}
+
+// Smooth value noise in [-1, 1]
+function __random(seed, i) {
+    var x = Math.sin(seed * 12.9898 + i * 78.233) * 43758.5453;
+    return (x - Math.floor(x)) * 2 - 1;
+}
+
+function __noise(seed, x) {
+    var i = Math.floor(x), f = x - i;
+    var a = __random(seed, i), b = __random(seed, i + 1);
+    return a + (b - a) * f * f * (3 - 2 * f);
+}
+
+function wiggle(freq, amp, octaves, ampMult, t) {
+    octaves = octaves === undefined ? 1 : octaves;
+    ampMult = ampMult === undefined ? 0.5 : ampMult;
+    t = t === undefined ? __ctx.time : t;
+    var value = valueAtTime(t);
+    var dims = Array.isArray(value) ? value.length : 1;
+    var offset = [];
+    for (var d = 0; d < dims; d++) {
+        var n = 0, total = 0, a = 1, f = freq;
+        for (var o = 0; o < Math.max(1, octaves); o++) {
+            n += __noise(__ctx.seed + d * 17 + o * 31, t * f) * a;
+            total += a;
+            a *= ampMult;
+            f *= 2;
+        }
+        offset.push(n / total * amp);
+    }
+    return Array.isArray(value) ? $bm_sum(value, offset) : value + offset[0];
+}
+
+// Baking
+
+function __resolve(json, pointer) {
+    return pointer.split('/').slice(1).reduce(function (v, key) {
+        return v[key.replace(/~1/g, '/').replace(/~0/g, '~')];
+    }, json);
+}
+
+function __hash(s) {
+    var h = 0;
+    for (var i = 0; i < s.length; i++) {
+        h = (h * 31 + s.charCodeAt(i)) % 65521;
+    }
+    return h;
+}
+
+function __numeric(v) {
+    if (typeof v === 'number') {
+        return isFinite(v) ? v : null;
+    }
+    if (Array.isArray(v) && v.length > 0 && v.every(function (x) {
+        return typeof x === 'number' && isFinite(x);
+    })) {
+        return v;
+    }
+    return null;
+}
+
+// Evaluate `expression` of the property at `pointer` for composition frames
+// `start` to `end`, non numeric results are returned as `null`
+function __bake(asset, pointer, layer, expression, start, end) {
+    var layers = asset < 0 ? __model.layers : __model.assets[asset].layers;
+    var json = __resolve(layers[layer], pointer);
+    var thisComp = __comp(layers, asset < 0 ? __model.nm : __model.assets[asset].id);
+    var thisLayer = __layer(layers[layer]);
+    var offset = thisLayer.startTime;
+    var results = [];
+    for (var frame = start; frame <= end; frame++) {
+        var time = frame / __fps;
+        __ctx = { time: time, offset: offset, prop: json, seed: __hash(pointer) + layer * 131 + asset };
+        results.push(__numeric(expression(time, __value(json, time - offset), thisComp, thisLayer)));
+    }
+    return results;
+}
+
+// Evaluate an expression selector for each unit of its text, returning the
+// values of each unit at each frame
+function __bakeText(asset, pointer, layer, expression, start, end, total) {
+    var layers = asset < 0 ? __model.layers : __model.assets[asset].layers;
+    var json = __resolve(layers[layer], pointer);
+    var thisComp = __comp(layers, asset < 0 ? __model.nm : __model.assets[asset].id);
+    var thisLayer = __layer(layers[layer]);
+    var offset = thisLayer.startTime;
+    var results = [];
+    for (var index = 1; index <= total; index++) {
+        var values = [];
+        for (var frame = start; frame <= end; frame++) {
+            var time = frame / __fps;
+            __ctx = { time: time, offset: offset, prop: json, seed: __hash(pointer) + layer * 131 + asset + index };
+            var value = __value(json, time - offset);
+            values.push(__numeric(expression(time, value, thisComp, thisLayer, index, total, [100, 100, 100])));
+        }
+        results.push(values);
+    }
+    return results;
+}
@@ -1,51 +1,117 @@
+use std::collections::HashMap;
 use std::io::Read;
 
-use crate::model::{Font as LottieFont, Model};
+use crate::model::{Bezier, Font as LottieFont, Model, Shape, ShapeLayer};
 #[cfg(feature = "text")]
-use fontkit::{Font, FontKey, FontKit};
+use crate::model::{FontPathOrigin, Vector2D};
+#[cfg(feature = "text")]
+use fontkit::{FontKey, FontKit, PathSegment};
 
-use crate::Error;
+use crate::{AssetResolver, Error};
 
-const BUFFER_LIMIT: usize = 10 * 1_024 * 1_024;
+/// Outline of a character at a font size of 1. The origin is on the baseline
+/// and y points down
+#[derive(Debug, Clone, Default)]
+pub struct Glyph {
+    pub beziers: Vec<Bezier>,
+    /// Horizontal distance to the next character
+    pub advance: f32,
+}
 
+/// Source of glyphs for text layers
 pub trait FontDB {
-    type FontKit;
-    type FontKey;
-    type Font;
-    fn new(fontkit: Self::FontKit) -> Self;
-    fn load_fonts_from_model(&mut self, model: &Model) -> Result<(), Error>;
-    fn font(&self, font: &LottieFont) -> Option<&Self::Font>;
-    fn fontkit(&self) -> &Self::FontKit;
-    fn from_reader<R: Read>(r: &mut R, root_path: &str) -> Result<Self, Error> where Self:Sized;
+    fn load_fonts_from_model(
+        &mut self,
+        model: &Model,
+        resolver: &dyn AssetResolver,
+    ) -> Result<(), Error>;
+    /// Glyph of `c` in the lottie `font`
+    fn glyph(&self, font: &LottieFont, c: char) -> Result<Glyph, Error>;
+    /// Create the font database used by [crate::Lottie::from_dotlottie] and,
+    /// unless [FontDB::from_reader] is implemented, by
+    /// [crate::Lottie::from_reader]
+    fn with_root(root_path: &str) -> Result<Self, Error>
+    where
+        Self: Sized;
+    /// Create the font database used by [crate::Lottie::from_reader], before
+    /// the model is read from `r`
+    fn from_reader<R: Read>(_: &mut R, root_path: &str) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Self::with_root(root_path)
+    }
 }
 
+/// A [FontDB] without any font, text layers are skipped
 pub struct MockFont;
 
 impl FontDB for MockFont {
-    type FontKit = ();
-    type FontKey = ();
-    type Font = ();
-    fn new(_: Self::FontKit) -> Self {
-        Self
+    fn load_fonts_from_model(&mut self, _: &Model, _: &dyn AssetResolver) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn glyph(&self, font: &LottieFont, _: char) -> Result<Glyph, Error> {
+        Err(Error::FontNotLoaded(font.name.clone()))
     }
 
-    fn load_fonts_from_model(&mut self, _: &Model) -> Result<(), Error> {
+    fn with_root(_: &str) -> Result<Self, Error> {
+        Ok(Self)
+    }
+}
+
+/// A [FontDB] building glyphs from the `chars` embedded in the model, no font
+/// needs to be installed
+#[derive(Default)]
+pub struct CharsDB {
+    /// Glyphs by font family, font style and character
+    glyphs: HashMap<(String, String, char), Glyph>,
+}
+
+impl FontDB for CharsDB {
+    fn load_fonts_from_model(&mut self, model: &Model, _: &dyn AssetResolver) -> Result<(), Error> {
+        for font_char in &model.chars {
+            let mut chars = font_char.character.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                continue;
+            };
+            let mut beziers = vec![];
+            collect_beziers(&font_char.data.shapes, &mut beziers);
+            let glyph = Glyph {
+                beziers: beziers.iter().map(|bezier| bezier.scale(0.01)).collect(),
+                advance: font_char.width / 100.0,
+            };
+            let key = (font_char.family.clone(), font_char.style.clone(), c);
+            self.glyphs.insert(key, glyph);
+        }
         Ok(())
     }
 
-    fn font(&self, _: &LottieFont) -> Option<&Self::Font> {
-        None
+    fn glyph(&self, font: &LottieFont, c: char) -> Result<Glyph, Error> {
+        self.glyphs
+            .get(&(font.family.clone(), font.style.clone(), c))
+            .cloned()
+            .ok_or_else(|| Error::FontGlyphNotFound(font.name.clone(), c))
     }
 
-    fn fontkit(&self) -> &Self::FontKit {
-        &()
+    fn with_root(_: &str) -> Result<Self, Error> {
+        Ok(Self::default())
     }
+}
 
-    fn from_reader<R: Read>(_: &mut R, _: &str) -> Result<Self, Error> {
-        Ok(Self)
+/// Outlines of the visible paths in `shapes`
+fn collect_beziers(shapes: &[ShapeLayer], result: &mut Vec<Bezier>) {
+    for shape in shapes.iter().filter(|shape| !shape.hidden) {
+        match &shape.shape {
+            Shape::Group { shapes } => collect_beziers(shapes, result),
+            Shape::Path { d, .. } => result.extend(d.initial_value()),
+            _ => {}
+        }
     }
 }
 
+/// A [FontDB] rendering text with installed and remote fonts through
+/// [fontkit]
 #[cfg(feature = "text")]
 pub struct FontKitDB {
     fontkit: FontKit,
@@ -53,30 +119,70 @@ pub struct FontKitDB {
 }
 
 #[cfg(feature = "text")]
-impl FontDB for FontKitDB {
-    type FontKit = FontKit;
-    type FontKey = FontKey;
-    type Font = Font;
-    fn new(fontkit: FontKit) -> Self {
+impl FontKitDB {
+    pub fn new(fontkit: FontKit) -> Self {
         Self {
             fontkit,
-            font_map: std::collections::HashMap::new(),
+            font_map: HashMap::new(),
         }
     }
 
-    #[cfg(not(all(target_os = "unknown", target_arch = "wasm32")))]
-    pub fn from_reader<R: Read>(r: R, root_path: &str) -> Result<Self, Error> {
-        let fontkit = FontKit::new();
-        let path = dirs::font_dir().unwrap();
-        fontkit.search_fonts_from_path(path)?;
-        #[cfg(target_os = "macos")]
-        fontkit.search_fonts_from_path(std::path::PathBuf::from("/System/Library/Fonts"))?;
-        let model = Model::from_reader(r)?;
-        Ok(Lottie::new(model, fontkit, root_path)?)
+    pub fn fontkit(&self) -> &FontKit {
+        &self.fontkit
     }
 
+    fn font(&self, font: &LottieFont) -> Option<impl std::ops::Deref<Target = fontkit::Font> + '_> {
+        match font.origin {
+            // This is not an html player. So we treat script/css urls as local obtained fonts
+            // TODO: could this be a thing in WASM target?
+            FontPathOrigin::Local | FontPathOrigin::ScriptUrl | FontPathOrigin::CssUrl => self
+                .fontkit
+                .query(&FontKey::new_with_family(font.name.clone()))
+                .or_else(|| {
+                    self.fontkit
+                        .query(&FontKey::new_with_family(font.family.clone()))
+                })
+                .or_else(|| {
+                    // default font
+                    self.fontkit
+                        .query(&FontKey::new_with_family("Fira Mono".to_string()))
+                }),
+            // TODO: What if font from url is *.ttc and font.name points to one font in the
+            // collection? Could this be possible?
+            FontPathOrigin::FontUrl => self.fontkit.query(self.font_map.get(&font.name)?.first()?),
+        }
+    }
+}
 
-    fn load_fonts_from_model(&mut self, model: &Model) -> Result<(), Error> {
+/// Without installed fonts, text is rendered with the bundled Fira Mono
+#[cfg(feature = "text")]
+impl Default for FontKitDB {
+    fn default() -> Self {
+        Self::new(FontKit::new())
+    }
+}
+
+#[cfg(feature = "text")]
+impl FontDB for FontKitDB {
+    /// Installed fonts are searched
+    fn with_root(_: &str) -> Result<Self, Error> {
+        let fontkit = FontKit::new();
+        #[cfg(not(all(target_os = "unknown", target_arch = "wasm32")))]
+        {
+            if let Some(path) = dirs::font_dir().filter(|path| path.exists()) {
+                fontkit.search_fonts_from_path(path)?;
+            }
+            #[cfg(target_os = "macos")]
+            fontkit.search_fonts_from_path(std::path::PathBuf::from("/System/Library/Fonts"))?;
+        }
+        Ok(Self::new(fontkit))
+    }
+
+    fn load_fonts_from_model(
+        &mut self,
+        model: &Model,
+        resolver: &dyn AssetResolver,
+    ) -> Result<(), Error> {
         // load default font
         #[cfg(not(target_arch = "wasm32"))]
         {
@@ -91,21 +197,14 @@ impl FontDB for FontKitDB {
                 path.push("assets/FiraMono-Regular.ttf");
             }
             if path.exists() {
-                self.fontkit.search_fonts_from_path(
-                    &path.into_os_string().into_string().unwrap_or_default(),
-                )?;
+                self.fontkit.search_fonts_from_path(path)?;
             }
         }
         // load remote fonts
         for font in &model.fonts.list {
             if let Some(path) = font.path.as_ref() {
-                if font.origin == crate::model::FontPathOrigin::FontUrl {
-                    let response = ureq::get(path).call()?;
-                    let mut bytes = vec![];
-                    response
-                        .into_reader()
-                        .take((BUFFER_LIMIT + 1) as u64)
-                        .read_to_end(&mut bytes)?;
+                if font.origin == FontPathOrigin::FontUrl {
+                    let bytes = resolver.resolve(path)?;
                     let keys = self.fontkit.add_font_from_buffer(bytes)?;
                     self.font_map.insert(font.name.clone(), keys);
                 }
@@ -114,29 +213,72 @@ impl FontDB for FontKitDB {
         Ok(())
     }
 
-    fn font(&self, font: &LottieFont) -> Option<impl Deref<Target = Font> + '_> {
-        match font.origin {
-            // This is not an html player. So we treat script/css urls as local obtained fonts
-            // TODO: could this be a thing in WASM target?
-            FontPathOrigin::Local | FontPathOrigin::ScriptUrl | FontPathOrigin::CssUrl => self
-                .fontkit
-                .query(&FontKey::new_with_family(font.name.clone()))
-                .or_else(|| {
-                    self.fontkit
-                        .query(&FontKey::new_with_family(font.family.clone()))
-                })
-                .or_else(|| {
-                    // default font
-                    self.fontkit
-                        .query(&FontKey::new_with_family("Fira Mono".to_string()))
-                }),
-            // TODO: What if font from url is *.ttc and font.name points to one font in the
-            // collection? Could this be possible?
-            FontPathOrigin::FontUrl => self.fontkit.query(self.font_map.get(&font.name)?.first()?),
-        }
-    }
+    fn glyph(&self, font: &LottieFont, c: char) -> Result<Glyph, Error> {
+        let lottie_font = font;
+        let font = self
+            .font(lottie_font)
+            .ok_or_else(|| Error::FontNotLoaded(lottie_font.name.clone()))?;
+        let (glyph, _) = font
+            .outline(c)
+            .ok_or_else(|| Error::FontGlyphNotFound(lottie_font.name.clone(), c))?;
+        let factor = 1.0 / glyph.units as f32;
+        let point = |p: fontkit::tiny_skia_path::Point| Vector2D::new(p.x, -p.y) * factor;
 
-    fn fontkit(&self) -> &FontKit {
-        &self.fontkit
+        let mut bezier = Bezier::default();
+        let mut beziers = vec![];
+        let mut last_pt = Vector2D::new(0.0, 0.0);
+        let segments = glyph.path.finish();
+        let segments = segments
+            .as_ref()
+            .map(|p| p.segments())
+            .into_iter()
+            .flatten();
+        for segment in segments {
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    if !bezier.verticies.is_empty() {
+                        let mut old = std::mem::take(&mut bezier);
+                        old.out_tangent.push(Vector2D::new(0.0, 0.0));
+                        beziers.push(old);
+                    }
+                    bezier.in_tangent.push(Vector2D::new(0.0, 0.0));
+                    last_pt = point(p);
+                    bezier.verticies.push(last_pt);
+                }
+                PathSegment::LineTo(p) => {
+                    let pt = point(p);
+                    bezier.out_tangent.push(Vector2D::new(0.0, 0.0));
+                    bezier.in_tangent.push(Vector2D::new(0.0, 0.0));
+                    bezier.verticies.push(pt);
+                    last_pt = pt;
+                }
+                PathSegment::CubicTo(p1, p2, p) => {
+                    let pt = point(p);
+                    bezier.out_tangent.push(point(p1) - last_pt);
+                    bezier.in_tangent.push(point(p2) - pt);
+                    bezier.verticies.push(pt);
+                    last_pt = pt;
+                }
+                PathSegment::QuadTo(p1, p) => {
+                    // Elevate to a cubic curve
+                    let (pt1, pt) = (point(p1), point(p));
+                    bezier.out_tangent.push((pt1 - last_pt) * 2.0 / 3.0);
+                    bezier.in_tangent.push((pt1 - pt) * 2.0 / 3.0);
+                    bezier.verticies.push(pt);
+                    last_pt = pt;
+                }
+                PathSegment::Close => {
+                    bezier.closed = true;
+                }
+            }
+        }
+        if !bezier.verticies.is_empty() {
+            bezier.out_tangent.push(Vector2D::new(0.0, 0.0));
+            beziers.push(bezier);
+        }
+        Ok(Glyph {
+            beziers,
+            advance: glyph.advanced_x as f32 * factor,
+        })
     }
 }
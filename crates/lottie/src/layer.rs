@@ -1,7 +1,9 @@
 pub mod frame;
 pub mod hierarchy;
 pub mod media;
+pub mod merge;
+pub mod modifier;
 pub mod shape;
 pub mod staged;
-#[cfg(feature = "font")]
 pub mod text;
+pub mod trim;
@@ -42,8 +42,10 @@ pub struct FrameTransformHierarchy {
 
 impl FrameTransformHierarchy {
     pub fn value(&self, mut frame: f32) -> Option<f32> {
+        // Layers are visible from their in point up to, not including, their
+        // out point
         for item in &self.stack {
-            if frame < item.start_frame || frame > item.end_frame {
+            if frame < item.start_frame || frame >= item.end_frame {
                 return None;
             }
             frame = item.frame_transform.transform(frame);
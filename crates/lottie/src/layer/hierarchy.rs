@@ -1,4 +1,4 @@
-use crate::model::{Animated, MatteMode, Transform};
+use crate::model::{Animated, MaskMode, MatteMode, Transform};
 
 use crate::prelude::Id;
 
@@ -49,6 +49,27 @@ impl OpacityHierarchy {
 pub struct StagedLayerMask {
     pub mode: MatteMode,
     pub id: Id,
+    /// Set when the source is one of the masks of a layer rather than a track
+    /// matte. The source is then drawn with [MatteMode::Alpha]
+    pub mask: Option<LayerMask>,
+}
+
+/// A mask from `masksProperties`. Consecutive masks of the same layer are
+/// combined with each other in order, before the result is applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerMask {
+    pub mode: MaskMode,
+    pub inverted: bool,
+    /// Position among the masks of its layer, a new combination starts at 0
+    pub index: usize,
+}
+
+impl LayerMask {
+    /// Combine the `coverage` of the previous masks with this `mask`
+    pub fn combine(&self, coverage: f32, mask: f32) -> f32 {
+        let mask = if self.inverted { 1.0 - mask } else { mask };
+        self.mode.combine(coverage, mask)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -0,0 +1,170 @@
+use flo_curves::bezier::path::{
+    path_add, path_intersect, path_remove_interior_points, path_sub, SimpleBezierPath,
+};
+use flo_curves::Coord2;
+use glam::Mat4;
+use lyon_path::geom::CubicBezierSegment;
+use lyon_path::math::{point, Point, Transform as PathTransform};
+use lyon_path::Path;
+
+use crate::layer::modifier::{build, contours, line, Contour, PathModifier};
+use crate::layer::shape::{expand_merges, expand_repeaters, PathFactory};
+use crate::model::*;
+
+/// Precision of path arithmetic
+const ACCURACY: f64 = 0.01;
+
+/// Combines the shapes above a [Shape::Merge] at a certain frame. Boolean
+/// operations are applied between the last shape and all shapes before it,
+/// same as lottie-android
+impl PathFactory for MergePaths {
+    fn path(&self, frame: f32) -> Path {
+        let mut paths = paths(&self.shapes, frame);
+        if let MergeMode::Merge = self.mode {
+            return concat(&paths);
+        }
+        let last = match paths.pop() {
+            Some(last) => to_beziers(&last),
+            None => return Path::new(),
+        };
+        let rest = paths
+            .iter()
+            .map(to_beziers)
+            .reduce(|union, path| path_add(&union, &path, ACCURACY))
+            .unwrap_or_default();
+        let result = match self.mode {
+            MergeMode::Merge | MergeMode::Add => path_add(&rest, &last, ACCURACY),
+            MergeMode::Subtract => path_sub(&last, &rest, ACCURACY),
+            MergeMode::Intersect => path_intersect(&rest, &last, ACCURACY),
+            MergeMode::ExcludeIntersections => {
+                let mut result = path_sub(&rest, &last, ACCURACY);
+                result.extend(path_sub::<SimpleBezierPath>(&last, &rest, ACCURACY));
+                result
+            }
+        };
+        from_beziers(&result)
+    }
+
+    fn is_animated(&self) -> bool {
+        is_animated(&self.shapes)
+    }
+}
+
+/// Paths of the shapes and groups in `shapes` with their modifiers applied,
+/// in the coordinate space of `shapes`. Each group produces a single path,
+/// the repeaters and merges inside it are expanded as in
+/// [crate::prelude::StyledShapeIterator]
+fn paths(shapes: &[ShapeLayer], frame: f32) -> Vec<Path> {
+    shapes
+        .iter()
+        .enumerate()
+        .filter(|(_, layer)| !layer.hidden)
+        .filter_map(|(index, layer)| {
+            let path = match &layer.shape {
+                Shape::Group { shapes } => {
+                    let shapes = expand_merges(expand_repeaters(shapes));
+                    let transform = shapes
+                        .iter()
+                        .find_map(|layer| match &layer.shape {
+                            Shape::Transform(t) => Some(t.value(frame)),
+                            _ => None,
+                        })
+                        .unwrap_or(Mat4::IDENTITY);
+                    concat(&paths(&shapes, frame)).transformed(&path_transform(transform))
+                }
+                Shape::Ellipse(ellipse) => ellipse.path(frame),
+                Shape::PolyStar(star) => star.path(frame),
+                Shape::Rectangle(rect) => rect.path(frame),
+                Shape::Path { d, .. } => d.value(frame).path(frame),
+                Shape::Merge(merge) => merge.path(frame),
+                _ => return None,
+            };
+            let path = shapes[index + 1..]
+                .iter()
+                .filter(|layer| layer.shape.is_modifier() && !layer.hidden)
+                .fold(path, |path, modifier| modifier.shape.modify(&path, frame));
+            Some(path)
+        })
+        .collect()
+}
+
+fn is_animated(shapes: &[ShapeLayer]) -> bool {
+    shapes.iter().any(|layer| match &layer.shape {
+        Shape::Group { shapes } => is_animated(shapes),
+        Shape::Transform(t) => t.is_animated(),
+        Shape::Ellipse(ellipse) => ellipse.is_animated(),
+        Shape::PolyStar(star) => star.is_animated(),
+        Shape::Rectangle(rect) => rect.is_animated(),
+        Shape::Path { d, .. } => d.is_animated(),
+        Shape::Merge(merge) => merge.is_animated(),
+        // Properties of modifiers are not tracked
+        shape => shape.is_modifier(),
+    })
+}
+
+fn concat(paths: &[Path]) -> Path {
+    let slices = paths.iter().map(Path::as_slice).collect::<Vec<_>>();
+    let mut builder = Path::builder();
+    builder.extend_from_paths(&slices);
+    builder.build()
+}
+
+fn path_transform(m: Mat4) -> PathTransform {
+    let m = m.to_cols_array();
+    PathTransform::new(m[0], m[1], m[4], m[5], m[12], m[13])
+}
+
+fn coord(p: Point) -> Coord2 {
+    Coord2(p.x as f64, p.y as f64)
+}
+
+/// Subpaths of `path` without overlapping areas, flo_curves treats every
+/// subpath as closed
+fn to_beziers(path: &Path) -> Vec<SimpleBezierPath> {
+    let beziers = contours(path)
+        .into_iter()
+        .map(|contour| {
+            let start = contour.segments[0].from;
+            let mut curves = contour
+                .segments
+                .iter()
+                .map(|s| (coord(s.ctrl1), coord(s.ctrl2), coord(s.to)))
+                .collect::<Vec<_>>();
+            let end = contour.segments[contour.segments.len() - 1].to;
+            if end != start {
+                let s = line(end, start);
+                curves.push((coord(s.ctrl1), coord(s.ctrl2), coord(s.to)));
+            }
+            (coord(start), curves)
+        })
+        .collect::<Vec<SimpleBezierPath>>();
+    path_remove_interior_points(&beziers, ACCURACY)
+}
+
+fn from_beziers(paths: &[SimpleBezierPath]) -> Path {
+    let point = |c: &Coord2| point(c.0 as f32, c.1 as f32);
+    let contours = paths
+        .iter()
+        .map(|(start, curves)| {
+            let mut from = point(start);
+            let segments = curves
+                .iter()
+                .map(|(ctrl1, ctrl2, to)| {
+                    let segment = CubicBezierSegment {
+                        from,
+                        ctrl1: point(ctrl1),
+                        ctrl2: point(ctrl2),
+                        to: point(to),
+                    };
+                    from = segment.to;
+                    segment
+                })
+                .collect();
+            Contour {
+                segments,
+                closed: true,
+            }
+        })
+        .collect::<Vec<_>>();
+    build(&contours)
+}
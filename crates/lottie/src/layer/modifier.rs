@@ -0,0 +1,614 @@
+use lyon_path::geom::euclid::approxeq::ApproxEq;
+use lyon_path::geom::{CubicBezierSegment, QuadraticBezierSegment};
+use lyon_path::math::{Point, Vector};
+use lyon_path::{Event, Path};
+
+use crate::model::*;
+
+/// Distance of the handles of a rounded corner from its ends, relative to the
+/// distance to the original corner. Same as lottie-web
+const ROUND_CORNER: f32 = 0.5519;
+/// Number of pieces each segment is split into before being twisted
+const TWIST_STEPS: usize = 8;
+/// Number of pieces each curve is split into before being offset
+const OFFSET_STEPS: usize = 4;
+
+/// Changes the geometry generated by a
+/// [PathFactory](crate::layer::shape::PathFactory) at a certain `frame`.
+/// Modifiers apply to the shapes above them in a group, in the order they
+/// appear
+pub trait PathModifier {
+    fn modify(&self, path: &Path, frame: f32) -> Path;
+    /// Whether the geometry changes between frames for a fixed path
+    fn is_animated(&self) -> bool;
+}
+
+impl PathModifier for Shape {
+    fn modify(&self, path: &Path, frame: f32) -> Path {
+        match self {
+            Shape::RoundedCorners(r) => r.modify(path, frame),
+            Shape::PuckerBloat(p) => p.modify(path, frame),
+            Shape::Twist(t) => t.modify(path, frame),
+            Shape::ZigZag(z) => z.modify(path, frame),
+            Shape::OffsetPath(o) => o.modify(path, frame),
+            _ => path.clone(),
+        }
+    }
+
+    fn is_animated(&self) -> bool {
+        match self {
+            Shape::RoundedCorners(r) => r.is_animated(),
+            Shape::PuckerBloat(p) => p.is_animated(),
+            Shape::Twist(t) => t.is_animated(),
+            Shape::ZigZag(z) => z.is_animated(),
+            Shape::OffsetPath(o) => o.is_animated(),
+            _ => false,
+        }
+    }
+}
+
+impl PathModifier for RoundedCorners {
+    fn modify(&self, path: &Path, frame: f32) -> Path {
+        let radius = self.radius.value(frame);
+        if radius <= 0.0 {
+            return path.clone();
+        }
+        let contours = contours(path)
+            .into_iter()
+            .map(|contour| round_corners(&contour, radius))
+            .collect::<Vec<_>>();
+        build(&contours)
+    }
+
+    fn is_animated(&self) -> bool {
+        self.radius.is_animated()
+    }
+}
+
+impl PathModifier for PuckerBloat {
+    fn modify(&self, path: &Path, frame: f32) -> Path {
+        let amount = self.amount.value(frame) / 100.0;
+        if amount == 0.0 {
+            return path.clone();
+        }
+        let contours = contours(path)
+            .into_iter()
+            .map(|contour| {
+                let vertices = contour.vertices();
+                let center = vertices
+                    .iter()
+                    .fold(Vector::zero(), |sum, v| sum + v.to_vector())
+                    / vertices.len() as f32;
+                let center = center.to_point();
+                // Vertices move towards the center, while handles move away
+                let vertex = |p: Point| p + (center - p) * amount;
+                let handle = |p: Point| p - (center - p) * amount;
+                Contour {
+                    segments: contour
+                        .segments
+                        .iter()
+                        .map(|s| CubicBezierSegment {
+                            from: vertex(s.from),
+                            ctrl1: handle(s.ctrl1),
+                            ctrl2: handle(s.ctrl2),
+                            to: vertex(s.to),
+                        })
+                        .collect(),
+                    closed: contour.closed,
+                }
+            })
+            .collect::<Vec<_>>();
+        build(&contours)
+    }
+
+    fn is_animated(&self) -> bool {
+        self.amount.is_animated()
+    }
+}
+
+impl PathModifier for Twist {
+    fn modify(&self, path: &Path, frame: f32) -> Path {
+        let angle = self.angle.value(frame).to_radians();
+        if angle == 0.0 {
+            return path.clone();
+        }
+        let center = self.center.value(frame).to_point();
+        let mut contours = contours(path)
+            .into_iter()
+            .map(|contour| Contour {
+                segments: contour
+                    .segments
+                    .iter()
+                    .flat_map(|s| {
+                        (0..TWIST_STEPS).map(move |i| {
+                            let step = 1.0 / TWIST_STEPS as f32;
+                            s.split_range(i as f32 * step..(i + 1) as f32 * step)
+                        })
+                    })
+                    .collect(),
+                closed: contour.closed,
+            })
+            .collect::<Vec<_>>();
+        let radius = contours
+            .iter()
+            .flat_map(|c| c.segments.iter())
+            .flat_map(|s| [s.from, s.ctrl1, s.ctrl2, s.to])
+            .map(|p| (p - center).length())
+            .fold(0.0, f32::max);
+        if radius == 0.0 {
+            return path.clone();
+        }
+        // Points rotate less the further they are from the center
+        let twist = |p: Point| {
+            let offset = p - center;
+            let (sin, cos) = (angle * (1.0 - offset.length() / radius)).sin_cos();
+            center
+                + Vector::new(
+                    offset.x * cos - offset.y * sin,
+                    offset.x * sin + offset.y * cos,
+                )
+        };
+        for contour in &mut contours {
+            for s in &mut contour.segments {
+                *s = CubicBezierSegment {
+                    from: twist(s.from),
+                    ctrl1: twist(s.ctrl1),
+                    ctrl2: twist(s.ctrl2),
+                    to: twist(s.to),
+                };
+            }
+        }
+        build(&contours)
+    }
+
+    fn is_animated(&self) -> bool {
+        self.angle.is_animated() || self.center.is_animated()
+    }
+}
+
+impl PathModifier for ZigZag {
+    fn modify(&self, path: &Path, frame: f32) -> Path {
+        let amplitude = self.amplitude.value(frame);
+        let frequency = self.frequency.value(frame).max(0.0).floor() as usize;
+        let smooth = self.point_type.value(frame).round() as i32 == 2;
+        if amplitude == 0.0 {
+            return path.clone();
+        }
+        let contours = contours(path)
+            .into_iter()
+            .map(|contour| zig_zag(&contour, amplitude, frequency, smooth))
+            .collect::<Vec<_>>();
+        build(&contours)
+    }
+
+    fn is_animated(&self) -> bool {
+        self.frequency.is_animated()
+            || self.amplitude.is_animated()
+            || self.point_type.is_animated()
+    }
+}
+
+impl PathModifier for OffsetPath {
+    fn modify(&self, path: &Path, frame: f32) -> Path {
+        let amount = self.amount.value(frame);
+        if amount == 0.0 {
+            return path.clone();
+        }
+        let miter_limit = self.miter_limit.value(frame);
+        let contours = contours(path)
+            .into_iter()
+            .map(|contour| offset(&contour, amount, self.line_join, miter_limit))
+            .collect::<Vec<_>>();
+        build(&contours)
+    }
+
+    fn is_animated(&self) -> bool {
+        self.amount.is_animated() || self.miter_limit.is_animated()
+    }
+}
+
+/// A subpath made of cubic segments, lines have their control points on
+/// their ends
+#[derive(Debug, Clone, Default)]
+pub(super) struct Contour {
+    pub(super) segments: Vec<CubicBezierSegment<f32>>,
+    pub(super) closed: bool,
+}
+
+impl Contour {
+    fn vertices(&self) -> Vec<Point> {
+        let mut result = self.segments.iter().map(|s| s.from).collect::<Vec<_>>();
+        if !self.closed {
+            result.extend(self.segments.last().map(|s| s.to));
+        }
+        result
+    }
+
+    /// Segment ending at the start of segment `index`
+    fn previous(&self, index: usize) -> Option<&CubicBezierSegment<f32>> {
+        if index > 0 {
+            self.segments.get(index - 1)
+        } else if self.closed {
+            self.segments.last()
+        } else {
+            None
+        }
+    }
+
+    /// Segment starting at the end of segment `index`
+    fn next(&self, index: usize) -> Option<&CubicBezierSegment<f32>> {
+        if index + 1 < self.segments.len() {
+            self.segments.get(index + 1)
+        } else if self.closed {
+            self.segments.first()
+        } else {
+            None
+        }
+    }
+}
+
+pub(super) fn line(from: Point, to: Point) -> CubicBezierSegment<f32> {
+    CubicBezierSegment {
+        from,
+        ctrl1: from,
+        ctrl2: to,
+        to,
+    }
+}
+
+fn is_line(s: &CubicBezierSegment<f32>) -> bool {
+    s.ctrl1.approx_eq(&s.from) && s.ctrl2.approx_eq(&s.to)
+}
+
+pub(super) fn contours(path: &Path) -> Vec<Contour> {
+    let mut result = vec![];
+    let mut current = Contour::default();
+    for event in path.iter() {
+        match event {
+            Event::Begin { .. } => current = Contour::default(),
+            Event::Line { from, to } => current.segments.push(line(from, to)),
+            Event::Quadratic { from, ctrl, to } => current
+                .segments
+                .push(QuadraticBezierSegment { from, ctrl, to }.to_cubic()),
+            Event::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => current.segments.push(CubicBezierSegment {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            }),
+            Event::End { last, first, close } => {
+                if close && !last.approx_eq(&first) {
+                    current.segments.push(line(last, first));
+                }
+                current.closed = close;
+                if !current.segments.is_empty() {
+                    result.push(std::mem::take(&mut current));
+                }
+            }
+        }
+    }
+    result
+}
+
+pub(super) fn build(contours: &[Contour]) -> Path {
+    let mut builder = Path::builder();
+    for contour in contours {
+        let first = match contour.segments.first() {
+            Some(first) => first,
+            None => continue,
+        };
+        builder.begin(first.from);
+        for s in &contour.segments {
+            if is_line(s) {
+                builder.line_to(s.to);
+            } else {
+                builder.cubic_bezier_to(s.ctrl1, s.ctrl2, s.to);
+            }
+        }
+        builder.end(contour.closed);
+    }
+    builder.build()
+}
+
+/// Unit tangent of `s` at `t`, falling back to the chord where the curve has
+/// no direction, like lines at their ends
+fn tangent(s: &CubicBezierSegment<f32>, t: f32) -> Vector {
+    let d = s.derivative(t);
+    if d.square_length() > 1e-6 {
+        return d.normalize();
+    }
+    let chord = s.to - s.from;
+    if chord.square_length() > 1e-6 {
+        chord.normalize()
+    } else {
+        Vector::zero()
+    }
+}
+
+fn point(s: &CubicBezierSegment<f32>, t: f32) -> Point {
+    if is_line(s) {
+        s.from.lerp(s.to, t)
+    } else {
+        s.sample(t)
+    }
+}
+
+/// Normal pointing to the right of `tangent`, which is outwards for
+/// clockwise contours
+fn normal(tangent: Vector) -> Vector {
+    Vector::new(tangent.y, -tangent.x)
+}
+
+/// Replace sharp corners, where both adjacent handles are empty, with an arc
+/// cut from both sides of the corner
+fn round_corners(contour: &Contour, radius: f32) -> Contour {
+    let segments = &contour.segments;
+    // Points replacing the start of each segment
+    let cuts = (0..segments.len())
+        .map(|index| {
+            let current = &segments[index];
+            let previous = contour.previous(index)?;
+            let corner = current.from;
+            if !previous.ctrl2.approx_eq(&corner) || !current.ctrl1.approx_eq(&corner) {
+                return None;
+            }
+            let before = previous.from - corner;
+            let after = current.to - corner;
+            let distance = radius.min(before.length() / 2.0).min(after.length() / 2.0);
+            if distance <= 0.0 {
+                return None;
+            }
+            Some((
+                corner + before.normalize() * distance,
+                corner + after.normalize() * distance,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let mut result = vec![];
+    for (index, segment) in segments.iter().enumerate() {
+        let mut segment = *segment;
+        if let Some((_, start)) = cuts[index] {
+            segment.from = start;
+            segment.ctrl1 = start;
+        }
+        let end = contour
+            .next(index)
+            .and_then(|_| cuts[(index + 1) % segments.len()]);
+        let corner = segment.to;
+        if let Some((end, _)) = end {
+            segment.to = end;
+            segment.ctrl2 = end;
+        }
+        result.push(segment);
+        if let Some((a, b)) = end {
+            result.push(CubicBezierSegment {
+                from: a,
+                ctrl1: a + (corner - a) * ROUND_CORNER,
+                ctrl2: b + (corner - b) * ROUND_CORNER,
+                to: b,
+            });
+        }
+    }
+    Contour {
+        segments: result,
+        closed: contour.closed,
+    }
+}
+
+/// A vertex of a zig zag, with its handles relative to the vertex
+struct Ridge {
+    point: Point,
+    handle_in: Vector,
+    handle_out: Vector,
+}
+
+fn ridge(point: Point, tangent: Vector, direction: f32, amplitude: f32, handle: f32) -> Ridge {
+    Ridge {
+        point: point + normal(tangent) * direction * amplitude,
+        handle_in: -tangent * handle,
+        handle_out: tangent * handle,
+    }
+}
+
+/// Add `frequency` ridges to every segment, alternating sides. Vertices are
+/// moved along the bisector of their segments
+fn zig_zag(contour: &Contour, amplitude: f32, frequency: usize, smooth: bool) -> Contour {
+    let handle = |s: &CubicBezierSegment<f32>| {
+        if smooth {
+            (s.to - s.from).length() / (frequency as f32 + 1.0) / 2.0
+        } else {
+            0.0
+        }
+    };
+    let corner = |previous: Option<&CubicBezierSegment<f32>>,
+                  next: Option<&CubicBezierSegment<f32>>,
+                  direction: f32| {
+        let incoming = previous.map(|s| tangent(s, 1.0)).unwrap_or_default();
+        let outgoing = next.map(|s| tangent(s, 0.0)).unwrap_or_default();
+        let bisector = (incoming + outgoing).try_normalize().unwrap_or(outgoing);
+        let point = next.map(|s| s.from).or(previous.map(|s| s.to)).unwrap();
+        let length = previous.or(next).map(&handle).unwrap_or_default();
+        ridge(point, bisector, direction, amplitude, length)
+    };
+    let mut ridges = vec![];
+    let mut direction = -1.0;
+    ridges.push(corner(
+        contour.previous(0),
+        contour.segments.first(),
+        direction,
+    ));
+    for (index, segment) in contour.segments.iter().enumerate() {
+        for i in 0..frequency {
+            direction = -direction;
+            let t = (i + 1) as f32 / (frequency + 1) as f32;
+            ridges.push(ridge(
+                point(segment, t),
+                tangent(segment, t),
+                direction,
+                amplitude,
+                handle(segment),
+            ));
+        }
+        direction = -direction;
+        if index + 1 < contour.segments.len() || !contour.closed {
+            ridges.push(corner(Some(segment), contour.next(index), direction));
+        }
+    }
+    let count = ridges.len();
+    let pairs = if contour.closed { count } else { count - 1 };
+    Contour {
+        segments: (0..pairs)
+            .map(|index| {
+                let a = &ridges[index];
+                let b = &ridges[(index + 1) % count];
+                CubicBezierSegment {
+                    from: a.point,
+                    ctrl1: a.point + a.handle_out,
+                    ctrl2: b.point + b.handle_in,
+                    to: b.point,
+                }
+            })
+            .collect(),
+        closed: contour.closed,
+    }
+}
+
+/// Twice the signed area of the control polygon, positive for clockwise
+/// contours in lottie's y-down space
+fn signed_area(contour: &Contour) -> f32 {
+    let points = contour
+        .segments
+        .iter()
+        .flat_map(|s| [s.from, s.ctrl1, s.ctrl2])
+        .collect::<Vec<_>>();
+    (0..points.len())
+        .map(|i| {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            a.x * b.y - b.x * a.y
+        })
+        .sum()
+}
+
+fn offset_segment(s: &CubicBezierSegment<f32>, amount: f32) -> CubicBezierSegment<f32> {
+    let start = normal(tangent(s, 0.0)) * amount;
+    let end = normal(tangent(s, 1.0)) * amount;
+    CubicBezierSegment {
+        from: s.from + start,
+        ctrl1: s.ctrl1 + start,
+        ctrl2: s.ctrl2 + end,
+        to: s.to + end,
+    }
+}
+
+/// Distance along the end tangent of `a` where it meets the start tangent of
+/// `b`, negative on the inner side of corners
+fn meeting(a: &CubicBezierSegment<f32>, b: &CubicBezierSegment<f32>) -> Option<f32> {
+    let incoming = tangent(a, 1.0);
+    let outgoing = tangent(b, 0.0);
+    let cross = incoming.cross(outgoing);
+    if cross.abs() < 1e-6 {
+        return None;
+    }
+    Some((b.from - a.to).cross(outgoing) / cross)
+}
+
+/// Move every segment outwards by `amount`, gaps at corners are filled with
+/// `line_join`
+fn offset(contour: &Contour, amount: f32, line_join: LineJoin, miter_limit: f32) -> Contour {
+    let amount = if contour.closed && signed_area(contour) < 0.0 {
+        -amount
+    } else {
+        amount
+    };
+    // Offset pieces with the corner they end at
+    let mut pieces = contour
+        .segments
+        .iter()
+        .flat_map(|s| {
+            let steps = if is_line(s) { 1 } else { OFFSET_STEPS };
+            (0..steps).map(move |i| {
+                let step = 1.0 / steps as f32;
+                let piece = s.split_range(i as f32 * step..(i + 1) as f32 * step);
+                (offset_segment(&piece, amount), piece.to)
+            })
+        })
+        .collect::<Vec<_>>();
+    let joints = if contour.closed {
+        pieces.len()
+    } else {
+        pieces.len() - 1
+    };
+    // Pieces overlap on the inner side of corners, cut them where they meet
+    for index in 0..joints {
+        let next = (index + 1) % pieces.len();
+        let (a, b) = (pieces[index].0, pieces[next].0);
+        let distance = match meeting(&a, &b) {
+            Some(distance) if distance < 0.0 => distance,
+            _ => continue,
+        };
+        let delta = tangent(&a, 1.0) * distance;
+        if delta.length() > (a.to - a.from).length() || delta.length() > (b.to - b.from).length() {
+            continue;
+        }
+        let meet = a.to + delta;
+        let piece = &mut pieces[index].0;
+        piece.ctrl2 += meet - piece.to;
+        piece.to = meet;
+        let piece = &mut pieces[next].0;
+        piece.ctrl1 += meet - piece.from;
+        piece.from = meet;
+    }
+    let mut result = vec![];
+    for (index, (piece, corner)) in pieces.iter().enumerate() {
+        result.push(*piece);
+        if index >= joints {
+            break;
+        }
+        let (next, _) = &pieces[(index + 1) % pieces.len()];
+        let (start, end) = (piece.to, next.from);
+        if start.approx_eq(&end) {
+            continue;
+        }
+        let incoming = tangent(piece, 1.0);
+        let outgoing = tangent(next, 0.0);
+        let distance = match meeting(piece, next) {
+            Some(distance) if distance > 0.0 => distance,
+            _ => {
+                result.push(line(start, end));
+                continue;
+            }
+        };
+        match line_join {
+            LineJoin::Miter => {
+                let tip = start + incoming * distance;
+                if (tip - *corner).length() <= miter_limit * amount.abs() {
+                    result.push(line(start, tip));
+                    result.push(line(tip, end));
+                } else {
+                    result.push(line(start, end));
+                }
+            }
+            LineJoin::Round => {
+                let angle = incoming.angle_to(outgoing).radians.abs();
+                let handle = 4.0 / 3.0 * (angle / 4.0).tan() * amount.abs();
+                result.push(CubicBezierSegment {
+                    from: start,
+                    ctrl1: start + incoming * handle,
+                    ctrl2: end - outgoing * handle,
+                    to: end,
+                });
+            }
+            LineJoin::Bevel => result.push(line(start, end)),
+        }
+    }
+    Contour {
+        segments: result,
+        closed: contour.closed,
+    }
+}
@@ -1,7 +1,8 @@
+use crate::layer::modifier::PathModifier;
 use crate::model::*;
 use lyon_path::geom::euclid::approxeq::ApproxEq;
 use lyon_path::geom::euclid::vec2;
-use lyon_path::math::Angle;
+use lyon_path::math::{Angle, Point};
 use lyon_path::path::{Builder, Path};
 use lyon_path::Winding;
 
@@ -31,6 +32,7 @@ impl<'a> Iterator for StyledShapeIter {
         if self.shape_index >= self.shapes.len() {
             return None;
         }
+        let shape_index = self.shape_index;
         let shape = self.shapes[self.shape_index as usize].clone();
         let mut fill = None;
         let mut transform = Transform::default();
@@ -52,7 +54,7 @@ impl<'a> Iterator for StyledShapeIter {
                 match &shape.shape {
                     Shape::Fill(f) if fill.is_none() => fill = Some(AnyFill::Solid(f.clone())),
                     Shape::GradientFill(f) if fill.is_none() => {
-                        fill = Some(AnyFill::Gradient(f.clone()))
+                        fill = Some(AnyFill::Gradient(Box::new(f.clone())))
                     }
                     Shape::Stroke(s) => {
                         find_stroke = true;
@@ -64,7 +66,7 @@ impl<'a> Iterator for StyledShapeIter {
                     Shape::GradientStroke(s) => {
                         find_stroke = true;
                         if index > self.stroke_index && stroke.is_none() {
-                            stroke = Some(AnyStroke::Gradient(s.clone()));
+                            stroke = Some(AnyStroke::Gradient(Box::new(s.clone())));
                             target_stroke_index = index;
                         }
                     }
@@ -93,22 +95,25 @@ impl<'a> Iterator for StyledShapeIter {
             self.shape_index += 1;
             self.stroke_index = self.shape_index as usize;
         }
-        // collect trim info
-        let mut trims = vec![];
-        for (_index, shape) in self.shapes.iter().rev().enumerate() {
-            if let Shape::Trim(trim) = &shape.shape {
-                match trim.multiple_shape {
-                    TrimMultipleShape::Individually => trims.push(TrimInfo {
-                        trim: trim.clone(),
-                        shapes: vec![shape.shape.clone()],
-                    }),
-                    TrimMultipleShape::Simultaneously => {
-                        // TODO: implement this
-                        continue;
-                    }
-                }
-            }
-        }
+        // Trims below the shape apply to it, from the top one
+        let trims = self.shapes[shape_index + 1..]
+            .iter()
+            .enumerate()
+            .filter(|(_, shape)| !shape.hidden)
+            .filter_map(|(index, shape)| match &shape.shape {
+                Shape::Trim(trim) => Some(TrimInfo {
+                    trim: trim.clone(),
+                    index: shape_index + 1 + index,
+                }),
+                _ => None,
+            })
+            .collect();
+        // Modifiers below the shape apply to it, in order
+        let modifiers = self.shapes[shape_index + 1..]
+            .iter()
+            .filter(|shape| shape.shape.is_modifier() && !shape.hidden)
+            .map(|shape| shape.shape.clone())
+            .collect();
         Some(StyledShape {
             shape,
             styles: vec![],
@@ -116,6 +121,7 @@ impl<'a> Iterator for StyledShapeIter {
             fill,
             transform,
             trims,
+            modifiers,
         })
     }
 }
@@ -129,14 +135,109 @@ impl StyledShapeIterator for ShapeGroup {
         StyledShapeIter {
             shape_index: 0,
             stroke_index: 0,
-            shapes: self.shapes.clone(),
+            shapes: expand_merges(expand_repeaters(&self.shapes)),
+        }
+    }
+}
+
+/// Replace each [Shape::Repeater] with copies of the items above it, each
+/// copy is a group transformed by a [RepeaterCopy]. Styles right below a
+/// repeater are applied to its copies as well
+pub(crate) fn expand_repeaters(shapes: &[ShapeLayer]) -> Vec<ShapeLayer> {
+    let mut result = vec![];
+    for (index, layer) in shapes.iter().enumerate() {
+        let (copies, offset, composite, transform) = match &layer.shape {
+            Shape::Repeater {
+                copies,
+                offset,
+                composite,
+                transform,
+            } => (copies, offset, composite, transform),
+            _ => {
+                result.push(layer.clone());
+                continue;
+            }
+        };
+        if layer.hidden {
+            continue;
+        }
+        let mut content = std::mem::take(&mut result);
+        content.extend(
+            shapes[index + 1..]
+                .iter()
+                .take_while(|shape| shape.shape.is_style())
+                .cloned(),
+        );
+        let max_copies = copies
+            .keyframes
+            .iter()
+            .map(|k| k.start_value.max(k.end_value))
+            .fold(0.0, f32::max)
+            .ceil() as u32;
+        result = (0..max_copies)
+            .map(|index| {
+                let mut shapes = content.clone();
+                shapes.push(ShapeLayer {
+                    name: None,
+                    hidden: false,
+                    shape: Shape::Transform(Transform {
+                        repeater: Some(Box::new(RepeaterCopy {
+                            index,
+                            copies: copies.clone(),
+                            offset: offset.clone(),
+                            transform: transform.clone(),
+                        })),
+                        ..Default::default()
+                    }),
+                });
+                ShapeLayer {
+                    name: layer.name.clone(),
+                    hidden: false,
+                    shape: Shape::Group { shapes },
+                }
+            })
+            .collect();
+        // The first shape in a group is drawn on top
+        if let Composite::Above = composite {
+            result.reverse();
+        }
+    }
+    result
+}
+
+/// Move the shapes and groups above each [Shape::Merge] into it, so they are
+/// drawn as the single path it generates. Styles above a merge are dropped
+pub(crate) fn expand_merges(shapes: Vec<ShapeLayer>) -> Vec<ShapeLayer> {
+    let mut result: Vec<ShapeLayer> = vec![];
+    for layer in shapes {
+        let merge = match layer.shape {
+            Shape::Merge(merge) => merge,
+            shape => {
+                result.push(ShapeLayer { shape, ..layer });
+                continue;
+            }
+        };
+        if layer.hidden {
+            continue;
         }
+        let (shapes, kept) = std::mem::take(&mut result)
+            .into_iter()
+            .filter(|shape| !shape.shape.is_style())
+            .partition(|shape| {
+                shape.shape.is_shape() || shape.shape.is_group() || shape.shape.is_modifier()
+            });
+        result = kept;
+        result.push(ShapeLayer {
+            shape: Shape::Merge(MergePaths { shapes, ..merge }),
+            ..layer
+        });
     }
+    result
 }
 
 pub enum AnyFill {
     Solid(Fill),
-    Gradient(GradientFill),
+    Gradient(Box<GradientFill>),
 }
 
 impl AnyFill {
@@ -150,7 +251,7 @@ impl AnyFill {
 
 pub enum AnyStroke {
     Solid(Stroke),
-    Gradient(GradientStroke),
+    Gradient(Box<GradientStroke>),
 }
 
 impl AnyStroke {
@@ -175,18 +276,70 @@ impl AnyStroke {
         }
     }
 
+    pub fn miter_limit(&self) -> f32 {
+        match &self {
+            AnyStroke::Solid(s) => s.miter_limit,
+            AnyStroke::Gradient(g) => g.miter_limit,
+        }
+    }
+
     pub fn opacity(&self) -> &Animated<f32> {
         match &self {
             AnyStroke::Solid(s) => &s.opacity,
             AnyStroke::Gradient(g) => &g.opacity,
         }
     }
+
+    pub fn dashes(&self) -> &[StrokeDash] {
+        match &self {
+            AnyStroke::Solid(s) => &s.dashes,
+            AnyStroke::Gradient(g) => &g.dashes,
+        }
+    }
+
+    pub fn dash_pattern(&self, frame: f32) -> Option<DashPattern> {
+        DashPattern::new(self.dashes(), frame)
+    }
+}
+
+/// Dash array and offset of a stroke at a certain frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DashPattern {
+    /// Alternating dash and gap lengths, always of an even length
+    pub array: Vec<f32>,
+    pub offset: f32,
+}
+
+impl DashPattern {
+    /// Returns `None` if `dashes` draws a solid line
+    pub fn new(dashes: &[StrokeDash], frame: f32) -> Option<Self> {
+        let mut array = vec![];
+        let mut offset = 0.0;
+        for dash in dashes {
+            let length = dash.length.value(frame);
+            match dash.ty {
+                StrokeDashType::Dash | StrokeDashType::Gap => array.push(length.max(0.0)),
+                StrokeDashType::Offset => offset += length,
+            }
+        }
+        if array.iter().all(|length| *length <= 0.0) {
+            return None;
+        }
+        // Same as SVG, an odd number of lengths is repeated to get an even
+        // one
+        if array.len() % 2 == 1 {
+            array.extend_from_within(..);
+        }
+        Some(DashPattern { array, offset })
+    }
 }
 
 #[derive(Clone)]
 pub struct TrimInfo {
     pub trim: Trim,
-    pub shapes: Vec<Shape>,
+    /// Position of the trim in its group. All shapes sharing a
+    /// [TrimMultipleShape::Simultaneously] trim are trimmed as a single path
+    pub index: usize,
 }
 
 pub struct StyledShape {
@@ -196,6 +349,25 @@ pub struct StyledShape {
     pub transform: Transform,
     pub styles: Vec<ShapeLayer>,
     pub trims: Vec<TrimInfo>,
+    /// Modifiers of this shape, see [PathModifier]
+    pub modifiers: Vec<Shape>,
+}
+
+impl StyledShape {
+    /// Apply [StyledShape::modifiers] to a path generated by this shape
+    pub fn modify_path(&self, path: Path, frame: f32) -> Path {
+        self.modifiers
+            .iter()
+            .fold(path, |path, modifier| modifier.modify(&path, frame))
+    }
+
+    /// Apply [StyledShape::trims] to a path generated by this shape, each
+    /// trim treats the path as the only shape it applies to
+    pub fn trim_path(&self, path: Path, frame: f32) -> Path {
+        self.trims
+            .iter()
+            .fold(path, |path, trim| trim.trim.trim_path(&path, frame))
+    }
 }
 
 impl Shape {
@@ -214,7 +386,19 @@ impl Shape {
             Shape::Rectangle { .. }
             | Shape::Ellipse { .. }
             | Shape::PolyStar { .. }
-            | Shape::Path { .. } => true,
+            | Shape::Path { .. }
+            | Shape::Merge(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_modifier(&self) -> bool {
+        match &self {
+            Shape::RoundedCorners(_)
+            | Shape::PuckerBloat(_)
+            | Shape::Twist(_)
+            | Shape::ZigZag(_)
+            | Shape::OffsetPath(_) => true,
             _ => false,
         }
     }
@@ -377,24 +561,62 @@ impl PathFactory for PolyStar {
     }
 }
 
+/// Distance from the ends of a rounded corner to its control points, relative
+/// to the radius. Same value as lottie-web
+const ROUND_CORNER: f32 = 0.5519;
+
+/// Rectangles start on the right edge below the top right corner, then go down
+/// when clockwise or round the top right corner when counter-clockwise, same as
+/// lottie-web
 impl PathFactory for Rectangle {
     fn path(&self, frame: f32) -> Path {
         let mut builder = Builder::new();
         let center = self.position.value(frame).to_point();
         let size = self.size.value(frame) / 2.0;
-        let mut pts = vec![
-            center - size,
-            center + vec2(size.x, -size.y),
-            center + size,
-            center + vec2(-size.x, size.y),
-        ];
-        if self.direction == ShapeDirection::CounterClockwise {
-            pts.reverse();
+        let radius = self
+            .radius
+            .value(frame)
+            .min(size.x.abs())
+            .min(size.y.abs())
+            .max(0.0);
+        let top_right = center + vec2(size.x, -size.y);
+        let bottom_right = center + size;
+        let bottom_left = center + vec2(-size.x, size.y);
+        let top_left = center - size;
+        // Point at `radius` from `corner` towards `to`
+        let towards = |corner: Point, to: Point| {
+            let direction = (to - corner).try_normalize().unwrap_or_default();
+            corner + direction * radius
+        };
+        let (start, corners) = match self.direction {
+            ShapeDirection::Clockwise => (
+                towards(top_right, bottom_right),
+                [bottom_right, bottom_left, top_left, top_right],
+            ),
+            ShapeDirection::CounterClockwise => (
+                towards(top_right, bottom_right),
+                [top_right, top_left, bottom_left, bottom_right],
+            ),
+        };
+        builder.begin(start);
+        let mut current = start;
+        for (index, corner) in corners.iter().enumerate() {
+            let prev = corners[(index + 3) % 4];
+            let next = corners[(index + 1) % 4];
+            let (from, to) = (towards(*corner, prev), towards(*corner, next));
+            // The path is closed back to its start point
+            if from != current && from != start {
+                builder.line_to(from);
+            }
+            if radius > 0.0 {
+                builder.cubic_bezier_to(
+                    from.lerp(*corner, ROUND_CORNER),
+                    to.lerp(*corner, ROUND_CORNER),
+                    to,
+                );
+            }
+            current = to;
         }
-        builder.begin(pts[0]);
-        builder.line_to(pts[1]);
-        builder.line_to(pts[2]);
-        builder.line_to(pts[3]);
         builder.end(true);
         builder.build()
     }
@@ -1,8 +1,8 @@
 use crate::model::*;
 
 use crate::font::FontDB;
-use crate::prelude::{Id, MaskHierarchy};
-use crate::Error;
+use crate::prelude::{Id, LayerMask, MaskHierarchy};
+use crate::{AssetResolver, Error};
 
 use super::frame::{FrameTransform, FrameTransformHierarchy};
 use super::hierarchy::TransformHierarchy;
@@ -37,6 +37,14 @@ impl RenderableContent {
             matte_mode: layer.matte_mode,
             mask_hierarchy: MaskHierarchy::default(),
             blend_mode: layer.blend_mode.unwrap_or(BlendMode::Normal),
+            effects: layer
+                .effects
+                .iter()
+                .filter(|e| e.enabled)
+                .cloned()
+                .collect(),
+            masks: layer.masks_properties.clone(),
+            styles: layer.styles.clone(),
         }
     }
 }
@@ -49,10 +57,6 @@ pub enum TargetRef {
 
 pub(crate) enum ContentInfo {
     Simple(RenderableContent),
-    ContentWithMasks {
-        content: RenderableContent,
-        masks: Vec<(RenderableContent, MatteMode)>,
-    },
     TextKeyframes(Vec<TextKeyframe>),
 }
 
@@ -85,93 +89,103 @@ pub struct StagedLayer {
     pub matte_mode: Option<MatteMode>,
     pub mask_hierarchy: MaskHierarchy,
     pub blend_mode: BlendMode,
+    /// Enabled effects of the layer, in the order they apply
+    pub effects: Vec<Effect>,
+    /// Masks of the layer, including the ones with [MaskMode::None], whose
+    /// paths effects like [EffectType::Stroke] draw along
+    pub masks: Vec<Mask>,
+    /// Layer styles, applied once effects are
+    pub styles: Vec<LayerStyle>,
 }
 
 impl ContentInfo {
+    /// Mask sources of `layer`, in the order they are combined. Masks with
+    /// [MaskMode::None] have no effect and are skipped
+    pub fn masks(layer: &Layer) -> Vec<(RenderableContent, LayerMask)> {
+        if !layer.has_mask {
+            return vec![];
+        }
+        layer
+            .masks_properties
+            .iter()
+            .filter(|mask| mask.mode != MaskMode::None)
+            .enumerate()
+            .map(|(index, mask)| {
+                let mut shapes = vec![ShapeLayer {
+                    name: Some(mask.name.clone()),
+                    hidden: false,
+                    shape: Shape::Path {
+                        d: mask.points.clone(),
+                        text_range: None,
+                    },
+                }];
+                if let Some(expand) = mask.expand.clone() {
+                    shapes.push(ShapeLayer {
+                        name: None,
+                        hidden: false,
+                        shape: Shape::OffsetPath(OffsetPath {
+                            amount: expand,
+                            line_join: LineJoin::Miter,
+                            miter_limit: Animated::from_value(4.0),
+                        }),
+                    });
+                }
+                shapes.push(ShapeLayer {
+                    name: None,
+                    hidden: false,
+                    shape: Shape::Fill(Fill {
+                        opacity: mask.opacity.clone(),
+                        color: Animated::from_value(Rgb::new_u8(0, 0, 0)),
+                        fill_rule: FillRule::EvenOdd,
+                    }),
+                });
+                shapes.push(ShapeLayer {
+                    name: None,
+                    hidden: false,
+                    shape: Shape::Transform(Transform::default()),
+                });
+                let info = LayerMask {
+                    mode: mask.mode,
+                    inverted: mask.inverted,
+                    index,
+                };
+                (RenderableContent::Shape(ShapeGroup { shapes }), info)
+            })
+            .collect()
+    }
+
     pub fn from_layer(
         layer: Layer,
         model: &Model,
         fontdb: &impl FontDB,
-        root_path: &str,
+        resolver: &dyn AssetResolver,
     ) -> Result<ContentInfo, Error> {
         let content = match layer.content.clone() {
             LayerContent::Shape(shape_group) => {
-                let mut result = vec![];
-                let content = RenderableContent::Shape(shape_group);
-                if layer.has_mask {
-                    for mask in &layer.masks_properties {
-                        let mut opacity = mask.opacity.clone();
-                        for keyframe in &mut opacity.keyframes {
-                            keyframe.start_value /= 100.0;
-                            keyframe.end_value /= 100.0;
-                        }
-                        let content = RenderableContent::Shape(ShapeGroup {
-                            shapes: vec![
-                                ShapeLayer {
-                                    name: Some(mask.name.clone()),
-                                    hidden: false,
-                                    shape: Shape::Path {
-                                        d: mask.points.clone(),
-                                        text_range: None,
-                                    },
-                                },
-                                ShapeLayer {
-                                    name: None,
-                                    hidden: false,
-                                    shape: Shape::Fill(Fill {
-                                        opacity,
-                                        color: Animated {
-                                            animated: false,
-                                            keyframes: vec![KeyFrame::from_value(Rgb::new_u8(
-                                                0, 0, 0,
-                                            ))],
-                                        },
-                                        fill_rule: FillRule::EvenOdd,
-                                    }),
-                                },
-                                ShapeLayer {
-                                    name: None,
-                                    hidden: false,
-                                    shape: Shape::Transform(Transform::default()),
-                                },
-                            ],
-                        });
-                        let matte_mode = match mask.mode {
-                            MaskMode::Add => MatteMode::Alpha,
-                            MaskMode::Subtract => MatteMode::InvertedAlpha,
-                            MaskMode::None => MatteMode::Normal,
-                            _ => unimplemented!(),
-                        };
-                        result.push((content, matte_mode));
-                    }
-                    ContentInfo::ContentWithMasks {
-                        content,
-                        masks: result,
-                    }
-                } else {
-                    ContentInfo::Simple(content)
-                }
+                ContentInfo::Simple(RenderableContent::Shape(shape_group))
             }
             LayerContent::PreCompositionRef(_)
             | LayerContent::Empty
+            | LayerContent::Unknown(_)
             | LayerContent::MediaRef(_) => ContentInfo::Simple(RenderableContent::Group.into()),
-            #[cfg(feature = "text")]
-            LayerContent::Text(text) => match RenderableContent::from_text(&text, model, fontdb) {
-                Ok(t) => ContentInfo::TextKeyframes(
-                    t.keyframes
-                        .into_iter()
-                        .map(|keyframe| TextKeyframe {
-                            content: keyframe.start_value,
-                            start_frame: keyframe.start_frame,
-                            end_frame: keyframe.end_frame,
-                        })
-                        .collect(),
-                ),
-                Err(e) => {
-                    log::warn!("{:?}", e);
-                    ContentInfo::Simple(RenderableContent::Group)
+            LayerContent::Text(text) => {
+                match RenderableContent::from_text(&text, &layer.masks_properties, model, fontdb) {
+                    Ok(t) => ContentInfo::TextKeyframes(
+                        t.keyframes
+                            .into_iter()
+                            .map(|keyframe| TextKeyframe {
+                                content: keyframe.start_value,
+                                start_frame: keyframe.start_frame,
+                                end_frame: keyframe.end_frame,
+                            })
+                            .collect(),
+                    ),
+                    Err(e) => {
+                        log::warn!("{:?}", e);
+                        ContentInfo::Simple(RenderableContent::Group)
+                    }
                 }
-            },
+            }
             LayerContent::SolidColor {
                 color,
                 height,
@@ -195,10 +209,9 @@ impl ContentInfo {
                     },
                 ],
             })),
-            LayerContent::Media(media) => ContentInfo::Simple(RenderableContent::Media(
-                Media::new(media, Some(root_path))?,
-            )),
-            _ => todo!(),
+            LayerContent::Media(media) => {
+                ContentInfo::Simple(RenderableContent::Media(Media::new(media, resolver)?))
+            }
         };
         Ok(content)
     }
@@ -1,324 +1,364 @@
+use std::sync::Arc;
+
 use crate::model::*;
-use fontkit::{Area, Line, Metrics, PathSegment, Span, TextMetrics};
 
-use crate::font::FontDB;
+mod animator;
+mod path;
+
+#[cfg(feature = "expressions")]
+pub(crate) use animator::text_units;
+pub use animator::{TextAnimatorValue, TextFrame};
+pub use path::{MeasuredPath, TextPath};
+
+use crate::font::{FontDB, Glyph};
 use crate::prelude::RenderableContent;
 use crate::Error;
 
-struct GlyphData {
-    c: char,
-    beziers: Vec<Bezier>,
-    offset_x: f32,
-}
-
 impl RenderableContent {
     pub fn from_text(
         text: &TextAnimationData,
+        masks: &[Mask],
         model: &Model,
-        fontdb: &FontDB,
+        fontdb: &impl FontDB,
     ) -> Result<Animated<RenderableContent>, Error> {
+        let path = text
+            .follow_path
+            .mask
+            .and_then(|index| masks.get(index))
+            .map(|mask| (&text.follow_path, mask));
         let keyframes = text
             .document
             .keyframes
             .iter()
             .map(|keyframe| {
-                let parser = TextDocumentParser::new(keyframe, &text.ranges, &model, fontdb)?;
-                let shape = parser.shape_layer()?;
-                let content = RenderableContent::Shape(ShapeGroup {
-                    shapes: vec![shape],
-                });
+                let content = if keyframe.start_value.value.trim().is_empty() {
+                    // Blank documents have nothing to draw
+                    RenderableContent::Group
+                } else {
+                    let parser =
+                        TextDocumentParser::new(keyframe, &text.ranges, path, model, fontdb)?;
+                    RenderableContent::Shape(ShapeGroup {
+                        shapes: vec![parser.shape_layer()],
+                    })
+                };
                 Ok(keyframe.alter_value(content.clone(), content))
             })
             .collect::<Result<Vec<_>, Error>>()?;
         Ok(Animated {
             animated: true,
             keyframes,
+            expression: None,
         })
     }
 }
 
-#[derive(Clone)]
-struct Styles {
-    fill: Rgb,
-    fill_opacity: f32,
+/// Scale of the font size for lowercase letters drawn as small capitals
+const SMALL_CAPS_SCALE: f32 = 0.7;
+/// Ascent of fonts which don't specify it, for a font size of 100
+const DEFAULT_ASCENT: f32 = 75.0;
+
+/// A line of text, with glyphs scaled to their font size
+#[derive(Default)]
+struct TextLine {
+    chars: Vec<char>,
+    glyphs: Vec<Glyph>,
+    /// The line ends its paragraph
+    last: bool,
+}
+
+impl TextLine {
+    fn width(&self, tracking: f32) -> f32 {
+        self.glyphs
+            .iter()
+            .map(|glyph| glyph.advance + tracking)
+            .sum()
+    }
 }
 
 struct TextDocumentParser<'a> {
-    model: &'a Model,
-    fontdb: &'a FontDB,
-    area: Area<Styles, TextMetrics>,
-    lottie_font: &'a Font,
+    lines: Vec<TextLine>,
+    ascent: f32,
     keyframe: &'a KeyFrame<TextDocument>,
     text_ranges: &'a Vec<TextRange>,
+    path: Option<(&'a TextFollowPath, &'a Mask)>,
 }
 
 impl<'a> TextDocumentParser<'a> {
     fn new(
         keyframe: &'a KeyFrame<TextDocument>,
         text_ranges: &'a Vec<TextRange>,
+        path: Option<(&'a TextFollowPath, &'a Mask)>,
         model: &'a Model,
-        fontdb: &'a FontDB,
+        fontdb: &impl FontDB,
     ) -> Result<Self, Error> {
         let doc = &keyframe.start_value;
         let lottie_font = model
             .font(&doc.font_name)
             .ok_or_else(|| Error::FontFamilyNotFound(doc.font_name.clone()))?;
-        let font = fontdb
-            .font(lottie_font)
-            .ok_or_else(|| Error::FontNotLoaded(doc.font_name.clone()))?;
-        font.load()?;
-
-        // parse fill/opacity data
-        let rgb = Rgb::new_u8(doc.fill_color.r, doc.fill_color.g, doc.fill_color.b);
-
-        let opacity = doc.fill_color.a as f32 / 255.0 * 100.0;
-        let styles = Styles {
-            fill: rgb,
-            fill_opacity: opacity,
-        };
-        // parse font data
-        let mut area = Area::new();
-        for line in doc.value.split('\r') {
-            let metrics = font.measure(&line)?;
-            let span = Span {
-                font_key: font.key(),
-                letter_spacing: 0.0,
-                line_height: None,
-                size: doc.size,
-                broke_from_prev: false,
-                metrics,
-                swallow_leading_space: false,
-                additional: styles.clone(),
-            };
-            let line = Line::new(span);
-            area.lines.push(line);
+        let tracking = tracking(doc);
+        let box_width = doc.box_size.first().copied();
+        let mut lines = vec![];
+        // After Effects breaks paragraphs with carriage returns
+        for paragraph in doc.value.split(['\r', '\n', '\u{3}']) {
+            let mut chars = vec![];
+            let mut glyphs = vec![];
+            for (c, scale) in apply_caps(paragraph, doc.caps) {
+                let glyph = fontdb.glyph(lottie_font, c)?;
+                let size = doc.size * scale;
+                chars.push(c);
+                glyphs.push(Glyph {
+                    beziers: glyph
+                        .beziers
+                        .iter()
+                        .map(|bezier| bezier.scale(size))
+                        .collect(),
+                    advance: glyph.advance * size,
+                });
+            }
+            lines.extend(wrap(chars, glyphs, box_width, tracking));
         }
-
         Ok(TextDocumentParser {
-            model,
-            fontdb,
-            area,
-            lottie_font,
+            lines,
+            ascent: lottie_font.ascent.unwrap_or(DEFAULT_ASCENT) * doc.size / 100.0,
             keyframe,
             text_ranges,
+            path,
         })
     }
 
-    fn shape_layer(&self) -> Result<ShapeLayer, Error> {
-        let font = self
-            .fontdb
-            .font(self.lottie_font)
-            .ok_or_else(|| Error::FontNotLoaded(self.lottie_font.name.clone()))?;
-        font.load()?;
-        let units = font.units_per_em() as f32;
+    fn shape_layer(&self) -> ShapeLayer {
         let doc = &self.keyframe.start_value;
-
-        let mut result = vec![];
+        let tracking = tracking(doc);
         let align_factor = match doc.justify {
-            TextJustify::Left => 0.0,
-            TextJustify::Center => -0.5,
-            TextJustify::Right => -1.0,
-            _ => 0.0, // TODO: support other TextJustify options
+            TextJustify::Left | TextJustify::LastLineLeft | TextJustify::LastLineFull => 0.0,
+            TextJustify::Center | TextJustify::LastLineCenter => 0.5,
+            TextJustify::Right | TextJustify::LastLineRight => 1.0,
         };
-        let start_shift_y = -doc.baseline_shift;
-        let mut line_y = 0.0;
+        let line_height = doc.line_height.unwrap_or(doc.size * 1.2);
+        // Box text starts at the top of its box, point text is aligned around
+        // its origin
+        let (left, top, box_width) = match (&doc.box_size[..], &doc.box_position[..]) {
+            ([width, _], [x, y]) => (*x, *y + self.ascent, *width),
+            _ => {
+                let width = self
+                    .lines
+                    .iter()
+                    .map(|line| line.width(tracking))
+                    .fold(0.0, f32::max);
+                (-width * align_factor, 0.0, width)
+            }
+        };
+        let start_shift_y = top - doc.baseline_shift;
         let value = self
-            .area
             .lines
             .iter()
-            .map(|l| {
-                l.spans
-                    .iter()
-                    .flat_map(|span| span.metrics.chars())
-                    .collect::<Vec<_>>()
-            })
+            .map(|line| line.chars.clone())
             .collect::<Vec<_>>();
-        for (line_index, line) in self.area.lines.iter().enumerate() {
-            let mut char_index = 0;
-            let mut adv_base = line.width() * align_factor;
-            for span in &line.spans {
-                let factor = span.size / units;
-                let mut all_beziers = vec![];
+        let animators = Arc::new(TextAnimators::new(value, self.text_ranges.clone()));
+        let text_path = self
+            .path
+            .map(|(options, mask)| Arc::new(TextPath::new(options.clone(), mask.points.clone())));
+        let fill_layer = ShapeLayer {
+            name: None,
+            hidden: false,
+            shape: Shape::Fill(doc.fill_color.into()),
+        };
+        let mut styles = vec![fill_layer];
+        if doc.stroke_width > 0.0 {
+            let stroke_layer = ShapeLayer {
+                name: None,
+                hidden: false,
+                shape: Shape::Stroke(Stroke {
+                    line_cap: LineCap::Butt,
+                    line_join: LineJoin::Miter,
+                    miter_limit: 4.0,
+                    opacity: Animated::from_value(doc.stroke_color.a as f32 / 255.0 * 100.0),
+                    width: Animated::from_value(doc.stroke_width),
+                    dashes: vec![],
+                    color: Animated::from_value(Rgb::new_u8(
+                        doc.stroke_color.r,
+                        doc.stroke_color.g,
+                        doc.stroke_color.b,
+                    )),
+                }),
+            };
+            // Groups listed first are drawn on top
+            if doc.stroke_above_fill {
+                styles.insert(0, stroke_layer);
+            } else {
+                styles.push(stroke_layer);
+            }
+        }
 
-                // styles
-                let fill = self
-                    .keyframe
-                    .alter_value(span.additional.fill, span.additional.fill);
-                let fill_opacity = self
-                    .keyframe
-                    .alter_value(span.additional.fill_opacity, span.additional.fill_opacity);
-                let fill_layer = ShapeLayer {
+        let mut result = vec![];
+        for (line_index, line) in self.lines.iter().enumerate() {
+            let width = line.width(tracking);
+            let justified = match doc.justify {
+                TextJustify::LastLineFull => true,
+                TextJustify::LastLineLeft
+                | TextJustify::LastLineCenter
+                | TextJustify::LastLineRight => !line.last,
+                _ => false,
+            };
+            // Justified lines spread the remaining width between words, or
+            // between characters when there is a single word
+            let spaces = line.chars.iter().filter(|c| **c == ' ').count();
+            let (mut offset_x, word_spacing, char_spacing) = if !justified {
+                (left + (box_width - width) * align_factor, 0.0, 0.0)
+            } else if spaces > 0 {
+                (left, (box_width - width).max(0.0) / spaces as f32, 0.0)
+            } else {
+                let gaps = line.chars.len().saturating_sub(1).max(1);
+                (left, 0.0, (box_width - width).max(0.0) / gaps as f32)
+            };
+            let line_start = offset_x;
+            let line_offset = start_shift_y - top + line_height * line_index as f32;
+            let mut glyphs = vec![];
+            for (char_index, (c, glyph)) in line.chars.iter().zip(&line.glyphs).enumerate() {
+                // Characters following a path are moved onto it by their
+                // animators
+                let path = text_path.as_ref().map(|path| TextPathInfo {
+                    path: path.clone(),
+                    position: offset_x - line_start,
+                    line_width: width,
+                    line_offset,
+                });
+                let transform = match path {
+                    Some(_) => Transform::default(),
+                    None => Transform {
+                        position: Some(Animated::from_value(Vector2D::new(offset_x, 0.0)).into()),
+                        ..Default::default()
+                    },
+                };
+                offset_x += glyph.advance + tracking + char_spacing;
+                if *c == ' ' {
+                    offset_x += word_spacing;
+                }
+                let text_range = if self.text_ranges.is_empty() && path.is_none() {
+                    None
+                } else {
+                    Some(TextRangeInfo {
+                        animators: animators.clone(),
+                        index: (line_index, char_index),
+                        advance: glyph.advance,
+                        align: align_factor,
+                        path,
+                    })
+                };
+                let path = ShapeLayer {
                     name: None,
                     hidden: false,
-                    shape: Shape::Fill(Fill {
-                        opacity: Animated {
-                            animated: false,
-                            keyframes: vec![fill_opacity],
-                        },
-                        color: Animated {
-                            animated: false,
-                            keyframes: vec![fill],
-                        },
-                        fill_rule: FillRule::NonZero,
-                    }),
+                    shape: Shape::Path {
+                        d: Animated::from_value(glyph.beziers.clone()),
+                        text_range,
+                    },
                 };
-                for (index, c) in span.metrics.chars().into_iter().enumerate() {
-                    let (glyph, _) = font.outline(c).ok_or_else(|| {
-                        Error::FontGlyphNotFound(self.lottie_font.name.clone(), c)
-                    })?;
-                    let mut bezier = Bezier::default();
-                    let mut beziers = vec![];
-                    let mut last_pt = Vector2D::new(0.0, 0.0);
-                    let adv = adv_base
-                        + span
-                            .metrics
-                            .slice(0, index as u32)
-                            .width(span.size, span.letter_spacing);
-                    let segments = glyph.path.finish();
-                    let segments = segments
-                        .as_ref()
-                        .map(|p| p.segments())
-                        .into_iter()
-                        .flatten();
-                    for segment in segments {
-                        match segment {
-                            PathSegment::MoveTo(p) => {
-                                if !bezier.verticies.is_empty() {
-                                    let mut old = std::mem::replace(&mut bezier, Bezier::default());
-                                    old.out_tangent.push(Vector2D::new(0.0, 0.0));
-                                    beziers.push(old);
-                                }
-                                bezier.in_tangent.push(Vector2D::new(0.0, 0.0));
-                                last_pt = Vector2D::new(p.x, -p.y) * factor;
-                                bezier.verticies.push(last_pt);
-                            }
-                            PathSegment::LineTo(p) => {
-                                let pt = Vector2D::new(p.x, -p.y) * factor;
-                                bezier.out_tangent.push(Vector2D::new(0.0, 0.0));
-                                bezier.in_tangent.push(Vector2D::new(0.0, 0.0));
-                                bezier.verticies.push(pt);
-                                last_pt = pt;
-                            }
-                            PathSegment::CubicTo(p1, p2, p) => {
-                                let pt1 = Vector2D::new(p1.x, -p1.y) * factor;
-                                let pt2 = Vector2D::new(p2.x, -p2.y) * factor;
-                                let pt = Vector2D::new(p.x, -p.y) * factor;
-
-                                bezier.out_tangent.push(pt1 - last_pt);
-                                bezier.in_tangent.push(pt2 - pt);
-                                bezier.verticies.push(pt);
-                                last_pt = pt;
-                            }
-                            PathSegment::QuadTo(p1, p) => {
-                                let pt1 = Vector2D::new(p1.x, -p1.y) * factor;
-                                let pt = Vector2D::new(p.x, -p.y) * factor;
-
-                                bezier.out_tangent.push(pt1 - last_pt);
-                                bezier.in_tangent.push(pt1 - pt);
-                                bezier.verticies.push(pt);
-                                last_pt = pt;
-                            }
-                            PathSegment::Close => {
-                                bezier.closed = true;
-                            }
-                        }
-                    }
-                    if !bezier.verticies.is_empty() {
-                        bezier.out_tangent.push(Vector2D::new(0.0, 0.0));
-                        beziers.push(bezier);
-                    }
-                    all_beziers.push(GlyphData {
-                        c,
-                        beziers,
-                        offset_x: adv,
-                    });
-                }
-
-                let mut glyphs = all_beziers
-                    .into_iter()
-                    .map(|data| {
-                        let GlyphData {
-                            c,
-                            beziers,
-                            offset_x,
-                        } = data;
-
-                        let mut transform = Transform::default();
-                        transform.position = Some(Animated {
-                            animated: false,
-                            keyframes: vec![KeyFrame::from_value(Vector2D::new(offset_x, 0.0))],
-                        });
-                        let text_range = if self.text_ranges.is_empty() {
-                            None
-                        } else {
-                            Some(TextRangeInfo {
-                                value: value.clone(),
-                                index: (line_index, char_index),
-                                ranges: self.text_ranges.clone(),
-                            })
-                        };
-                        char_index += 1;
-                        ShapeLayer {
-                            name: Some(format!("{}", c)),
-                            hidden: false,
-                            shape: Shape::Group {
-                                shapes: vec![
-                                    ShapeLayer {
-                                        name: None,
-                                        hidden: false,
-                                        shape: Shape::Path {
-                                            d: Animated {
-                                                animated: false,
-                                                keyframes: vec![self
-                                                    .keyframe
-                                                    .alter_value(beziers.clone(), beziers)],
-                                            },
-                                            text_range,
-                                        },
-                                    },
-                                    fill_layer.clone(),
-                                    ShapeLayer {
-                                        name: None,
-                                        hidden: false,
-                                        shape: Shape::Transform(transform),
-                                    },
-                                ],
-                            },
-                        }
+                // A fill and a stroke of the same path are painted stroke
+                // last, so each style paints its own copy of the glyph
+                let mut shapes = styles
+                    .iter()
+                    .map(|style| ShapeLayer {
+                        name: None,
+                        hidden: false,
+                        shape: Shape::Group {
+                            shapes: vec![path.clone(), style.clone()],
+                        },
                     })
                     .collect::<Vec<_>>();
-
-                let shift = Vector2D::new(0.0, start_shift_y + line_y);
-                let transform_position = self.keyframe.alter_value(shift, shift);
-                let mut transform = Transform::default();
-                transform.position = Some(Animated {
-                    animated: false,
-                    keyframes: vec![transform_position],
-                });
-
-                glyphs.push(ShapeLayer {
+                shapes.push(ShapeLayer {
                     name: None,
                     hidden: false,
                     shape: Shape::Transform(transform),
                 });
-                let line_values = line
-                    .spans()
-                    .iter()
-                    .map(|span| span.metrics.value().to_string())
-                    .collect::<Vec<_>>();
-                let line_value = line_values.join("");
-                result.push(ShapeLayer {
-                    name: Some(line_value),
+                glyphs.push(ShapeLayer {
+                    name: Some(format!("{}", c)),
                     hidden: false,
-                    shape: Shape::Group { shapes: glyphs },
+                    shape: Shape::Group { shapes },
                 });
             }
-            line_y += line.height();
+
+            let shift = match self.path {
+                Some(_) => Vector2D::zero(),
+                None => Vector2D::new(0.0, start_shift_y + line_height * line_index as f32),
+            };
+            let transform = Transform {
+                position: Some(Animated::from_value(shift).into()),
+                ..Default::default()
+            };
+            glyphs.push(ShapeLayer {
+                name: None,
+                hidden: false,
+                shape: Shape::Transform(transform),
+            });
+            result.push(ShapeLayer {
+                name: Some(line.chars.iter().collect()),
+                hidden: false,
+                shape: Shape::Group { shapes: glyphs },
+            });
         }
-        Ok(ShapeLayer {
+        ShapeLayer {
             name: None,
             hidden: false,
             shape: Shape::Group { shapes: result },
+        }
+    }
+}
+
+/// Extra space after each character of `doc`
+fn tracking(doc: &TextDocument) -> f32 {
+    doc.tracking / 1000.0 * doc.size
+}
+
+/// Characters of `text` as drawn with `caps`, with the scale of their font
+/// size
+fn apply_caps(text: &str, caps: TextCaps) -> Vec<(char, f32)> {
+    text.chars()
+        .flat_map(|c| {
+            let (chars, scale): (Vec<char>, f32) = match caps {
+                TextCaps::Regular => (vec![c], 1.0),
+                TextCaps::AllCaps => (c.to_uppercase().collect(), 1.0),
+                TextCaps::SmallCaps if c.is_lowercase() => {
+                    (c.to_uppercase().collect(), SMALL_CAPS_SCALE)
+                }
+                TextCaps::SmallCaps => (vec![c], 1.0),
+            };
+            chars.into_iter().map(move |c| (c, scale))
         })
+        .collect()
+}
+
+/// Break a paragraph into lines no wider than `box_width`, at the last space
+/// before the overflowing character if there is one
+fn wrap(
+    chars: Vec<char>,
+    glyphs: Vec<Glyph>,
+    box_width: Option<f32>,
+    tracking: f32,
+) -> Vec<TextLine> {
+    let mut lines = vec![];
+    let mut line = TextLine::default();
+    let mut last_space = None;
+    for (c, glyph) in chars.into_iter().zip(glyphs) {
+        let overflows =
+            box_width.is_some_and(|width| line.width(tracking) + glyph.advance + tracking > width);
+        if overflows && c != ' ' && !line.chars.is_empty() {
+            let mut next = TextLine::default();
+            if let Some(index) = last_space.take() {
+                next.chars = line.chars.split_off(index + 1);
+                next.glyphs = line.glyphs.split_off(index + 1);
+                // The space the line breaks at isn't drawn
+                line.chars.pop();
+                line.glyphs.pop();
+            }
+            lines.push(std::mem::replace(&mut line, next));
+        }
+        if c == ' ' {
+            last_space = Some(line.chars.len());
+        }
+        line.chars.push(c);
+        line.glyphs.push(glyph);
     }
+    line.last = true;
+    lines.push(line);
+    lines
 }
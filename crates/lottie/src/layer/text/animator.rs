@@ -0,0 +1,450 @@
+use glam::{Mat4, Vec3};
+
+use crate::layer::text::path::MeasuredPath;
+use crate::lerp::Lerp;
+use crate::model::*;
+
+/// Combined effect of the animators of a text layer on one character
+#[derive(Debug, Clone)]
+pub struct TextAnimatorValue {
+    /// Matrix applied to the glyph, in glyph space
+    pub transform: Mat4,
+    /// Opacity multiplier of the glyph, from 0 to 1
+    pub opacity: f32,
+    /// Opacity multiplier of the glyph stroke, on top of [Self::opacity]
+    pub stroke_opacity: f32,
+    /// Standard deviation of the glyph blur
+    pub blur: f32,
+    fill: Vec<ColorAnimator>,
+    stroke: Vec<ColorAnimator>,
+    /// Widths the glyph stroke moves to, by the amount of their animator
+    stroke_width: Vec<(f32, f32)>,
+}
+
+impl TextAnimatorValue {
+    pub fn fill_color(&self, color: Rgb) -> Rgb {
+        self.fill
+            .iter()
+            .fold(color, |color, animator| animator.apply(color))
+    }
+
+    pub fn stroke_color(&self, color: Rgb) -> Rgb {
+        self.stroke
+            .iter()
+            .fold(color, |color, animator| animator.apply(color))
+    }
+
+    pub fn stroke_width(&self, width: f32) -> f32 {
+        self.stroke_width
+            .iter()
+            .fold(width, |width, (target, amount)| {
+                target.lerp(&width, *amount)
+            })
+    }
+}
+
+/// Animators of a text layer evaluated at a frame, every character of the
+/// layer needs them all
+#[derive(Debug)]
+pub struct TextFrame {
+    pub amounts: TextRangeAmounts,
+    /// Path followed by the characters
+    pub path: Option<MeasuredPath>,
+}
+
+impl TextFrame {
+    /// Evaluate the animators and the path shared by `info` and the other
+    /// characters of its layer
+    pub fn new(info: &TextRangeInfo, frame: f32) -> Self {
+        TextFrame {
+            amounts: info.animators.amounts(frame),
+            path: info.path.as_ref().map(|path| path.path.measure(frame)),
+        }
+    }
+}
+
+/// Color changes of one animator, applied by its amount
+#[derive(Debug, Clone)]
+struct ColorAnimator {
+    amount: f32,
+    color: Option<Rgb>,
+    /// Hue offset in degrees
+    hue: f32,
+    /// Saturation offset in percent
+    saturation: f32,
+    /// Brightness offset in percent
+    brightness: f32,
+}
+
+impl ColorAnimator {
+    fn new(
+        amount: f32,
+        color: &Option<Animated<Rgb>>,
+        hue: &Option<Animated<f32>>,
+        saturation: &Option<Animated<f32>>,
+        brightness: &Option<Animated<f32>>,
+        frame: f32,
+    ) -> Option<Self> {
+        if color.is_none() && hue.is_none() && saturation.is_none() && brightness.is_none() {
+            return None;
+        }
+        let number = |value: &Option<Animated<f32>>| value.as_ref().map_or(0.0, |v| v.value(frame));
+        Some(ColorAnimator {
+            amount,
+            color: color.as_ref().map(|c| c.value(frame)),
+            hue: number(hue),
+            saturation: number(saturation),
+            brightness: number(brightness),
+        })
+    }
+
+    fn apply(&self, color: Rgb) -> Rgb {
+        let color = match self.color {
+            Some(target) => target.lerp(&color, self.amount),
+            None => color,
+        };
+        if self.hue == 0.0 && self.saturation == 0.0 && self.brightness == 0.0 {
+            return color;
+        }
+        let [h, s, v] = rgb_to_hsv(color);
+        hsv_to_rgb([
+            (h + self.hue * self.amount / 360.0).rem_euclid(1.0),
+            (s + self.saturation * self.amount / 100.0).clamp(0.0, 1.0),
+            (v + self.brightness * self.amount / 100.0).clamp(0.0, 1.0),
+        ])
+    }
+}
+
+impl TextRangeInfo {
+    /// Effect of all animators on this character at `frame`, `text` is the
+    /// [TextFrame] of its layer at the same frame
+    pub fn value(&self, frame: f32, text: &TextFrame) -> TextAnimatorValue {
+        let (line, index) = self.index;
+        let mut result = TextAnimatorValue {
+            transform: Mat4::IDENTITY,
+            opacity: 1.0,
+            stroke_opacity: 1.0,
+            blur: 0.0,
+            fill: vec![],
+            stroke: vec![],
+            stroke_width: vec![],
+        };
+        let mut offset = Vector2D::zero();
+        let mut anchor = Vector2D::zero();
+        let mut position = Vector2D::zero();
+        let mut scale = Vector2D::new(1.0, 1.0);
+        let (mut rotation, mut rotation_x, mut rotation_y) = (0.0, 0.0, 0.0);
+        let (mut skew, mut skew_axis) = (0.0, 0.0);
+        let number = |value: &Option<Animated<f32>>| value.as_ref().map(|v| v.value(frame));
+        for (range, amounts) in self.animators.ranges.iter().zip(text.amounts.iter()) {
+            let style = match range.style.as_ref() {
+                Some(style) => style,
+                None => continue,
+            };
+            let amount = amounts[line][index];
+            if let Some(tracking) = number(&style.letter_spacing) {
+                // Characters move by the tracking of those before them, and
+                // the line moves back to keep its justification
+                let line_amounts = &amounts[line];
+                let before = line_amounts[..index].iter().sum::<f32>();
+                let total = line_amounts.iter().sum::<f32>();
+                offset.x += tracking * (before - total * self.align);
+            }
+            if let Some(spacing) = number(&style.line_spacing) {
+                offset.y += spacing * amount * line as f32;
+            }
+            if amount == 0.0 {
+                continue;
+            }
+            if let Some(transform) = style.transform.as_ref() {
+                if let Some(a) = transform.anchor.as_ref() {
+                    anchor += a.value(frame) * amount;
+                }
+                if let Some(p) = transform.position.as_ref() {
+                    position += p.value(frame) * amount;
+                }
+                let s = transform.scale.value(frame) / 100.0;
+                scale.x *= s.x.lerp(&1.0, amount);
+                scale.y *= s.y.lerp(&1.0, amount);
+                rotation += transform.rotation.value(frame) * amount;
+                rotation += number(&transform.rotation_z).unwrap_or(0.0) * amount;
+                rotation_x += number(&transform.rotation_x).unwrap_or(0.0) * amount;
+                rotation_y += number(&transform.rotation_y).unwrap_or(0.0) * amount;
+                skew += number(&transform.skew).unwrap_or(0.0) * amount;
+                skew_axis += number(&transform.skew_axis).unwrap_or(0.0) * amount;
+                let o = transform.opacity.value(frame) / 100.0;
+                result.opacity *= o.lerp(&1.0, amount);
+            }
+            if let Some(o) = number(&style.stroke_opacity) {
+                result.stroke_opacity *= (o / 100.0).lerp(&1.0, amount);
+            }
+            if let Some(width) = number(&style.stroke_width) {
+                result.stroke_width.push((width, amount));
+            }
+            // Blurriness is converted like the Gaussian Blur effect's
+            result.blur += number(&style.blur).unwrap_or(0.0) * amount * 0.3;
+            result.fill.extend(ColorAnimator::new(
+                amount,
+                &style.fill_color,
+                &style.fill_hue,
+                &style.fill_saturation,
+                &style.fill_brightness,
+                frame,
+            ));
+            result.stroke.extend(ColorAnimator::new(
+                amount,
+                &style.stroke_color,
+                &style.stroke_hue,
+                &style.stroke_saturation,
+                &style.stroke_brightness,
+                frame,
+            ));
+        }
+        let is_3d = rotation_x != 0.0 || rotation_y != 0.0;
+        let transform = Transform {
+            anchor: Some(Animated::from_value(anchor)),
+            position: Some(Animated::from_value(position).into()),
+            scale: Animated::from_value(scale * 100.0),
+            rotation: Animated::from_value(rotation),
+            rotation_x: is_3d.then(|| Animated::from_value(rotation_x)),
+            rotation_y: is_3d.then(|| Animated::from_value(rotation_y)),
+            rotation_z: is_3d.then(|| Animated::from_value(rotation)),
+            skew: Some(Animated::from_value(skew)),
+            skew_axis: Some(Animated::from_value(skew_axis)),
+            ..Default::default()
+        };
+        // Characters following a path move along it by their tracking
+        let placement = match (self.path.as_ref(), text.path.as_ref()) {
+            (Some(path), Some(measured)) => {
+                let count = self.animators.value[line].len();
+                let placement = path.transform(
+                    frame,
+                    measured,
+                    self.advance,
+                    self.align,
+                    (index, count),
+                    offset.x,
+                );
+                offset.x = 0.0;
+                placement
+            }
+            _ => Mat4::IDENTITY,
+        };
+        // Characters are transformed around the middle of their baseline
+        let center = Vec3::new(self.advance / 2.0, 0.0, 0.0);
+        result.transform = placement
+            * Mat4::from_translation(Vec3::new(offset.x, offset.y, 0.0) + center)
+            * transform.value(0.0)
+            * Mat4::from_translation(-center);
+        result
+    }
+}
+
+impl TextRangeSelector {
+    /// Amount of each character of `lines` selected at `frame`, from -1 to 1
+    pub fn amounts(&self, lines: &[Vec<char>], frame: f32) -> Vec<Vec<f32>> {
+        let (units, total) = text_units(lines, self.based_on);
+        let unit_amounts = if self.expressible && !self.expression_amounts.is_empty() {
+            (0..total)
+                .map(|unit| {
+                    self.expression_amounts
+                        .get(unit)
+                        .map_or(0.0, |amount| amount.value(frame) / 100.0)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let order = if self.randomize {
+                shuffled(total)
+            } else {
+                (0..total).collect()
+            };
+            (0..total)
+                .map(|unit| self.unit_amount(order[unit] as f32, total, frame))
+                .collect()
+        };
+        units
+            .into_iter()
+            .map(|line| {
+                line.into_iter()
+                    .map(|unit| unit.map_or(0.0, |unit| unit_amounts[unit]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Amount of the unit at `index` out of `total`, following lottie-web
+    fn unit_amount(&self, index: f32, total: usize, frame: f32) -> f32 {
+        let divisor = match self.range_units {
+            TextRangeUnits::Index => 1.0,
+            TextRangeUnits::Percent => 100.0 / total.max(1) as f32,
+        };
+        let offset = self.offset.as_ref().map_or(0.0, |o| o.value(frame)) / divisor;
+        let start = self
+            .start
+            .as_ref()
+            .map_or(0.0, |s| s.value(frame) / divisor);
+        let end = self
+            .end
+            .as_ref()
+            .map_or(total as f32, |e| e.value(frame) / divisor);
+        let (s, e) = if start > end {
+            (end + offset, start + offset)
+        } else {
+            (start + offset, end + offset)
+        };
+        let ramp = || ((0.5 + index - s) / (e - s)).clamp(0.0, 1.0);
+        let mut amount = match self.shape {
+            TextShape::Square => (e.min(index + 1.0) - s.max(index)).clamp(0.0, 1.0),
+            TextShape::RampUp if e == s => (index >= e) as u8 as f32,
+            TextShape::RampUp => ramp(),
+            TextShape::RampDown if e == s => (index < e) as u8 as f32,
+            TextShape::RampDown => 1.0 - ramp(),
+            _ if e == s => 0.0,
+            TextShape::Triangle => 1.0 - (2.0 * ramp() - 1.0).abs(),
+            TextShape::Round => {
+                let half = (e - s) / 2.0;
+                let x = (index + 0.5 - s).clamp(0.0, e - s) - half;
+                (1.0 - x * x / (half * half)).max(0.0).sqrt()
+            }
+            TextShape::Smooth => {
+                let x = (index + 0.5 - s).clamp(0.0, e - s) / (e - s);
+                (1.0 - (std::f32::consts::TAU * x).cos()) / 2.0
+            }
+        };
+        amount = self.ease(amount, frame);
+        let smoothness = self
+            .selector_smoothness
+            .as_ref()
+            .map_or(100.0, |s| s.value(frame))
+            / 100.0;
+        if smoothness < 1.0 {
+            let threshold = 0.5 - smoothness.max(f32::EPSILON) * 0.5;
+            amount = ((amount - threshold) / smoothness.max(f32::EPSILON)).clamp(0.0, 1.0);
+        }
+        amount * self.max_amount.value(frame) / 100.0
+    }
+
+    /// Ease `amount` with the ease high and ease low of the selector
+    fn ease(&self, amount: f32, frame: f32) -> f32 {
+        let low = self.min_ease.value(frame) / 100.0;
+        let high = self.max_ease.value(frame) / 100.0;
+        if low == 0.0 && high == 0.0 {
+            return amount;
+        }
+        let (x1, y1) = if low > 0.0 { (low, 0.0) } else { (0.0, -low) };
+        let (x2, y2) = if high > 0.0 {
+            (1.0 - high, 1.0)
+        } else {
+            (1.0, 1.0 + high)
+        };
+        let keyframe = KeyFrame {
+            start_value: 0.0,
+            end_value: 1.0,
+            start_frame: 0.0,
+            end_frame: 1.0,
+            hold: false,
+            easing_out: Some(Easing {
+                x: vec![x1],
+                y: vec![y1],
+            }),
+            easing_in: Some(Easing {
+                x: vec![x2],
+                y: vec![y2],
+            }),
+        };
+        keyframe.value(amount.clamp(0.0, 1.0))
+    }
+}
+
+/// Unit of each character of `lines` counted by `based_on`, with the number
+/// of units. Spaces have no unit when they aren't counted
+pub(crate) fn text_units(
+    lines: &[Vec<char>],
+    based_on: TextBased,
+) -> (Vec<Vec<Option<usize>>>, usize) {
+    let mut total: usize = 0;
+    let units = lines
+        .iter()
+        .enumerate()
+        .map(|(line_index, line)| {
+            let mut in_word = false;
+            line.iter()
+                .map(|c| match based_on {
+                    TextBased::Characters => {
+                        total += 1;
+                        Some(total - 1)
+                    }
+                    TextBased::CharactersExcludingSpaces if c.is_whitespace() => None,
+                    TextBased::CharactersExcludingSpaces => {
+                        total += 1;
+                        Some(total - 1)
+                    }
+                    // Spaces belong to the word before them
+                    TextBased::Words if c.is_whitespace() => {
+                        in_word = false;
+                        total.checked_sub(1)
+                    }
+                    TextBased::Words => {
+                        if !in_word {
+                            in_word = true;
+                            total += 1;
+                        }
+                        Some(total - 1)
+                    }
+                    TextBased::Lines => {
+                        total = line_index + 1;
+                        Some(line_index)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    (units, total)
+}
+
+/// A permutation of `0..len`, always the same for a given length
+fn shuffled(len: usize) -> Vec<usize> {
+    let mut order = (0..len).collect::<Vec<_>>();
+    let mut seed = len as u32 ^ 0x9e37_79b9;
+    for i in (1..len).rev() {
+        // xorshift32
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        order.swap(i, seed as usize % (i + 1));
+    }
+    order
+}
+
+fn rgb_to_hsv(color: Rgb) -> [f32; 3] {
+    let [r, g, b] = [color.r, color.g, color.b].map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0) / 6.0
+    } else if max == g {
+        ((b - r) / delta + 2.0) / 6.0
+    } else {
+        ((r - g) / delta + 4.0) / 6.0
+    };
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+    [h, s, max]
+}
+
+fn hsv_to_rgb([h, s, v]: [f32; 3]) -> Rgb {
+    let h = h * 6.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+    Rgb::new_f32(r + m, g + m, b + m)
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use glam::{Mat4, Quat, Vec3};
+use lyon_algorithms::measure::{PathMeasurements, SampleType};
+use lyon_algorithms::path::Path;
+
+use crate::layer::shape::PathFactory;
+use crate::model::*;
+
+/// Tolerance used when measuring the length of the path
+const TOLERANCE: f32 = 1e-3;
+
+/// Mask path followed by the characters of a text layer, shared by all of
+/// them
+#[derive(Debug)]
+pub struct TextPath {
+    pub options: TextFollowPath,
+    pub mask: Animated<Vec<Bezier>>,
+}
+
+/// A [TextPath] at a frame, measured for placing characters along it
+pub struct MeasuredPath {
+    path: Path,
+    measurements: PathMeasurements,
+    closed: bool,
+}
+
+impl TextPath {
+    pub fn new(options: TextFollowPath, mask: Animated<Vec<Bezier>>) -> Self {
+        TextPath { options, mask }
+    }
+
+    pub fn measure(&self, frame: f32) -> MeasuredPath {
+        let mut beziers = self.mask.value(frame);
+        if flag(&self.options.reverse, frame) {
+            beziers = beziers.iter().rev().map(Bezier::reversed).collect();
+        }
+        let path = beziers.path(frame);
+        MeasuredPath {
+            measurements: PathMeasurements::from_path(&path, TOLERANCE),
+            path,
+            closed: beziers.first().is_some_and(|bezier| bezier.closed),
+        }
+    }
+}
+
+impl fmt::Debug for MeasuredPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeasuredPath")
+            .field("length", &self.measurements.length())
+            .field("closed", &self.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+fn flag(value: &Option<Animated<f32>>, frame: f32) -> bool {
+    value.as_ref().is_some_and(|v| v.value(frame) != 0.0)
+}
+
+impl TextPathInfo {
+    /// Matrix moving a character from the origin onto the path at `frame`,
+    /// which is `measured` at that frame. `index` is the position of the
+    /// character among the `count` ones of its line, and `offset` moves it
+    /// further along the path
+    pub(crate) fn transform(
+        &self,
+        frame: f32,
+        measured: &MeasuredPath,
+        advance: f32,
+        align: f32,
+        (index, count): (usize, usize),
+        offset: f32,
+    ) -> Mat4 {
+        let options = &self.path.options;
+        let number = |value: &Option<Animated<f32>>| value.as_ref().map_or(0.0, |v| v.value(frame));
+        let length = measured.measurements.length();
+        let (first, last) = (number(&options.first_margin), number(&options.last_margin));
+        // Forced alignment spreads the characters over the length between the
+        // margins, otherwise the line is justified within them
+        let remaining = length - first - last - self.line_width;
+        let (start, spacing) = if flag(&options.force_alignment, frame) {
+            (first, remaining / count.saturating_sub(1).max(1) as f32)
+        } else {
+            (first + remaining * align, 0.0)
+        };
+        let distance = start + self.position + spacing * index as f32 + advance / 2.0 + offset;
+
+        let mut sampler = measured
+            .measurements
+            .create_sampler(&measured.path, SampleType::Distance);
+        // Closed paths wrap around, characters past the ends of open paths
+        // continue along their tangent
+        let (sample, overflow) = if measured.closed && length > 0.0 {
+            (sampler.sample(distance.rem_euclid(length)), 0.0)
+        } else {
+            let clamped = distance.clamp(0.0, length);
+            (sampler.sample(clamped), distance - clamped)
+        };
+        let tangent = sample.tangent();
+        if !tangent.x.is_finite() || !tangent.y.is_finite() {
+            return Mat4::IDENTITY;
+        }
+        let point = sample.position() + tangent * overflow;
+        let rotation = if flag(&options.perpendicular, frame) {
+            tangent.y.atan2(tangent.x)
+        } else {
+            0.0
+        };
+        Mat4::from_translation(Vec3::new(point.x, point.y, 0.0))
+            * Mat4::from_quat(Quat::from_rotation_z(rotation))
+            * Mat4::from_translation(Vec3::new(-advance / 2.0, self.line_offset, 0.0))
+    }
+}
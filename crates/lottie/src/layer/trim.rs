@@ -0,0 +1,75 @@
+use std::ops::Range;
+
+use lyon_algorithms::measure::{PathMeasurements, SampleType};
+use lyon_path::Path;
+
+use crate::model::Trim;
+
+/// Tolerance used when measuring path lengths
+const TOLERANCE: f32 = 1e-3;
+
+impl Trim {
+    /// Parts of a path kept at `frame`, as ranges of its normalized length.
+    /// The offset can wrap the kept part around the end of the path, which
+    /// results in two ranges
+    pub fn ranges(&self, frame: f32) -> Vec<Range<f32>> {
+        let start = (self.start.value(frame) / 100.0).clamp(0.0, 1.0);
+        let end = (self.end.value(frame) / 100.0).clamp(0.0, 1.0);
+        let (start, end) = (start.min(end), start.max(end));
+        if end - start >= 1.0 {
+            return vec![0.0..1.0];
+        }
+        if end <= start {
+            return vec![];
+        }
+        let offset = (self.offset.value(frame) / 360.0).rem_euclid(1.0);
+        let (start, end) = (start + offset, end + offset);
+        if end <= 1.0 {
+            vec![start..end]
+        } else if start >= 1.0 {
+            vec![start - 1.0..end - 1.0]
+        } else {
+            vec![start..1.0, 0.0..end - 1.0]
+        }
+    }
+
+    /// Trim a single path at `frame`
+    pub fn trim_path(&self, path: &Path, frame: f32) -> Path {
+        self.trim_paths(std::slice::from_ref(path), frame)
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Trim `paths` as if they were one continuous path, made of all of them
+    /// in order. Returns the trimmed part of each path
+    pub fn trim_paths(&self, paths: &[Path], frame: f32) -> Vec<Path> {
+        let ranges = self.ranges(frame);
+        if ranges == [0.0..1.0] {
+            return paths.to_vec();
+        }
+        let measurements = paths
+            .iter()
+            .map(|path| PathMeasurements::from_path(path, TOLERANCE))
+            .collect::<Vec<_>>();
+        let total = measurements.iter().map(|m| m.length()).sum::<f32>();
+        let mut offset = 0.0;
+        paths
+            .iter()
+            .zip(measurements.iter())
+            .map(|(path, measurements)| {
+                let length = measurements.length();
+                let mut sampler = measurements.create_sampler(path, SampleType::Distance);
+                let mut builder = Path::builder();
+                for range in &ranges {
+                    let start = (range.start * total - offset).max(0.0);
+                    let end = (range.end * total - offset).min(length);
+                    if end > start {
+                        sampler.split_range(start..end, &mut builder);
+                    }
+                }
+                offset += length;
+                builder.build()
+            })
+            .collect()
+    }
+}
@@ -1,3 +1,5 @@
+use glam::Vec3;
+
 use crate::model::{Bezier, GradientColor, Rgb, Rgba, Vector2D};
 
 pub trait Lerp {
@@ -13,6 +15,14 @@ impl Lerp for Vector2D {
     }
 }
 
+impl Lerp for Vec3 {
+    type Target = Vec3;
+
+    fn lerp(&self, other: &Self, t: f32) -> Self::Target {
+        (*self - *other) * t + *other
+    }
+}
+
 impl Lerp for f32 {
     type Target = f32;
 
@@ -1,33 +1,44 @@
-use std::io::Read;
 use crate::model::Model;
+pub use dotlottie::{DotLottie, Manifest, ManifestAnimation};
 pub use error::Error;
-pub use font::{FontDB, MockFont};
 #[cfg(feature = "text")]
-pub use fontkit::tiny_skia_path;
+pub use font::FontKitDB;
+pub use font::{CharsDB, FontDB, Glyph, MockFont};
 #[cfg(feature = "text")]
-use fontkit::FontKit;
+pub use fontkit::tiny_skia_path;
+pub use glam;
+use glam::Vec2;
 pub use lerp::*;
 pub use renderer::*;
+pub use resolver::*;
+use std::io::Read;
 use timeline::Timeline;
 
+mod dotlottie;
 mod error;
+#[cfg(feature = "expressions")]
+mod expression;
 mod font;
 mod layer;
 mod lerp;
 mod model;
 mod renderer;
+mod resolver;
+mod scene;
 mod timeline;
 
-
 pub mod prelude {
     pub use crate::layer::frame::*;
     pub use crate::layer::hierarchy::*;
+    pub use crate::layer::modifier::PathModifier;
     pub use crate::layer::shape::{
-        AnyFill, AnyStroke, PathFactory, StyledShape, StyledShapeIterator, TrimInfo,
+        AnyFill, AnyStroke, DashPattern, PathFactory, StyledShape, StyledShapeIterator, TrimInfo,
     };
     pub use crate::layer::staged::{RenderableContent, StagedLayer};
+    pub use crate::layer::text::{MeasuredPath, TextAnimatorValue, TextFrame, TextPath};
     pub use crate::model::*;
-    pub use crate::timeline::{Id, TimelineAction};
+    pub use crate::scene::*;
+    pub use crate::timeline::{Id, Timeline, TimelineAction};
 }
 
 pub struct Lottie<F: FontDB> {
@@ -38,13 +49,13 @@ pub struct Lottie<F: FontDB> {
 }
 
 impl<F: FontDB> Lottie<F> {
-    /// Initiate a new `Lottie` by providing a raw `Model`, a `FontKit` for font
-    /// management, and a root path.Root path will be used to resolve relative
-    /// paths of media files in this lottie model
-    pub fn new(model: Model, mut fontdb: F, root_path: &str) -> Result<Self, Error> {
-        fontdb.load_fonts_from_model(&model)?;
+    /// Initiate a new `Lottie` by providing a raw `Model`, a [FontDB] for font
+    /// management, and an [AssetResolver] loading media files and remote
+    /// fonts referenced by this lottie model
+    pub fn new(model: Model, mut fontdb: F, resolver: &dyn AssetResolver) -> Result<Self, Error> {
+        fontdb.load_fonts_from_model(&model, resolver)?;
 
-        let timeline = Timeline::new(&model, &fontdb, root_path)?;
+        let timeline = Timeline::new(&model, &fontdb, resolver)?;
         Ok(Lottie {
             model,
             timeline,
@@ -53,11 +64,19 @@ impl<F: FontDB> Lottie<F> {
         })
     }
 
+    /// Assets are resolved with a [DefaultResolver] rooted at `root_path`
     #[cfg(not(all(target_os = "unknown", target_arch = "wasm32")))]
     pub fn from_reader<R: Read>(r: &mut R, root_path: &str) -> Result<Self, Error> {
         let font = F::from_reader(r, root_path)?;
         let model = Model::from_reader(r)?;
-        Ok(Lottie::new(model, font, root_path)?)
+        Ok(Lottie::new(model, font, &DefaultResolver::new(root_path))?)
+    }
+
+    /// Load animation `id` from a dotLottie archive, media assets are
+    /// resolved from the archive
+    pub fn from_dotlottie(archive: &DotLottie, id: &str) -> Result<Self, Error> {
+        let model = archive.model(id)?;
+        Lottie::new(model, F::with_root("")?, archive)
     }
 
     pub fn timeline(&self) -> &Timeline {
@@ -67,4 +86,29 @@ impl<F: FontDB> Lottie<F> {
     pub fn fontdb(&self) -> &F {
         &self.fontdb
     }
+
+    /// Render a single frame with `backend`, without playing through the
+    /// preceding frames. The animation is stretched to fill `width` x
+    /// `height`
+    pub fn render_frame(
+        &self,
+        backend: &mut dyn FrameRenderer,
+        frame: f32,
+        width: u32,
+        height: u32,
+    ) -> RgbaImage {
+        // A model without an area has nothing to draw, and can't be scaled
+        if self.model.width == 0 || self.model.height == 0 {
+            return RgbaImage {
+                data: vec![0; width as usize * height as usize * 4],
+                width,
+                height,
+            };
+        }
+        let scale = Vec2::new(
+            width as f32 / self.model.width as f32,
+            height as f32 / self.model.height as f32,
+        );
+        backend.render_frame(&self.timeline, frame, width, height, scale)
+    }
 }
@@ -1,8 +1,12 @@
 pub use euclid::default::Rect;
 pub use euclid::rect;
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec3, Vec4};
 use serde::{Deserialize, Serialize};
 pub use serde_json::Error;
+use std::sync::Arc;
+
+use crate::layer::text::TextPath;
+
 pub type Vector2D = euclid::default::Vector2D<f32>;
 
 mod animated;
@@ -15,9 +19,9 @@ use helpers::*;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Model {
-    #[serde(rename = "nm")]
+    #[serde(rename = "nm", skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
-    #[serde(rename = "v", default)]
+    #[serde(rename = "v", default, skip_serializing_if = "Option::is_none")]
     version: Option<String>,
     #[serde(rename = "ip")]
     pub start_frame: f32,
@@ -25,15 +29,17 @@ pub struct Model {
     pub end_frame: f32,
     #[serde(rename = "fr")]
     pub frame_rate: f32,
-    #[serde(rename = "w")]
+    #[serde(rename = "w", deserialize_with = "u32_from_number")]
     pub width: u32,
-    #[serde(rename = "h")]
+    #[serde(rename = "h", deserialize_with = "u32_from_number")]
     pub height: u32,
     pub layers: Vec<Layer>,
     #[serde(default)]
     pub assets: Vec<Asset>,
     #[serde(default)]
     pub fonts: FontList,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chars: Vec<FontChar>,
 }
 
 impl Model {
@@ -45,6 +51,10 @@ impl Model {
         (self.end_frame - self.start_frame) as f32 / self.frame_rate as f32
     }
 
+    pub fn to_writer<W: std::io::Write>(&self, w: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(w, self)
+    }
+
     pub fn font(&self, name: &str) -> Option<&Font> {
         self.fonts.list.iter().find(|f| f.name == name)
     }
@@ -61,9 +71,9 @@ pub struct Layer {
     is_3d: bool,
     #[serde(rename = "hd", default)]
     pub hidden: bool,
-    #[serde(rename = "ind", default)]
+    #[serde(rename = "ind", default, skip_serializing_if = "Option::is_none")]
     pub index: Option<u32>,
-    #[serde(rename = "parent", default)]
+    #[serde(rename = "parent", default, skip_serializing_if = "Option::is_none")]
     pub parent_index: Option<u32>,
     #[serde(skip)]
     pub id: u32,
@@ -80,20 +90,24 @@ pub struct Layer {
     pub end_frame: f32,
     #[serde(rename = "st")]
     pub start_time: f32,
-    #[serde(rename = "nm")]
+    #[serde(rename = "nm", skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
-    #[serde(rename = "ks", default)]
+    #[serde(rename = "ks", default, skip_serializing_if = "Option::is_none")]
     pub transform: Option<Transform>,
     #[serde(flatten)]
     pub content: LayerContent,
-    #[serde(rename = "tt", default)]
+    #[serde(rename = "tt", default, skip_serializing_if = "Option::is_none")]
     pub matte_mode: Option<MatteMode>,
-    #[serde(rename = "bm", default)]
+    #[serde(rename = "bm", default, skip_serializing_if = "Option::is_none")]
     pub blend_mode: Option<BlendMode>,
     #[serde(default, rename = "hasMask")]
     pub has_mask: bool,
     #[serde(default, rename = "masksProperties")]
     pub masks_properties: Vec<Mask>,
+    #[serde(rename = "ef", default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<Effect>,
+    #[serde(rename = "sy", default, skip_serializing_if = "Vec::is_empty")]
+    pub styles: Vec<LayerStyle>,
 }
 
 impl Layer {
@@ -123,6 +137,8 @@ impl Layer {
             blend_mode: None,
             has_mask: false,
             masks_properties: vec![],
+            effects: vec![],
+            styles: vec![],
         }
     }
 }
@@ -138,47 +154,64 @@ pub enum LayerContent {
     MediaRef(MediaRef),
     Empty,
     Shape(ShapeGroup),
-    #[cfg(feature = "text")]
-    Text(TextAnimationData),
+    Text(Box<TextAnimationData>),
     Media(Media),
+    /// Layer types not supported yet, kept in raw form
+    Unknown(serde_json::Value),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MediaRef {
     #[serde(rename = "refId")]
     pub ref_id: String,
+    /// Audio layers reference media assets the same way as image layers
+    #[serde(skip)]
+    pub is_audio: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PreCompositionRef {
     #[serde(rename = "refId")]
     pub ref_id: String,
-    #[serde(rename = "w")]
+    #[serde(rename = "w", deserialize_with = "u32_from_number")]
     width: u32,
-    #[serde(rename = "h")]
+    #[serde(rename = "h", deserialize_with = "u32_from_number")]
     height: u32,
-    #[serde(rename = "tm")]
+    #[serde(rename = "tm", skip_serializing_if = "Option::is_none")]
     pub time_remapping: Option<Animated<f32>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Transform {
-    #[serde(rename = "a", default)]
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
     pub anchor: Option<Animated<Vector2D>>,
-    #[serde(rename = "p", default)]
-    pub position: Option<Animated<Vector2D>>,
+    #[serde(rename = "p", default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
     #[serde(rename = "s", default = "default_vec2_100")]
     pub scale: Animated<Vector2D>,
     #[serde(rename = "r", default)]
     pub rotation: Animated<f32>,
+    #[serde(rename = "rx", default, skip_serializing_if = "Option::is_none")]
+    pub rotation_x: Option<Animated<f32>>,
+    #[serde(rename = "ry", default, skip_serializing_if = "Option::is_none")]
+    pub rotation_y: Option<Animated<f32>>,
+    #[serde(rename = "rz", default, skip_serializing_if = "Option::is_none")]
+    pub rotation_z: Option<Animated<f32>>,
+    /// Rotations around the x, y and z axes applied after `rotation_*`
+    #[serde(rename = "or", default, skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<Animated<Vec3>>,
     #[serde(skip)]
     pub auto_orient: bool,
     #[serde(rename = "o", default = "default_number_100")]
     pub opacity: Animated<f32>,
-    #[serde(rename = "sk", default)]
+    #[serde(rename = "sk", default, skip_serializing_if = "Option::is_none")]
     pub skew: Option<Animated<f32>>,
-    #[serde(rename = "sa", default)]
+    #[serde(rename = "sa", default, skip_serializing_if = "Option::is_none")]
     pub skew_axis: Option<Animated<f32>>,
+    /// Set on transforms of groups generated from a [Shape::Repeater], which
+    /// take the place of all fields above
+    #[serde(skip)]
+    pub repeater: Option<Box<RepeaterCopy>>,
 }
 
 impl Default for Transform {
@@ -188,10 +221,15 @@ impl Default for Transform {
             position: Default::default(),
             scale: default_vec2_100(),
             rotation: Default::default(),
+            rotation_x: Default::default(),
+            rotation_y: Default::default(),
+            rotation_z: Default::default(),
+            orientation: Default::default(),
             opacity: default_number_100(),
             skew: Default::default(),
             skew_axis: Default::default(),
             auto_orient: false,
+            repeater: None,
         }
     }
 }
@@ -208,38 +246,49 @@ impl Transform {
             .as_ref()
             .and_then(|a| Some(a.keyframes.last()?.end_frame))
             .unwrap_or(0.0);
-        let pos_frames = self
-            .position
-            .as_ref()
-            .and_then(|a| Some(a.keyframes.last()?.end_frame))
-            .unwrap_or(0.0);
+        let pos_frames = self.position.as_ref().map(|p| p.frames()).unwrap_or(0.0);
         let scale_frames = self.scale.keyframes.last().unwrap().end_frame;
         let rotation_frames = self.rotation.keyframes.last().unwrap().end_frame;
+        let other_frames = [
+            &self.rotation_x,
+            &self.rotation_y,
+            &self.rotation_z,
+            &self.skew,
+            &self.skew_axis,
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|a| Some(a.keyframes.last()?.end_frame))
+        .chain(
+            self.orientation
+                .as_ref()
+                .and_then(|a| Some(a.keyframes.last()?.end_frame)),
+        )
+        .fold(0.0, f32::max);
+        let repeater_frames = self.repeater.as_ref().map(|r| r.frames()).unwrap_or(0.0);
         anchor_frames
             .max(pos_frames)
             .max(scale_frames)
             .max(rotation_frames)
+            .max(other_frames)
+            .max(repeater_frames)
     }
 
     pub fn initial_value(&self) -> Mat4 {
         self.value(0.0)
     }
 
+    /// Matrix of the transform, composed in the same order as lottie-web:
+    /// anchor, scale, skew, rotation, then position
     pub fn value(&self, frame: f32) -> Mat4 {
+        if let Some(repeater) = self.repeater.as_ref() {
+            return repeater.value(frame);
+        }
         let mut angle = 0.0;
         if let Some(position) = self.position.as_ref() {
             if self.auto_orient && position.is_animated() {
-                let len = position.keyframes.len() - 1;
-                let mut frame = position.keyframes[0].start_frame.max(frame);
-                frame = position.keyframes[len].start_frame.min(frame);
-                if let Some(keyframe) = position
-                    .keyframes
-                    .iter()
-                    .find(|keyframe| frame >= keyframe.start_frame && frame < keyframe.end_frame)
-                {
-                    angle = (keyframe.end_value - keyframe.start_value)
-                        .angle_from_x_axis()
-                        .to_degrees();
+                if let Some(direction) = position.direction(frame) {
+                    angle = direction.angle_from_x_axis().to_degrees();
                 }
             }
         }
@@ -251,10 +300,9 @@ impl Transform {
         let position = self
             .position
             .as_ref()
-            .map(|a| a.value(frame))
+            .map(|p| p.value(frame))
             .unwrap_or_default();
         let mut scale = self.scale.value(frame) / 100.0;
-        let rotation = self.rotation.value(frame) + angle;
         // Some lottie file has scale = 0, which is invalid
         if scale.x == 0.0 {
             scale.x = f32::EPSILON;
@@ -262,7 +310,63 @@ impl Transform {
         if scale.y == 0.0 {
             scale.y = f32::EPSILON;
         }
-        mat4(anchor, position, scale, rotation)
+        let skew = self.skew.as_ref().map(|s| s.value(frame)).unwrap_or(0.0);
+        let skew_axis = self
+            .skew_axis
+            .as_ref()
+            .map(|s| s.value(frame))
+            .unwrap_or(0.0);
+        Mat4::from_translation(Vec3::new(position.x, position.y, 0.0))
+            * Mat4::from_rotation_z(angle.to_radians())
+            * self.rotation_value(frame)
+            * skew_mat4(skew, skew_axis)
+            * Mat4::from_scale(Vec3::new(scale.x, scale.y, 1.0))
+            * Mat4::from_translation(Vec3::new(-anchor.x, -anchor.y, 0.0))
+    }
+
+    /// Rotation matrix, 3D layers rotate around each axis instead of
+    /// `rotation`
+    fn rotation_value(&self, frame: f32) -> Mat4 {
+        let is_3d = self.rotation_x.is_some()
+            || self.rotation_y.is_some()
+            || self.rotation_z.is_some()
+            || self.orientation.is_some();
+        if !is_3d {
+            return Mat4::from_rotation_z(self.rotation.value(frame).to_radians());
+        }
+        let radians = |a: &Option<Animated<f32>>| {
+            a.as_ref()
+                .map(|a| a.value(frame).to_radians())
+                .unwrap_or(0.0)
+        };
+        let orientation = self
+            .orientation
+            .as_ref()
+            .map(|o| o.value(frame))
+            .unwrap_or_default();
+        Mat4::from_rotation_x(-orientation.x.to_radians())
+            * Mat4::from_rotation_y(-orientation.y.to_radians())
+            * Mat4::from_rotation_z(orientation.z.to_radians())
+            * Mat4::from_rotation_x(-radians(&self.rotation_x))
+            * Mat4::from_rotation_y(-radians(&self.rotation_y))
+            * Mat4::from_rotation_z(radians(&self.rotation_z))
+    }
+
+    /// Opacity from 0 to 100, repeater copies fade from their start opacity to
+    /// their end opacity
+    pub fn opacity_value(&self, frame: f32) -> f32 {
+        match self.repeater.as_ref() {
+            Some(repeater) => repeater.opacity(frame),
+            None => self.opacity.value(frame),
+        }
+    }
+
+    /// Whether [Transform::opacity_value] changes over time
+    pub fn is_opacity_animated(&self) -> bool {
+        match self.repeater.as_ref() {
+            Some(repeater) => repeater.is_animated(),
+            None => self.opacity.is_animated(),
+        }
     }
 
     pub fn is_animated(&self) -> bool {
@@ -273,13 +377,102 @@ impl Transform {
             || self
                 .position
                 .as_ref()
-                .map(|a| a.is_animated())
+                .map(|p| p.is_animated())
                 .unwrap_or(false)
             || self.scale.is_animated()
             || self.rotation.is_animated()
+            || [
+                &self.rotation_x,
+                &self.rotation_y,
+                &self.rotation_z,
+                &self.skew,
+                &self.skew_axis,
+            ]
+            .into_iter()
+            .flatten()
+            .any(|a| a.is_animated())
+            || self
+                .orientation
+                .as_ref()
+                .map(|o| o.is_animated())
+                .unwrap_or(false)
+            || self
+                .repeater
+                .as_ref()
+                .map(|r| r.is_animated())
+                .unwrap_or(false)
     }
 }
 
+/// Position of a [Transform], either a single animated point or, when split,
+/// separately animated coordinates
+#[derive(Debug, Clone)]
+pub enum Position {
+    Combined(Animated<Vector2D>),
+    Split {
+        x: Animated<f32>,
+        y: Animated<f32>,
+        z: Option<Animated<f32>>,
+    },
+}
+
+impl Position {
+    pub fn value(&self, frame: f32) -> Vector2D {
+        match self {
+            Position::Combined(p) => p.value(frame),
+            Position::Split { x, y, .. } => Vector2D::new(x.value(frame), y.value(frame)),
+        }
+    }
+
+    pub fn is_animated(&self) -> bool {
+        match self {
+            Position::Combined(p) => p.is_animated(),
+            Position::Split { x, y, .. } => x.is_animated() || y.is_animated(),
+        }
+    }
+
+    fn frames(&self) -> f32 {
+        let last = |a: &Animated<f32>| a.keyframes.last().map_or(0.0, |k| k.end_frame);
+        match self {
+            Position::Combined(p) => p.keyframes.last().map_or(0.0, |k| k.end_frame),
+            Position::Split { x, y, .. } => last(x).max(last(y)),
+        }
+    }
+
+    /// Direction of motion at `frame`, from the start to the end of the
+    /// current keyframe
+    fn direction(&self, frame: f32) -> Option<Vector2D> {
+        match self {
+            Position::Combined(p) => keyframe_delta(p, frame),
+            Position::Split { x, y, .. } => Some(Vector2D::new(
+                keyframe_delta(x, frame).unwrap_or(0.0),
+                keyframe_delta(y, frame).unwrap_or(0.0),
+            )),
+        }
+    }
+}
+
+impl From<Animated<Vector2D>> for Position {
+    fn from(position: Animated<Vector2D>) -> Self {
+        Position::Combined(position)
+    }
+}
+
+/// Change of value over the keyframe at `frame`, clamped to the keyframes
+fn keyframe_delta<T>(animated: &Animated<T>, frame: f32) -> Option<T>
+where
+    T: Copy + std::ops::Sub<Output = T>,
+{
+    let first = animated.keyframes.first()?;
+    let last = animated.keyframes.last()?;
+    let frame = frame.max(first.start_frame).min(last.start_frame);
+    animated
+        .keyframes
+        .iter()
+        .find(|keyframe| frame >= keyframe.start_frame && frame < keyframe.end_frame)
+        .map(|keyframe| keyframe.end_value - keyframe.start_value)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RepeaterTransform {
     #[serde(rename = "a", default)]
@@ -294,10 +487,118 @@ pub struct RepeaterTransform {
     start_opacity: Animated<f32>,
     #[serde(rename = "eo")]
     end_opacity: Animated<f32>,
-    #[serde(rename = "sk", default)]
-    skew: Option<Animated<Vector2D>>,
-    #[serde(rename = "sa", default)]
-    skew_axis: Option<Animated<Vector2D>>,
+    /// Kept for round trips, lottie-web doesn't skew repeater copies
+    #[serde(rename = "sk", default, skip_serializing_if = "Option::is_none")]
+    skew: Option<Animated<f32>>,
+    #[serde(rename = "sa", default, skip_serializing_if = "Option::is_none")]
+    skew_axis: Option<Animated<f32>>,
+}
+
+impl RepeaterTransform {
+    /// Matrix of the copy `n` steps away from the original shapes, `n` may be
+    /// fractional or negative
+    fn value(&self, frame: f32, n: f32) -> Mat4 {
+        let anchor = self.anchor.value(frame);
+        let anchor = Vec3::new(anchor.x, anchor.y, 0.0);
+        let position = self.position.value(frame) * n;
+        let scale = self.scale.value(frame) / 100.0;
+        let scale = Vec3::new(repeat_scale(scale.x, n), repeat_scale(scale.y, n), 1.0);
+        let rotation = self.rotation.value(frame) * n;
+        Mat4::from_translation(Vec3::new(position.x, position.y, 0.0))
+            * Mat4::from_translation(anchor)
+            * Mat4::from_scale(scale)
+            * Mat4::from_rotation_z(rotation * std::f32::consts::PI / 180.0)
+            * Mat4::from_translation(-anchor)
+    }
+
+    fn frames(&self) -> f32 {
+        [
+            self.anchor.keyframes.last().map(|k| k.end_frame),
+            self.position.keyframes.last().map(|k| k.end_frame),
+            self.scale.keyframes.last().map(|k| k.end_frame),
+            self.rotation.keyframes.last().map(|k| k.end_frame),
+            self.start_opacity.keyframes.last().map(|k| k.end_frame),
+            self.end_opacity.keyframes.last().map(|k| k.end_frame),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(0.0, f32::max)
+    }
+
+    fn is_animated(&self) -> bool {
+        self.anchor.is_animated()
+            || self.position.is_animated()
+            || self.scale.is_animated()
+            || self.rotation.is_animated()
+            || self.start_opacity.is_animated()
+            || self.end_opacity.is_animated()
+    }
+}
+
+/// Scale factor `scale` applied `n` times, fractional steps scale partially
+fn repeat_scale(scale: f32, n: f32) -> f32 {
+    let whole = n.trunc();
+    let fract = n - whole;
+    let partial = if fract >= 0.0 {
+        1.0 + (scale - 1.0) * fract
+    } else {
+        1.0 / (1.0 + (scale - 1.0) * -fract)
+    };
+    scale.powi(whole as i32) * partial
+}
+
+/// The `index`th copy generated by a [Shape::Repeater]. Copies beyond the
+/// current number of copies are hidden
+#[derive(Debug, Clone)]
+pub struct RepeaterCopy {
+    pub index: u32,
+    pub copies: Animated<f32>,
+    pub offset: Animated<f32>,
+    pub transform: RepeaterTransform,
+}
+
+impl RepeaterCopy {
+    pub fn copies(&self, frame: f32) -> u32 {
+        self.copies.value(frame).ceil().max(0.0) as u32
+    }
+
+    pub fn is_visible(&self, frame: f32) -> bool {
+        self.index < self.copies(frame)
+    }
+
+    pub fn value(&self, frame: f32) -> Mat4 {
+        if !self.is_visible(frame) {
+            return Mat4::from_scale(Vec3::new(f32::EPSILON, f32::EPSILON, 1.0));
+        }
+        let n = self.index as f32 + self.offset.value(frame);
+        self.transform.value(frame, n)
+    }
+
+    /// Opacity from 0 to 100
+    pub fn opacity(&self, frame: f32) -> f32 {
+        let copies = self.copies(frame);
+        if self.index >= copies {
+            return 0.0;
+        }
+        let start = self.transform.start_opacity.value(frame);
+        let end = self.transform.end_opacity.value(frame);
+        if copies == 1 {
+            start
+        } else {
+            start + (end - start) * self.index as f32 / (copies - 1) as f32
+        }
+    }
+
+    fn frames(&self) -> f32 {
+        [&self.copies, &self.offset]
+            .into_iter()
+            .filter_map(|a| Some(a.keyframes.last()?.end_frame))
+            .fold(self.transform.frames(), f32::max)
+    }
+
+    fn is_animated(&self) -> bool {
+        self.copies.is_animated() || self.offset.is_animated() || self.transform.is_animated()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -307,27 +608,52 @@ pub struct FontList {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Font {
-    #[serde(default)]
-    ascent: Option<f32>,
+    /// Height above the baseline, for a font size of 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ascent: Option<f32>,
     #[serde(rename = "fFamily")]
     pub family: String,
     #[serde(rename = "fName")]
     pub name: String,
     #[serde(rename = "fStyle")]
-    style: String,
-    #[serde(rename = "fPath", default)]
+    pub style: String,
+    #[serde(rename = "fPath", default, skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
-    #[serde(rename = "fWeight")]
+    #[serde(rename = "fWeight", skip_serializing_if = "Option::is_none")]
     weight: Option<String>,
     #[serde(default)]
     pub origin: FontPathOrigin,
-    #[serde(rename = "fClass", default)]
+    #[serde(rename = "fClass", default, skip_serializing_if = "Option::is_none")]
     class: Option<String>,
 }
 
+/// Outline of a character exported with the animation, sized for a font
+/// size of 100
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FontChar {
+    #[serde(rename = "ch")]
+    pub character: String,
+    #[serde(rename = "fFamily")]
+    pub family: String,
+    #[serde(default)]
+    pub style: String,
+    #[serde(default)]
+    pub size: f32,
+    #[serde(rename = "w", default)]
+    pub width: f32,
+    #[serde(default)]
+    pub data: FontCharData,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct FontCharData {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shapes: Vec<ShapeLayer>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ShapeLayer {
-    #[serde(rename = "nm", default)]
+    #[serde(rename = "nm", default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(rename = "hd", default)]
     pub hidden: bool,
@@ -337,11 +663,56 @@ pub struct ShapeLayer {
 
 #[derive(Debug, Clone)]
 pub struct TextRangeInfo {
-    pub value: Vec<Vec<char>>,
+    /// Animators of the text layer, shared by all of its characters
+    pub animators: Arc<TextAnimators>,
     pub index: (usize, usize), // line, char
+    /// Advance of the character
+    pub advance: f32,
+    /// Share of its width a line moves back by when it grows, from its
+    /// justification
+    pub align: f32,
+    /// Set when the text follows a mask path
+    pub path: Option<TextPathInfo>,
+}
+
+/// Amount of each character selected by each range, by range, line and
+/// character
+pub type TextRangeAmounts = Vec<Vec<Vec<f32>>>;
+
+/// Lines of a text layer with the animators changing its characters
+#[derive(Debug)]
+pub struct TextAnimators {
+    pub value: Vec<Vec<char>>,
     pub ranges: Vec<TextRange>,
 }
 
+impl TextAnimators {
+    pub fn new(value: Vec<Vec<char>>, ranges: Vec<TextRange>) -> Self {
+        TextAnimators { value, ranges }
+    }
+
+    /// Amount of each character selected by each range at `frame`
+    pub fn amounts(&self, frame: f32) -> TextRangeAmounts {
+        self.ranges
+            .iter()
+            .map(|range| range.selector.amounts(&self.value, frame))
+            .collect()
+    }
+}
+
+/// Placement of a character of text following a mask path
+#[derive(Debug, Clone)]
+pub struct TextPathInfo {
+    /// Mask path of the text layer, shared by all of its characters
+    pub path: Arc<TextPath>,
+    /// Distance from the start of the line to the character
+    pub position: f32,
+    /// Width of the line of the character
+    pub line_width: f32,
+    /// Distance from the path to the baseline of the line
+    pub line_offset: f32,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "ty")]
 pub enum Shape {
@@ -388,45 +759,71 @@ pub enum Shape {
     #[serde(rename = "tm")]
     Trim(Trim),
     #[serde(rename = "rd")]
-    RoundedCorners {
-        #[serde(rename = "r")]
-        radius: Animated<f32>,
-    },
+    RoundedCorners(RoundedCorners),
     #[serde(rename = "pb")]
-    PuckerBloat {
-        #[serde(rename = "a")]
-        amount: Animated<f32>,
-    },
+    PuckerBloat(PuckerBloat),
     #[serde(rename = "tw")]
-    Twist {
-        #[serde(rename = "a")]
-        angle: Animated<f32>,
-        #[serde(rename = "c")]
-        center: Animated<Vector2D>,
-    },
+    Twist(Twist),
     #[serde(rename = "mm")]
-    Merge {
-        #[serde(rename = "mm")]
-        mode: MergeMode,
-    },
+    Merge(MergePaths),
     #[serde(rename = "op")]
-    OffsetPath {
-        #[serde(rename = "a")]
-        amount: Animated<f32>,
-        #[serde(rename = "lj")]
-        line_join: LineJoin,
-        #[serde(rename = "ml")]
-        miter_limit: f32,
-    },
+    OffsetPath(OffsetPath),
     #[serde(rename = "zz")]
-    ZigZag {
-        #[serde(rename = "r")]
-        radius: Animated<f32>,
-        #[serde(rename = "s")]
-        distance: Animated<f32>,
-        #[serde(rename = "pt")]
-        ridges: Animated<f32>,
-    },
+    ZigZag(ZigZag),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoundedCorners {
+    #[serde(rename = "r")]
+    pub radius: Animated<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PuckerBloat {
+    /// Percentage, negative values pucker and positive values bloat
+    #[serde(rename = "a")]
+    pub amount: Animated<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Twist {
+    /// Rotation in degrees at the center
+    #[serde(rename = "a")]
+    pub angle: Animated<f32>,
+    #[serde(rename = "c")]
+    pub center: Animated<Vector2D>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OffsetPath {
+    #[serde(rename = "a")]
+    pub amount: Animated<f32>,
+    #[serde(rename = "lj")]
+    pub line_join: LineJoin,
+    #[serde(rename = "ml", default = "default_miter_limit")]
+    pub miter_limit: Animated<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MergePaths {
+    #[serde(rename = "mm")]
+    pub mode: MergeMode,
+    /// Items above the merge in its group, which are combined into a single
+    /// path. Filled in when iterating styled shapes
+    #[serde(skip)]
+    pub shapes: Vec<ShapeLayer>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ZigZag {
+    /// Number of ridges per segment
+    #[serde(rename = "r")]
+    pub frequency: Animated<f32>,
+    #[serde(rename = "s")]
+    pub amplitude: Animated<f32>,
+    /// 1 for corner points, 2 for smooth points
+    #[serde(rename = "pt")]
+    pub point_type: Animated<f32>,
 }
 
 #[derive(
@@ -470,9 +867,9 @@ pub enum LineJoin {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StrokeDash {
     #[serde(rename = "v")]
-    length: Animated<f32>,
+    pub length: Animated<f32>,
     #[serde(rename = "n")]
-    ty: StrokeDashType,
+    pub ty: StrokeDashType,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -506,13 +903,12 @@ pub enum TrimMultipleShape {
     Simultaneously = 2,
 }
 
-#[derive(
-    serde_repr::Serialize_repr, serde_repr::Deserialize_repr, Debug, Clone, Copy, PartialEq,
-)]
+/// Only 3 reverses shapes, other values are drawn clockwise like lottie-web
+#[derive(serde_repr::Serialize_repr, Debug, Clone, Copy, PartialEq)]
 #[repr(u8)]
 pub enum ShapeDirection {
     Clockwise = 1,
-    CounterClockwise = 2,
+    CounterClockwise = 3,
 }
 
 impl Default for ShapeDirection {
@@ -524,8 +920,11 @@ impl Default for ShapeDirection {
 #[derive(serde_repr::Serialize_repr, serde_repr::Deserialize_repr, Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum MergeMode {
-    #[serde(other)]
-    Unsupported = 1,
+    Merge = 1,
+    Add = 2,
+    Subtract = 3,
+    Intersect = 4,
+    ExcludeIntersections = 5,
 }
 
 #[derive(
@@ -586,6 +985,14 @@ pub enum TextBased {
     Lines = 4,
 }
 
+/// Units of the start, end and offset of a [TextRangeSelector]
+#[derive(serde_repr::Serialize_repr, serde_repr::Deserialize_repr, Debug, Clone, Copy)]
+#[repr(u8)]
+pub enum TextRangeUnits {
+    Percent = 1,
+    Index = 2,
+}
+
 #[derive(serde_repr::Serialize_repr, serde_repr::Deserialize_repr, Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum TextShape {
@@ -662,10 +1069,12 @@ impl Fill {
             opacity: Animated {
                 animated: false,
                 keyframes: vec![KeyFrame::from_value(0.0)],
+                expression: None,
             },
             color: Animated {
                 animated: false,
                 keyframes: vec![KeyFrame::from_value(Rgb::new_u8(0, 0, 0))],
+                expression: None,
             },
             fill_rule: FillRule::NonZero,
         }
@@ -677,11 +1086,13 @@ impl From<Rgba> for Fill {
         Fill {
             opacity: Animated {
                 animated: false,
-                keyframes: vec![KeyFrame::from_value(color.a as f32 / 255.0)],
+                keyframes: vec![KeyFrame::from_value(color.a as f32 / 255.0 * 100.0)],
+                expression: None,
             },
             color: Animated {
                 animated: false,
                 keyframes: vec![KeyFrame::from_value(Rgb::new_u8(color.r, color.g, color.b))],
+                expression: None,
             },
             fill_rule: FillRule::NonZero,
         }
@@ -695,13 +1106,13 @@ pub struct Stroke {
     #[serde(rename = "lj")]
     pub line_join: LineJoin,
     #[serde(rename = "ml", default)]
-    miter_limit: f32,
+    pub miter_limit: f32,
     #[serde(rename = "o")]
     pub opacity: Animated<f32>,
     #[serde(rename = "w")]
     pub width: Animated<f32>,
-    #[serde(rename = "d", default)]
-    dashes: Vec<StrokeDash>,
+    #[serde(rename = "d", default, skip_serializing_if = "Vec::is_empty")]
+    pub dashes: Vec<StrokeDash>,
     #[serde(rename = "c")]
     pub color: Animated<Rgb>,
 }
@@ -729,13 +1140,58 @@ pub struct Gradient {
     pub gradient_ty: GradientType,
     #[serde(rename = "g")]
     pub colors: ColorList,
+    /// Distance of the focal point of a radial gradient from its center, in
+    /// percentage of its radius
+    #[serde(rename = "h", default, skip_serializing_if = "Option::is_none")]
+    pub highlight_length: Option<Animated<f32>>,
+    /// Direction of the focal point, in degrees relative to the direction of
+    /// the gradient
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
+    pub highlight_angle: Option<Animated<f32>>,
+}
+
+impl Gradient {
+    /// Focal point of a radial gradient, which is where the first stop is
+    /// drawn. Same as the start point for linear gradients
+    pub fn focal_point(&self, frame: f32) -> Vector2D {
+        let start = self.start.value(frame);
+        let length = match (&self.gradient_ty, &self.highlight_length) {
+            (GradientType::Radial, Some(length)) => length.value(frame),
+            _ => return start,
+        };
+        let direction = self.end.value(frame) - start;
+        let angle = direction.y.atan2(direction.x)
+            + self
+                .highlight_angle
+                .as_ref()
+                .map(|a| a.value(frame))
+                .unwrap_or(0.0)
+                .to_radians();
+        // A focal point on the circle itself makes the gradient degenerate
+        let distance = direction.length() * (length / 100.0).clamp(-0.99, 0.99);
+        start + Vector2D::new(angle.cos(), angle.sin()) * distance
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.start.is_animated()
+            || self.end.is_animated()
+            || self.colors.colors.is_animated()
+            || self
+                .highlight_length
+                .as_ref()
+                .is_some_and(|h| h.is_animated())
+            || self
+                .highlight_angle
+                .as_ref()
+                .is_some_and(|a| a.is_animated())
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GradientFill {
     #[serde(rename = "o")]
     pub opacity: Animated<f32>,
-    #[serde(rename = "r")]
+    #[serde(rename = "r", default)]
     pub fill_rule: FillRule,
     #[serde(flatten)]
     pub gradient: Gradient,
@@ -747,14 +1203,14 @@ pub struct GradientStroke {
     pub line_cap: LineCap,
     #[serde(rename = "lj")]
     pub line_join: LineJoin,
-    #[serde(rename = "ml")]
-    miter_limit: f32,
+    #[serde(rename = "ml", default)]
+    pub miter_limit: f32,
     #[serde(rename = "o")]
     pub opacity: Animated<f32>,
     #[serde(rename = "w")]
     pub width: Animated<f32>,
-    #[serde(rename = "d", default)]
-    dashes: Vec<StrokeDash>,
+    #[serde(rename = "d", default, skip_serializing_if = "Vec::is_empty")]
+    pub dashes: Vec<StrokeDash>,
     #[serde(flatten)]
     pub gradient: Gradient,
 }
@@ -791,9 +1247,9 @@ pub struct PolyStar {
     pub outer_radius: Animated<f32>,
     #[serde(rename = "os")]
     pub outer_roundness: Animated<f32>,
-    #[serde(rename = "ir", default)]
+    #[serde(rename = "ir", default, skip_serializing_if = "Option::is_none")]
     pub inner_radius: Option<Animated<f32>>,
-    #[serde(rename = "is")]
+    #[serde(rename = "is", skip_serializing_if = "Option::is_none")]
     pub inner_roundness: Option<Animated<f32>>,
     #[serde(rename = "r")]
     pub rotation: Animated<f32>,
@@ -834,12 +1290,12 @@ pub struct Media {
         default
     )]
     pub embedded: bool,
-    id: String,
-    #[serde(rename = "nm", default)]
+    pub id: String,
+    #[serde(rename = "nm", default, skip_serializing_if = "Option::is_none")]
     name: Option<String>,
-    #[serde(rename = "w", default)]
+    #[serde(rename = "w", default, skip_serializing_if = "Option::is_none")]
     pub width: Option<u32>,
-    #[serde(rename = "h", default)]
+    #[serde(rename = "h", default, skip_serializing_if = "Option::is_none")]
     pub height: Option<u32>,
 }
 
@@ -847,9 +1303,9 @@ pub struct Media {
 pub struct Precomposition {
     pub id: String,
     pub layers: Vec<Layer>,
-    #[serde(rename = "nm")]
+    #[serde(rename = "nm", skip_serializing_if = "Option::is_none")]
     name: Option<String>,
-    #[serde(rename = "fr")]
+    #[serde(rename = "fr", skip_serializing_if = "Option::is_none")]
     pub frame_rate: Option<f32>,
 }
 
@@ -882,7 +1338,38 @@ pub struct Bezier {
     pub out_tangent: Vec<Vector2D>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+impl Bezier {
+    /// The same curve, going from its last point to its first one
+    pub fn reversed(&self) -> Bezier {
+        let reverse = |points: &Vec<Vector2D>| {
+            let mut points = points.iter().rev().copied().collect::<Vec<_>>();
+            // Closed curves keep their first point
+            if self.closed {
+                points.rotate_right(1);
+            }
+            points
+        };
+        Bezier {
+            closed: self.closed,
+            verticies: reverse(&self.verticies),
+            in_tangent: reverse(&self.out_tangent),
+            out_tangent: reverse(&self.in_tangent),
+        }
+    }
+
+    /// The same curve with every point multiplied by `factor`
+    pub fn scale(&self, factor: f32) -> Bezier {
+        let scale = |points: &Vec<Vector2D>| points.iter().map(|p| *p * factor).collect();
+        Bezier {
+            closed: self.closed,
+            verticies: scale(&self.verticies),
+            in_tangent: scale(&self.in_tangent),
+            out_tangent: scale(&self.out_tangent),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TextAnimationData {
     #[serde(rename = "a")]
     pub ranges: Vec<TextRange>,
@@ -891,82 +1378,97 @@ pub struct TextAnimationData {
     #[serde(rename = "m")]
     options: TextAlignmentOptions,
     #[serde(rename = "p")]
-    follow_path: TextFollowPath,
+    pub follow_path: TextFollowPath,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TextStyle {
-    #[serde(rename = "sw", default)]
-    stroke_width: Option<Animated<f32>>,
-    #[serde(rename = "sc", default)]
-    stroke_color: Option<Animated<Rgb>>,
-    #[serde(rename = "sh", default)]
-    stroke_hue: Option<Animated<f32>>,
-    #[serde(rename = "ss", default)]
-    stroke_saturation: Option<Animated<f32>>,
-    #[serde(rename = "sb", default)]
-    stroke_brightness: Option<Animated<f32>>,
-    #[serde(rename = "so", default)]
-    stroke_opacity: Option<Animated<f32>>,
-    #[serde(rename = "fc", default)]
-    fill_color: Option<Animated<Rgb>>,
-    #[serde(rename = "fh", default)]
-    fill_hue: Option<Animated<f32>>,
-    #[serde(rename = "fs", default)]
-    fill_saturation: Option<Animated<f32>>,
-    #[serde(rename = "fb", default)]
-    fill_brightness: Option<Animated<f32>>,
-    #[serde(rename = "t", default)]
+    #[serde(rename = "sw", default, skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<Animated<f32>>,
+    #[serde(rename = "sc", default, skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<Animated<Rgb>>,
+    #[serde(rename = "sh", default, skip_serializing_if = "Option::is_none")]
+    pub stroke_hue: Option<Animated<f32>>,
+    #[serde(rename = "ss", default, skip_serializing_if = "Option::is_none")]
+    pub stroke_saturation: Option<Animated<f32>>,
+    #[serde(rename = "sb", default, skip_serializing_if = "Option::is_none")]
+    pub stroke_brightness: Option<Animated<f32>>,
+    #[serde(rename = "so", default, skip_serializing_if = "Option::is_none")]
+    pub stroke_opacity: Option<Animated<f32>>,
+    #[serde(rename = "fc", default, skip_serializing_if = "Option::is_none")]
+    pub fill_color: Option<Animated<Rgb>>,
+    #[serde(rename = "fh", default, skip_serializing_if = "Option::is_none")]
+    pub fill_hue: Option<Animated<f32>>,
+    #[serde(rename = "fs", default, skip_serializing_if = "Option::is_none")]
+    pub fill_saturation: Option<Animated<f32>>,
+    #[serde(rename = "fb", default, skip_serializing_if = "Option::is_none")]
+    pub fill_brightness: Option<Animated<f32>>,
+    #[serde(rename = "t", default, skip_serializing_if = "Option::is_none")]
     pub letter_spacing: Option<Animated<f32>>,
-    #[serde(rename = "bl", default)]
-    blur: Option<Animated<f32>>,
-    #[serde(rename = "ls", default)]
+    #[serde(rename = "bl", default, skip_serializing_if = "Option::is_none")]
+    pub blur: Option<Animated<f32>>,
+    #[serde(rename = "ls", default, skip_serializing_if = "Option::is_none")]
     pub line_spacing: Option<Animated<f32>>,
-    #[serde(flatten)]
-    transform: Option<Transform>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<Transform>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TextRange {
-    #[serde(rename = "nm", default)]
-    name: Option<String>,
-    #[serde(rename = "a", default)]
+    #[serde(rename = "nm", default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
     pub style: Option<TextStyle>,
     #[serde(rename = "s")]
     pub selector: TextRangeSelector,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TextRangeSelector {
-    #[serde(rename = "t", deserialize_with = "bool_from_int")]
-    expressible: bool,
+    #[serde(
+        rename = "t",
+        deserialize_with = "bool_from_int",
+        serialize_with = "int_from_bool"
+    )]
+    pub expressible: bool,
     #[serde(rename = "xe")]
-    max_ease: Animated<f32>,
+    pub max_ease: Animated<f32>,
     #[serde(rename = "ne")]
-    min_ease: Animated<f32>,
+    pub min_ease: Animated<f32>,
     #[serde(rename = "a")]
-    max_amount: Animated<f32>,
+    pub max_amount: Animated<f32>,
     #[serde(rename = "b")]
-    based_on: TextBased,
-    #[serde(rename = "rn", deserialize_with = "bool_from_int")]
-    randomize: bool,
+    pub based_on: TextBased,
+    #[serde(
+        rename = "rn",
+        deserialize_with = "bool_from_int",
+        serialize_with = "int_from_bool"
+    )]
+    pub randomize: bool,
     #[serde(rename = "sh")]
-    shape: TextShape,
-    #[serde(rename = "o", default)]
-    offset: Option<Animated<f32>>,
+    pub shape: TextShape,
+    #[serde(rename = "o", default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<Animated<f32>>,
     #[serde(rename = "r")]
-    pub range_units: TextBased,
-    #[serde(rename = "sm", default)]
-    selector_smoothness: Option<Animated<f32>>,
-    #[serde(rename = "s", default)]
+    pub range_units: TextRangeUnits,
+    #[serde(rename = "sm", default, skip_serializing_if = "Option::is_none")]
+    pub selector_smoothness: Option<Animated<f32>>,
+    #[serde(rename = "s", default, skip_serializing_if = "Option::is_none")]
     pub start: Option<Animated<f32>>,
-    #[serde(rename = "e", default)]
+    #[serde(rename = "e", default, skip_serializing_if = "Option::is_none")]
     pub end: Option<Animated<f32>>,
+    /// Expression of an expression selector, giving the amount of each unit
+    #[serde(rename = "x", default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    /// Amount of each unit of an expression selector, evaluated when the
+    /// timeline is built with the `expressions` feature
+    #[serde(skip)]
+    pub expression_amounts: Vec<Animated<f32>>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TextData {
-    #[serde(rename = "x", default)]
+    #[serde(rename = "x", default, skip_serializing_if = "Option::is_none")]
     expression: Option<String>,
     #[serde(
         deserialize_with = "keyframes_from_array",
@@ -977,10 +1479,36 @@ pub struct TextData {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TextAlignmentOptions {}
+pub struct TextAlignmentOptions {
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
+    alignment: Option<Animated<Vector2D>>,
+    #[serde(rename = "g", default, skip_serializing_if = "Option::is_none")]
+    grouping: Option<f32>,
+}
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TextFollowPath {}
+/// Options of text laid out along the path of a layer mask
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TextFollowPath {
+    /// Index of the mask in `masksProperties`, text doesn't follow a path
+    /// when it's missing
+    #[serde(rename = "m", default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<usize>,
+    /// Distance from the start of the path to the text
+    #[serde(rename = "f", default, skip_serializing_if = "Option::is_none")]
+    pub first_margin: Option<Animated<f32>>,
+    /// Distance from the end of the path to the text
+    #[serde(rename = "l", default, skip_serializing_if = "Option::is_none")]
+    pub last_margin: Option<Animated<f32>>,
+    /// Characters are rotated along the path when not 0
+    #[serde(rename = "p", default, skip_serializing_if = "Option::is_none")]
+    pub perpendicular: Option<Animated<f32>>,
+    /// Characters are spread between the margins when not 0
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
+    pub force_alignment: Option<Animated<f32>>,
+    /// The path is followed from its end when not 0
+    #[serde(rename = "r", default, skip_serializing_if = "Option::is_none")]
+    pub reverse: Option<Animated<f32>>,
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TextDocument {
@@ -1003,24 +1531,28 @@ pub struct TextDocument {
         serialize_with = "array_from_rgba",
         default
     )]
-    stroke_color: Rgba,
+    pub stroke_color: Rgba,
     #[serde(rename = "sw", default)]
-    stroke_width: f32,
+    pub stroke_width: f32,
     #[serde(rename = "of", default)]
-    stroke_above_fill: bool,
-    #[serde(rename = "lh", default)]
-    line_height: Option<f32>,
+    pub stroke_above_fill: bool,
+    #[serde(rename = "lh", default, skip_serializing_if = "Option::is_none")]
+    pub line_height: Option<f32>,
     #[serde(rename = "j", default)]
     pub justify: TextJustify,
+    /// Extra space after each character, in thousandths of the font size
+    #[serde(rename = "tr", default)]
+    pub tracking: f32,
     #[serde(rename = "ls", default)]
     pub baseline_shift: f32,
-    // TODO:
-    #[serde(default)]
-    sz: Vec<f32>,
-    #[serde(default)]
-    ps: Vec<f32>,
-    #[serde(default)]
-    ca: TextCaps,
+    /// Width and height of the paragraph box, text is wrapped to its width
+    #[serde(rename = "sz", default, skip_serializing_if = "Vec::is_empty")]
+    pub box_size: Vec<f32>,
+    /// Top left corner of the paragraph box
+    #[serde(rename = "ps", default, skip_serializing_if = "Vec::is_empty")]
+    pub box_position: Vec<f32>,
+    #[serde(rename = "ca", default)]
+    pub caps: TextCaps,
 }
 
 impl Default for TextDocument {
@@ -1036,9 +1568,10 @@ impl Default for TextDocument {
             baseline_shift: 0.0,
             value: String::new(),
             justify: TextJustify::Left,
-            sz: vec![],
-            ps: vec![],
-            ca: TextCaps::Regular,
+            tracking: 0.0,
+            box_size: vec![],
+            box_position: vec![],
+            caps: TextCaps::Regular,
         }
     }
 }
@@ -1050,17 +1583,23 @@ pub struct Mask {
     #[serde(rename = "mn", default)]
     match_name: String,
     #[serde(rename = "inv", default)]
-    inverted: bool,
+    pub inverted: bool,
     #[serde(rename = "pt")]
     pub points: Animated<Vec<Bezier>>,
     #[serde(rename = "o")]
     pub opacity: Animated<f32>,
     pub mode: MaskMode,
-    #[serde(rename = "e", default)]
-    expand: Option<Animated<f32>>,
+    /// Distance the mask path is grown by, or shrunk by if negative
+    #[serde(
+        rename = "x",
+        alias = "e",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expand: Option<Animated<f32>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum MaskMode {
     #[serde(rename = "n")]
     None,
@@ -1078,12 +1617,380 @@ pub enum MaskMode {
     Difference,
 }
 
-fn mat4(anchor: Vector2D, position: Vector2D, scale: Vector2D, rotation: f32) -> Mat4 {
-    let anchor = Vec3::new(anchor.x, anchor.y, 0.0);
-    let scale = Vec3::new(scale.x, scale.y, 1.0);
-    let position = Vec3::new(position.x, position.y, 0.0);
-    Mat4::from_translation(position)
-        * Mat4::from_rotation_z(rotation * std::f32::consts::PI / 180.0)
-        * Mat4::from_scale(scale)
-        * Mat4::from_translation(-anchor)
+impl MaskMode {
+    /// Coverage a layer starts with when this is the mode of its first mask.
+    /// Masks that remove content start from a fully visible layer
+    pub fn initial_coverage(self) -> f32 {
+        match self {
+            MaskMode::Add | MaskMode::Lighten | MaskMode::Difference => 0.0,
+            MaskMode::None | MaskMode::Subtract | MaskMode::Intersect | MaskMode::Darken => 1.0,
+        }
+    }
+
+    /// Combine the `coverage` of previous masks with the coverage of this
+    /// `mask`, both in range `0.0..=1.0`
+    pub fn combine(self, coverage: f32, mask: f32) -> f32 {
+        match self {
+            MaskMode::None => coverage,
+            MaskMode::Add => coverage + mask - coverage * mask,
+            MaskMode::Subtract => coverage * (1.0 - mask),
+            MaskMode::Intersect => coverage * mask,
+            MaskMode::Lighten => coverage.max(mask),
+            MaskMode::Darken => coverage.min(mask),
+            MaskMode::Difference => (coverage - mask).abs(),
+        }
+    }
+}
+
+/// An effect applied to a layer, with its parameters in the order After
+/// Effects lists them
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Effect {
+    #[serde(rename = "ty")]
+    pub ty: EffectType,
+    #[serde(rename = "nm", default)]
+    pub name: String,
+    #[serde(rename = "mn", default)]
+    pub match_name: String,
+    #[serde(rename = "ix", default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    #[serde(
+        rename = "en",
+        deserialize_with = "bool_from_int",
+        serialize_with = "int_from_bool",
+        default = "default_true"
+    )]
+    pub enabled: bool,
+    #[serde(rename = "ef", default)]
+    pub values: Vec<EffectValue>,
+}
+
+impl Effect {
+    /// Numeric value of the parameter at `index`, for sliders, angles,
+    /// checkboxes, dropdowns and layer references
+    pub fn number(&self, index: usize, frame: f32) -> Option<f32> {
+        match &self.values.get(index)?.value {
+            EffectValueKind::Slider(v)
+            | EffectValueKind::Angle(v)
+            | EffectValueKind::Checkbox(v)
+            | EffectValueKind::Dropdown(v)
+            | EffectValueKind::Layer(v) => Some(v.value(frame)),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self, index: usize, frame: f32) -> Option<Rgb> {
+        match &self.values.get(index)?.value {
+            EffectValueKind::Color(v) => Some(v.value(frame)),
+            _ => None,
+        }
+    }
+
+    pub fn point(&self, index: usize, frame: f32) -> Option<Vector2D> {
+        match &self.values.get(index)?.value {
+            EffectValueKind::Point(v) => Some(v.value(frame)),
+            _ => None,
+        }
+    }
+
+    /// Whether any parameter of this effect changes over time
+    pub fn is_animated(&self) -> bool {
+        self.values.iter().any(|v| match &v.value {
+            EffectValueKind::Slider(v)
+            | EffectValueKind::Angle(v)
+            | EffectValueKind::Checkbox(v)
+            | EffectValueKind::Dropdown(v)
+            | EffectValueKind::Layer(v) => v.is_animated(),
+            EffectValueKind::Color(v) => v.is_animated(),
+            EffectValueKind::Point(v) => v.is_animated(),
+            EffectValueKind::Other { .. } => false,
+        })
+    }
+}
+
+/// Type of an [Effect]. Types without a variant are kept as
+/// [EffectType::Other], so they survive serialization
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EffectType {
+    Custom,
+    PaintOverTransparent,
+    Tint,
+    Fill,
+    Stroke,
+    Tritone,
+    ProLevels,
+    DropShadow,
+    RadialWipe,
+    DisplacementMap,
+    Matte3,
+    GaussianBlur,
+    Twirl,
+    MeshWarp,
+    Wavy,
+    Spherize,
+    Puppet,
+    Other(u8),
+}
+
+impl EffectType {
+    pub(crate) fn from_u8(ty: u8) -> Self {
+        match ty {
+            5 => EffectType::Custom,
+            7 => EffectType::PaintOverTransparent,
+            20 => EffectType::Tint,
+            21 => EffectType::Fill,
+            22 => EffectType::Stroke,
+            23 => EffectType::Tritone,
+            24 => EffectType::ProLevels,
+            25 => EffectType::DropShadow,
+            26 => EffectType::RadialWipe,
+            27 => EffectType::DisplacementMap,
+            28 => EffectType::Matte3,
+            29 => EffectType::GaussianBlur,
+            30 => EffectType::Twirl,
+            31 => EffectType::MeshWarp,
+            32 => EffectType::Wavy,
+            33 => EffectType::Spherize,
+            34 => EffectType::Puppet,
+            other => EffectType::Other(other),
+        }
+    }
+
+    pub(crate) fn to_u8(self) -> u8 {
+        match self {
+            EffectType::Custom => 5,
+            EffectType::PaintOverTransparent => 7,
+            EffectType::Tint => 20,
+            EffectType::Fill => 21,
+            EffectType::Stroke => 22,
+            EffectType::Tritone => 23,
+            EffectType::ProLevels => 24,
+            EffectType::DropShadow => 25,
+            EffectType::RadialWipe => 26,
+            EffectType::DisplacementMap => 27,
+            EffectType::Matte3 => 28,
+            EffectType::GaussianBlur => 29,
+            EffectType::Twirl => 30,
+            EffectType::MeshWarp => 31,
+            EffectType::Wavy => 32,
+            EffectType::Spherize => 33,
+            EffectType::Puppet => 34,
+            EffectType::Other(other) => other,
+        }
+    }
+}
+
+/// A single parameter of an [Effect]
+#[derive(Debug, Clone)]
+pub struct EffectValue {
+    pub name: String,
+    pub match_name: String,
+    pub index: Option<u32>,
+    pub value: EffectValueKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum EffectValueKind {
+    Slider(Animated<f32>),
+    /// Angle in degrees
+    Angle(Animated<f32>),
+    Color(Animated<Rgb>),
+    Point(Animated<Vector2D>),
+    /// Zero when unchecked
+    Checkbox(Animated<f32>),
+    /// One-based index of the selected option
+    Dropdown(Animated<f32>),
+    /// Index of the referenced layer, or of a mask for some effects
+    Layer(Animated<f32>),
+    /// Parameters with no meaningful value, like group markers, kept in raw
+    /// form
+    Other {
+        ty: u8,
+        value: Option<serde_json::Value>,
+    },
+}
+
+/// A layer style, applied to a layer once its effects are
+#[derive(Debug, Clone)]
+pub struct LayerStyle {
+    pub name: Option<String>,
+    pub match_name: Option<String>,
+    pub kind: LayerStyleKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum LayerStyleKind {
+    Stroke(StrokeStyle),
+    DropShadow(ShadowStyle),
+    InnerShadow(ShadowStyle),
+    OuterGlow(GlowStyle),
+    InnerGlow(GlowStyle),
+    BevelEmboss(Box<BevelEmbossStyle>),
+    Satin(SatinStyle),
+    ColorOverlay(ColorOverlayStyle),
+    GradientOverlay(GradientOverlayStyle),
+    /// Style types not supported yet, kept in raw form
+    Unknown(serde_json::Value),
+}
+
+/// Stroke drawn outside of the layer
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StrokeStyle {
+    #[serde(rename = "s", default)]
+    pub size: Animated<f32>,
+    #[serde(rename = "c")]
+    pub color: Animated<Rgb>,
+}
+
+/// Parameters of drop shadows and inner shadows
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShadowStyle {
+    #[serde(rename = "c")]
+    pub color: Animated<Rgb>,
+    #[serde(rename = "o", default = "default_number_100")]
+    pub opacity: Animated<f32>,
+    /// Angle of the light in degrees, counter-clockwise from the right. The
+    /// shadow is cast in the opposite direction
+    #[serde(rename = "a", default)]
+    pub angle: Animated<f32>,
+    /// Blur size
+    #[serde(rename = "s", default)]
+    pub size: Animated<f32>,
+    #[serde(rename = "d", default)]
+    pub distance: Animated<f32>,
+    /// Spread of drop shadows or choke of inner shadows, in percent
+    #[serde(rename = "ch", default)]
+    pub choke: Animated<f32>,
+    #[serde(rename = "bm", default, skip_serializing_if = "Option::is_none")]
+    pub blend_mode: Option<Animated<f32>>,
+    #[serde(rename = "no", default, skip_serializing_if = "Option::is_none")]
+    pub noise: Option<Animated<f32>>,
+    /// Whether the layer knocks out the drop shadow
+    #[serde(rename = "lc", default, skip_serializing_if = "Option::is_none")]
+    pub layer_conceal: Option<Animated<f32>>,
+}
+
+/// Parameters of outer glows and inner glows
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GlowStyle {
+    #[serde(rename = "c")]
+    pub color: Animated<Rgb>,
+    #[serde(rename = "o", default = "default_number_100")]
+    pub opacity: Animated<f32>,
+    #[serde(rename = "r", default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<Animated<f32>>,
+    #[serde(rename = "ch", default)]
+    pub choke: Animated<f32>,
+    #[serde(rename = "s", default)]
+    pub size: Animated<f32>,
+    #[serde(rename = "j", default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<Animated<f32>>,
+    #[serde(rename = "no", default, skip_serializing_if = "Option::is_none")]
+    pub noise: Option<Animated<f32>>,
+    #[serde(rename = "bm", default, skip_serializing_if = "Option::is_none")]
+    pub blend_mode: Option<Animated<f32>>,
+    /// Where inner glows start, from the center or the edges
+    #[serde(rename = "sr", default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Animated<f32>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BevelEmbossStyle {
+    #[serde(rename = "bs", default, skip_serializing_if = "Option::is_none")]
+    pub bevel_style: Option<Animated<f32>>,
+    #[serde(rename = "t", default, skip_serializing_if = "Option::is_none")]
+    pub technique: Option<Animated<f32>>,
+    #[serde(rename = "sr", default, skip_serializing_if = "Option::is_none")]
+    pub strength: Option<Animated<f32>>,
+    #[serde(rename = "s", default)]
+    pub size: Animated<f32>,
+    #[serde(rename = "sf", default, skip_serializing_if = "Option::is_none")]
+    pub soften: Option<Animated<f32>>,
+    #[serde(rename = "ga", default, skip_serializing_if = "Option::is_none")]
+    pub global_angle: Option<Animated<f32>>,
+    #[serde(rename = "a", default)]
+    pub angle: Animated<f32>,
+    #[serde(rename = "ll", default, skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<Animated<f32>>,
+    #[serde(rename = "hm", default, skip_serializing_if = "Option::is_none")]
+    pub highlight_mode: Option<Animated<f32>>,
+    #[serde(rename = "hc", default, skip_serializing_if = "Option::is_none")]
+    pub highlight_color: Option<Animated<Rgb>>,
+    #[serde(rename = "ho", default, skip_serializing_if = "Option::is_none")]
+    pub highlight_opacity: Option<Animated<f32>>,
+    #[serde(rename = "sm", default, skip_serializing_if = "Option::is_none")]
+    pub shadow_mode: Option<Animated<f32>>,
+    #[serde(rename = "sc", default, skip_serializing_if = "Option::is_none")]
+    pub shadow_color: Option<Animated<Rgb>>,
+    #[serde(rename = "so", default, skip_serializing_if = "Option::is_none")]
+    pub shadow_opacity: Option<Animated<f32>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SatinStyle {
+    #[serde(rename = "bm", default, skip_serializing_if = "Option::is_none")]
+    pub blend_mode: Option<Animated<f32>>,
+    #[serde(rename = "c")]
+    pub color: Animated<Rgb>,
+    #[serde(rename = "o", default = "default_number_100")]
+    pub opacity: Animated<f32>,
+    #[serde(rename = "a", default)]
+    pub angle: Animated<f32>,
+    #[serde(rename = "d", default)]
+    pub distance: Animated<f32>,
+    #[serde(rename = "s", default)]
+    pub size: Animated<f32>,
+    #[serde(rename = "in", default, skip_serializing_if = "Option::is_none")]
+    pub invert: Option<Animated<f32>>,
+}
+
+/// Paint the layer with a single color
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ColorOverlayStyle {
+    #[serde(rename = "bm", default, skip_serializing_if = "Option::is_none")]
+    pub blend_mode: Option<Animated<f32>>,
+    #[serde(rename = "c")]
+    pub color: Animated<Rgb>,
+    #[serde(rename = "so", default = "default_number_100")]
+    pub opacity: Animated<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GradientOverlayStyle {
+    #[serde(rename = "bm", default, skip_serializing_if = "Option::is_none")]
+    pub blend_mode: Option<Animated<f32>>,
+    #[serde(rename = "o", default = "default_number_100")]
+    pub opacity: Animated<f32>,
+    #[serde(rename = "gf", default, skip_serializing_if = "Option::is_none")]
+    pub gradient: Option<ColorList>,
+    #[serde(rename = "gs", default, skip_serializing_if = "Option::is_none")]
+    pub smoothness: Option<Animated<f32>>,
+    #[serde(rename = "a", default)]
+    pub angle: Animated<f32>,
+    #[serde(rename = "gt", default, skip_serializing_if = "Option::is_none")]
+    pub gradient_ty: Option<Animated<f32>>,
+    #[serde(rename = "re", default, skip_serializing_if = "Option::is_none")]
+    pub reverse: Option<Animated<f32>>,
+    #[serde(rename = "al", default, skip_serializing_if = "Option::is_none")]
+    pub align: Option<Animated<f32>>,
+    #[serde(rename = "s", default = "default_number_100")]
+    pub scale: Animated<f32>,
+    #[serde(rename = "of", default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<Animated<f32>>,
+}
+
+/// Skew by `skew` degrees along the axis rotated `axis` degrees from the x
+/// axis, as lottie-web's `skewFromAxis(-skew, axis)`
+fn skew_mat4(skew: f32, axis: f32) -> Mat4 {
+    if skew == 0.0 {
+        return Mat4::IDENTITY;
+    }
+    let axis = axis.to_radians();
+    let shear = Mat4::from_cols(
+        Vec4::X,
+        Vec4::new(-skew.to_radians().tan(), 1.0, 0.0, 0.0),
+        Vec4::Z,
+        Vec4::W,
+    );
+    Mat4::from_rotation_z(-axis) * shear * Mat4::from_rotation_z(axis)
 }
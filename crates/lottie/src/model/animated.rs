@@ -20,10 +20,17 @@ pub struct Animated<T> {
     #[serde(
         deserialize_with = "keyframes_from_array",
         serialize_with = "array_from_keyframes",
-        bound = "T: FromTo<helpers::Value>",
+        bound(
+            serialize = "T: FromTo<helpers::Value> + Clone",
+            deserialize = "T: FromTo<helpers::Value>"
+        ),
         rename = "k"
     )]
     pub keyframes: Vec<KeyFrame<T>>,
+    /// JavaScript expression driving this value, it is only evaluated with
+    /// the `expressions` feature
+    #[serde(rename = "x", default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
 }
 
 impl<T: Clone + Lerp<Target = T>> Animated<T> {
@@ -37,7 +44,9 @@ impl<T: Clone + Lerp<Target = T>> Animated<T> {
                 end_frame: 0.0,
                 easing_out: None,
                 easing_in: None,
+                hold: false,
             }],
+            expression: None,
         }
     }
 
@@ -111,6 +120,7 @@ where
         Self {
             animated: false,
             keyframes: vec![KeyFrame::default()],
+            expression: None,
         }
     }
 }
@@ -130,6 +140,10 @@ pub struct KeyFrame<T> {
     pub easing_out: Option<Easing>,
     #[serde(rename = "i", default)]
     pub easing_in: Option<Easing>,
+    /// The value jumps to the next keyframe instead of interpolating, its end
+    /// value is the start value
+    #[serde(skip)]
+    pub hold: bool,
 }
 
 impl<T: Clone> KeyFrame<T> {
@@ -141,6 +155,7 @@ impl<T: Clone> KeyFrame<T> {
             end_frame: 0.0,
             easing_out: None,
             easing_in: None,
+            hold: false,
         }
     }
 
@@ -152,6 +167,7 @@ impl<T: Clone> KeyFrame<T> {
             end_frame: self.end_frame,
             easing_out: self.easing_out.clone(),
             easing_in: self.easing_in.clone(),
+            hold: self.hold,
         }
     }
 }
@@ -234,6 +250,7 @@ impl<T: Clone + Lerp<Target = T>> KeyFrame<T> {
             }),
             start_frame: self.start_frame,
             end_frame: frame,
+            hold: self.hold,
         };
         let keyframe_b = KeyFrame {
             start_value: value,
@@ -248,6 +265,7 @@ impl<T: Clone + Lerp<Target = T>> KeyFrame<T> {
             }),
             start_frame: frame,
             end_frame: self.end_frame,
+            hold: self.hold,
         };
         (Some(keyframe_a), Some(keyframe_b))
     }
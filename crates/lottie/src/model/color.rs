@@ -13,10 +13,10 @@ pub struct Rgba {
 impl Rgba {
     pub fn new_f32(r: f32, g: f32, b: f32, a: f32) -> Rgba {
         Rgba {
-            r: (r * 255.0) as u8,
-            g: (g * 255.0) as u8,
-            b: (b * 255.0) as u8,
-            a: (a * 255.0) as u8,
+            r: (r * 255.0).round() as u8,
+            g: (g * 255.0).round() as u8,
+            b: (b * 255.0).round() as u8,
+            a: (a * 255.0).round() as u8,
         }
     }
 
@@ -51,7 +51,11 @@ impl FromStr for Rgba {
 
 impl ToString for Rgba {
     fn to_string(&self) -> String {
-        todo!()
+        if self.a == 255 {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
     }
 }
 
@@ -65,9 +69,9 @@ pub struct Rgb {
 impl Rgb {
     pub fn new_f32(r: f32, g: f32, b: f32) -> Rgb {
         Rgb {
-            r: (r * 255.0) as u8,
-            g: (g * 255.0) as u8,
-            b: (b * 255.0) as u8,
+            r: (r * 255.0).round() as u8,
+            g: (g * 255.0).round() as u8,
+            b: (b * 255.0).round() as u8,
         }
     }
 
@@ -79,7 +79,12 @@ pub fn array_from_rgba<S>(b: &Rgba, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let a = [b.r as f32, b.g as f32, b.b as f32, b.a as f32];
+    let a = [
+        b.r as f32 / 255.0,
+        b.g as f32 / 255.0,
+        b.b as f32 / 255.0,
+        b.a as f32 / 255.0,
+    ];
     let mut seq = serializer.serialize_seq(Some(a.len()))?;
     seq.serialize_element(&a[0])?;
     seq.serialize_element(&a[1])?;
@@ -88,6 +93,263 @@ where
     seq.end()
 }
 
+impl<'de> serde::Deserialize<'de> for ShapeDirection {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        Ok(match u8::deserialize(d)? {
+            3 => ShapeDirection::CounterClockwise,
+            _ => ShapeDirection::Clockwise,
+        })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for EffectType {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        Ok(EffectType::from_u8(u8::deserialize(d)?))
+    }
+}
+
+impl Serialize for EffectType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.to_u8())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for EffectValue {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(d)?;
+        let field = |value: &serde_json::Value, key: &str| {
+            value
+                .get(key)
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let name = field(&value, "nm");
+        let match_name = field(&value, "mn");
+        let index = value
+            .get("ix")
+            .and_then(serde_json::Value::as_u64)
+            .map(|ix| ix as u32);
+        let ty = value
+            .get("ty")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| D::Error::missing_field("ty"))? as u8;
+        let v = value.get_mut("v").map(serde_json::Value::take);
+        let number = |v: Option<serde_json::Value>| -> Result<Animated<f32>, D::Error> {
+            Animated::deserialize(v.unwrap_or_default()).map_err(D::Error::custom)
+        };
+        let value = match ty {
+            0 => EffectValueKind::Slider(number(v)?),
+            1 => EffectValueKind::Angle(number(v)?),
+            2 => EffectValueKind::Color(
+                Animated::deserialize(v.unwrap_or_default()).map_err(D::Error::custom)?,
+            ),
+            3 => EffectValueKind::Point(
+                Animated::deserialize(v.unwrap_or_default()).map_err(D::Error::custom)?,
+            ),
+            4 => EffectValueKind::Checkbox(number(v)?),
+            7 => EffectValueKind::Dropdown(number(v)?),
+            10 => EffectValueKind::Layer(number(v)?),
+            ty => EffectValueKind::Other { ty, value: v },
+        };
+        Ok(EffectValue {
+            name,
+            match_name,
+            index,
+            value,
+        })
+    }
+}
+
+impl Serialize for EffectValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum Value_<'a> {
+            Number(&'a Animated<f32>),
+            Color(&'a Animated<Rgb>),
+            Point(&'a Animated<Vector2D>),
+            Raw(&'a serde_json::Value),
+        }
+
+        #[derive(Serialize)]
+        struct EffectValue_<'a> {
+            ty: u8,
+            nm: &'a str,
+            mn: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            ix: Option<u32>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            v: Option<Value_<'a>>,
+        }
+
+        let (ty, v) = match &self.value {
+            EffectValueKind::Slider(v) => (0, Some(Value_::Number(v))),
+            EffectValueKind::Angle(v) => (1, Some(Value_::Number(v))),
+            EffectValueKind::Color(v) => (2, Some(Value_::Color(v))),
+            EffectValueKind::Point(v) => (3, Some(Value_::Point(v))),
+            EffectValueKind::Checkbox(v) => (4, Some(Value_::Number(v))),
+            EffectValueKind::Dropdown(v) => (7, Some(Value_::Number(v))),
+            EffectValueKind::Layer(v) => (10, Some(Value_::Number(v))),
+            EffectValueKind::Other { ty, value } => (*ty, value.as_ref().map(Value_::Raw)),
+        };
+        EffectValue_ {
+            ty,
+            nm: &self.name,
+            mn: &self.match_name,
+            ix: self.index,
+            v,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Position {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Split {
+            x: Animated<f32>,
+            y: Animated<f32>,
+            #[serde(default)]
+            z: Option<Animated<f32>>,
+        }
+
+        let value = serde_json::Value::deserialize(d)?;
+        let split = value
+            .get("s")
+            .is_some_and(|s| s.as_bool() == Some(true) || s.as_u64() == Some(1));
+        if split {
+            let Split { x, y, z } = Split::deserialize(value).map_err(D::Error::custom)?;
+            Ok(Position::Split { x, y, z })
+        } else {
+            Animated::deserialize(value)
+                .map(Position::Combined)
+                .map_err(D::Error::custom)
+        }
+    }
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Split_<'a> {
+            s: bool,
+            x: &'a Animated<f32>,
+            y: &'a Animated<f32>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            z: Option<&'a Animated<f32>>,
+        }
+
+        match self {
+            Position::Combined(position) => position.serialize(serializer),
+            Position::Split { x, y, z } => Split_ {
+                s: true,
+                x,
+                y,
+                z: z.as_ref(),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for LayerStyle {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(d)?;
+        let field = |key: &str| {
+            value
+                .get(key)
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string)
+        };
+        let name = field("nm");
+        let match_name = field("mn");
+        let ty = value.get("ty").and_then(serde_json::Value::as_u64);
+        let kind = match ty {
+            Some(0) => {
+                LayerStyleKind::Stroke(StrokeStyle::deserialize(value).map_err(D::Error::custom)?)
+            }
+            Some(1) => LayerStyleKind::DropShadow(
+                ShadowStyle::deserialize(value).map_err(D::Error::custom)?,
+            ),
+            Some(2) => LayerStyleKind::InnerShadow(
+                ShadowStyle::deserialize(value).map_err(D::Error::custom)?,
+            ),
+            Some(3) => {
+                LayerStyleKind::OuterGlow(GlowStyle::deserialize(value).map_err(D::Error::custom)?)
+            }
+            Some(4) => {
+                LayerStyleKind::InnerGlow(GlowStyle::deserialize(value).map_err(D::Error::custom)?)
+            }
+            Some(5) => LayerStyleKind::BevelEmboss(Box::new(
+                BevelEmbossStyle::deserialize(value).map_err(D::Error::custom)?,
+            )),
+            Some(6) => {
+                LayerStyleKind::Satin(SatinStyle::deserialize(value).map_err(D::Error::custom)?)
+            }
+            Some(7) => LayerStyleKind::ColorOverlay(
+                ColorOverlayStyle::deserialize(value).map_err(D::Error::custom)?,
+            ),
+            Some(8) => LayerStyleKind::GradientOverlay(
+                GradientOverlayStyle::deserialize(value).map_err(D::Error::custom)?,
+            ),
+            _ => LayerStyleKind::Unknown(value),
+        };
+        Ok(LayerStyle {
+            name,
+            match_name,
+            kind,
+        })
+    }
+}
+
+impl Serialize for LayerStyle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum LayerStyle_<'a> {
+            Stroke(&'a StrokeStyle),
+            Shadow(&'a ShadowStyle),
+            Glow(&'a GlowStyle),
+            BevelEmboss(&'a BevelEmbossStyle),
+            Satin(&'a SatinStyle),
+            ColorOverlay(&'a ColorOverlayStyle),
+            GradientOverlay(&'a GradientOverlayStyle),
+        }
+
+        #[derive(Serialize)]
+        struct TypedLayerStyle<'a> {
+            ty: u8,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            nm: Option<&'a String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            mn: Option<&'a String>,
+            #[serde(flatten)]
+            style: LayerStyle_<'a>,
+        }
+
+        let (ty, style) = match &self.kind {
+            LayerStyleKind::Stroke(s) => (0, LayerStyle_::Stroke(s)),
+            LayerStyleKind::DropShadow(s) => (1, LayerStyle_::Shadow(s)),
+            LayerStyleKind::InnerShadow(s) => (2, LayerStyle_::Shadow(s)),
+            LayerStyleKind::OuterGlow(s) => (3, LayerStyle_::Glow(s)),
+            LayerStyleKind::InnerGlow(s) => (4, LayerStyle_::Glow(s)),
+            LayerStyleKind::BevelEmboss(s) => (5, LayerStyle_::BevelEmboss(s)),
+            LayerStyleKind::Satin(s) => (6, LayerStyle_::Satin(s)),
+            LayerStyleKind::ColorOverlay(s) => (7, LayerStyle_::ColorOverlay(s)),
+            LayerStyleKind::GradientOverlay(s) => (8, LayerStyle_::GradientOverlay(s)),
+            LayerStyleKind::Unknown(value) => return value.serialize(serializer),
+        };
+        TypedLayerStyle {
+            ty,
+            nm: self.name.as_ref(),
+            mn: self.match_name.as_ref(),
+            style,
+        }
+        .serialize(serializer)
+    }
+}
+
 impl<'de> serde::Deserialize<'de> for LayerContent {
     fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
         let value = serde_json::Value::deserialize(d)?;
@@ -119,8 +381,10 @@ impl<'de> serde::Deserialize<'de> for LayerContent {
                         width: color.width,
                     }
                 }
-                2 | 6 => {
-                    LayerContent::MediaRef(MediaRef::deserialize(value).map_err(D::Error::custom)?)
+                ty @ (2 | 6) => {
+                    let mut media = MediaRef::deserialize(value).map_err(D::Error::custom)?;
+                    media.is_audio = ty == 6;
+                    LayerContent::MediaRef(media)
                 }
                 3 => LayerContent::Empty,
                 4 => {
@@ -132,14 +396,13 @@ impl<'de> serde::Deserialize<'de> for LayerContent {
                         .unwrap_or_default();
                     LayerContent::Shape(ShapeGroup { shapes })
                 }
-                #[cfg(feature = "text")]
                 5 => {
                     let v = value.get("t").ok_or_else(|| D::Error::missing_field("t"))?;
                     let v = TextAnimationData::deserialize(v).map_err(D::Error::custom)?;
-                    LayerContent::Text(v)
+                    LayerContent::Text(Box::new(v))
                 }
-                // 7 => LayerContent::Null(Type3::deserialize(value).unwrap()),
-                _type => LayerContent::Empty, //panic!("unsupported type {:?}", type_),
+                // Keep unsupported layers as is, so they survive serialization
+                _type => LayerContent::Unknown(value),
             },
         )
     }
@@ -153,9 +416,12 @@ impl Serialize for LayerContent {
         #[derive(Serialize)]
         #[serde(untagged)]
         enum LayerContent_<'a> {
-            // T1(&'a Type1),
+            PreCompositionRef(&'a PreCompositionRef),
             SolidColor { sc: String, sh: f32, sw: f32 },
+            MediaRef(&'a MediaRef),
+            Empty {},
             Shape { shapes: &'a Vec<ShapeLayer> },
+            Text { t: &'a TextAnimationData },
         }
 
         #[derive(Serialize)]
@@ -167,9 +433,9 @@ impl Serialize for LayerContent {
         }
 
         let msg = match self {
-            LayerContent::Shape(ShapeGroup { shapes }) => TypedLayerContent {
-                t: 4,
-                content: LayerContent_::Shape { shapes },
+            LayerContent::PreCompositionRef(r) => TypedLayerContent {
+                t: 0,
+                content: LayerContent_::PreCompositionRef(r),
             },
             LayerContent::SolidColor {
                 color,
@@ -183,7 +449,28 @@ impl Serialize for LayerContent {
                     sw: *width,
                 },
             },
-            _ => unimplemented!(),
+            LayerContent::MediaRef(r) => TypedLayerContent {
+                t: if r.is_audio { 6 } else { 2 },
+                content: LayerContent_::MediaRef(r),
+            },
+            LayerContent::Empty => TypedLayerContent {
+                t: 3,
+                content: LayerContent_::Empty {},
+            },
+            LayerContent::Shape(ShapeGroup { shapes }) => TypedLayerContent {
+                t: 4,
+                content: LayerContent_::Shape { shapes },
+            },
+            LayerContent::Text(t) => TypedLayerContent {
+                t: 5,
+                content: LayerContent_::Text { t },
+            },
+            LayerContent::Unknown(value) => return value.serialize(serializer),
+            LayerContent::Media(_) => {
+                return Err(serde::ser::Error::custom(
+                    "resolved media content has no lottie representation",
+                ))
+            }
         };
         msg.serialize(serializer)
     }
@@ -201,25 +488,16 @@ where
 pub fn array_from_keyframes<S, T>(b: &Vec<KeyFrame<T>>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
+    T: FromTo<Value> + Clone,
 {
-    todo!()
-    // let animated = AnimatedHelper::from(b);
-    // match animated {
-    //     AnimatedHelper::Plain(data) => data.serialize(serializer),
-    //     AnimatedHelper::AnimatedHelper(data) => {
-    //         let mut seq = serializer.serialize_seq(Some(data.len()))?;
-    //         for keyframe in data {
-    //             seq.serialize_element(&keyframe)?;
-    //         }
-    //         seq.end()
-    //     }
-    // }
+    AnimatedHelper::from(b).serialize(serializer)
 }
 
 pub fn default_vec2_100() -> Animated<Vector2D> {
     Animated {
         animated: false,
         keyframes: vec![KeyFrame::from_value(Vector2D::new(100.0, 100.0))],
+        expression: None,
     }
 }
 
@@ -227,6 +505,19 @@ pub fn default_number_100() -> Animated<f32> {
     Animated {
         animated: false,
         keyframes: vec![KeyFrame::from_value(100.0)],
+        expression: None,
+    }
+}
+
+pub fn default_true() -> bool {
+    true
+}
+
+pub fn default_miter_limit() -> Animated<f32> {
+    Animated {
+        animated: false,
+        keyframes: vec![KeyFrame::from_value(4.0)],
+        expression: None,
     }
 }
 
@@ -263,6 +554,16 @@ impl<'de> Visitor<'de> for NumberVistor {
     }
 }
 
+/// Sizes are sometimes exported as floating point numbers
+pub fn u32_from_number<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserializer
+        .deserialize_any(NumberVistor)?
+        .unwrap_or_default())
+}
+
 pub(crate) fn vec_from_array<'de, D>(deserializer: D) -> Result<Vec<Vector2D>, D::Error>
 where
     D: Deserializer<'de>,
@@ -305,47 +606,91 @@ pub(crate) struct ColorListHelper {
 impl From<ColorListHelper> for ColorList {
     fn from(helper: ColorListHelper) -> Self {
         let color_count = helper.color_count;
+        let keyframes = helper
+            .colors
+            .keyframes
+            .into_iter()
+            .map(|keyframe| {
+                let start = f32_to_gradient_colors(&keyframe.start_value, color_count);
+                let end = f32_to_gradient_colors(&keyframe.end_value, color_count);
+                keyframe.alter_value(start, end)
+            })
+            .collect::<Vec<_>>();
         ColorList {
-            color_count,
+            // Merged opacity stops are written back as color stops
+            color_count: keyframes
+                .first()
+                .map(|keyframe| keyframe.start_value.len())
+                .unwrap_or(color_count),
             colors: Animated {
                 animated: helper.colors.animated,
-                keyframes: helper
-                    .colors
-                    .keyframes
-                    .into_iter()
-                    .map(|keyframe| {
-                        let start = f32_to_gradient_colors(&keyframe.start_value, color_count);
-                        let end = f32_to_gradient_colors(&keyframe.end_value, color_count);
-                        keyframe.alter_value(start, end)
-                    })
-                    .collect(),
+                keyframes,
+                expression: helper.colors.expression,
             },
         }
     }
 }
 
+/// Bodymovin stores `color_count` color stops of `[offset, r, g, b]`,
+/// optionally followed by opacity stops of `[offset, a]`. Opacity stops don't
+/// have to match color stops, in which case they are merged into one stop
+/// per color stop and per opacity stop, so the number of stops only depends
+/// on the length of `data` and keyframes can be interpolated
 fn f32_to_gradient_colors(data: &Vec<f32>, color_count: usize) -> Vec<GradientColor> {
-    if data.len() == color_count * 4 {
-        // Rgb color
-        data.chunks(4)
-            .map(|chunk| GradientColor {
-                offset: chunk[0],
-                color: Rgba::new_f32(chunk[1], chunk[2], chunk[3], 1.0),
-            })
-            .collect()
-    } else if data.len() == color_count * 4 + color_count * 2 {
-        // Rgba color
-        (&data[0..(color_count * 4)])
-            .chunks(4)
-            .zip((&data[(color_count * 4)..]).chunks(2))
-            .map(|(chunk, opacity)| GradientColor {
-                offset: chunk[0],
-                color: Rgba::new_f32(chunk[1], chunk[2], chunk[3], opacity[1]),
-            })
-            .collect()
+    let split = (color_count * 4).min(data.len());
+    let colors = data[..split]
+        .chunks_exact(4)
+        .map(|chunk| (chunk[0], [chunk[1], chunk[2], chunk[3]]))
+        .collect::<Vec<_>>();
+    let opacities = data[split..]
+        .chunks_exact(2)
+        .map(|chunk| (chunk[0], [chunk[1]]))
+        .collect::<Vec<_>>();
+    let stop = |offset: f32| {
+        let [r, g, b] = interpolate_stops(&colors, offset).unwrap_or([0.0; 3]);
+        let [a] = interpolate_stops(&opacities, offset).unwrap_or([1.0]);
+        GradientColor {
+            offset,
+            color: Rgba::new_f32(r, g, b, a),
+        }
+    };
+    if opacities.is_empty() || opacities.len() == colors.len() {
+        colors.iter().map(|(offset, _)| stop(*offset)).collect()
     } else {
-        unimplemented!()
+        let mut offsets = colors
+            .iter()
+            .map(|(offset, _)| *offset)
+            .chain(opacities.iter().map(|(offset, _)| *offset))
+            .collect::<Vec<_>>();
+        offsets.sort_by(f32::total_cmp);
+        offsets.dedup();
+        offsets.into_iter().map(stop).collect()
+    }
+}
+
+/// Value of piecewise linear `stops` at `offset`, clamped to the first and
+/// last stops
+fn interpolate_stops<const N: usize>(stops: &[(f32, [f32; N])], offset: f32) -> Option<[f32; N]> {
+    let (first, last) = (stops.first()?, stops.last()?);
+    if offset <= first.0 {
+        return Some(first.1);
+    }
+    for pair in stops.windows(2) {
+        let ((o0, v0), (o1, v1)) = (pair[0], pair[1]);
+        if offset <= o1 {
+            let t = if o1 > o0 {
+                (offset - o0) / (o1 - o0)
+            } else {
+                1.0
+            };
+            let mut result = v0;
+            for (index, value) in result.iter_mut().enumerate() {
+                *value += (v1[index] - v0[index]) * t;
+            }
+            return Some(result);
+        }
     }
+    Some(last.1)
 }
 
 impl From<ColorList> for ColorListHelper {
@@ -364,6 +709,7 @@ impl From<ColorList> for ColorListHelper {
                         keyframe.alter_value(start, end)
                     })
                     .collect(),
+                expression: list.colors.expression,
             },
         }
     }
@@ -1,3 +1,5 @@
+use glam::Vec3;
+
 use super::{Bezier, Rgb, TextDocument, Value, Vector2D};
 
 pub trait FromTo<T> {
@@ -12,7 +14,19 @@ impl FromTo<Value> for Vector2D {
     }
 
     fn to(self) -> Value {
-        todo!()
+        Value::List(vec![self.x, self.y])
+    }
+}
+
+impl FromTo<Value> for Vec3 {
+    fn from(v: Value) -> Self {
+        let v = v.as_f32_vec().unwrap();
+        let get = |i: usize| v.get(i).cloned().unwrap_or(0.0);
+        Vec3::new(get(0), get(1), get(2))
+    }
+
+    fn to(self) -> Value {
+        Value::List(vec![self.x, self.y, self.z])
     }
 }
 
@@ -55,8 +69,13 @@ impl FromTo<Value> for Vec<Bezier> {
         }
     }
 
-    fn to(self) -> Value {
-        Value::ComplexBezier(self)
+    fn to(mut self) -> Value {
+        // A single plain path is not wrapped in an array
+        if self.len() == 1 {
+            Value::Bezier(self.remove(0))
+        } else {
+            Value::ComplexBezier(self)
+        }
     }
 }
 
@@ -1,14 +1,14 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use super::{Easing, FromTo, KeyFrame, Value};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(transparent)]
 pub(super) struct AnimatedHelper {
     data: TolerantAnimatedHelper,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(untagged)]
 enum TolerantAnimatedHelper {
     Plain(Value),
@@ -19,34 +19,90 @@ fn default_none<T>() -> Option<T> {
     None
 }
 
-#[derive(Deserialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
 struct LegacyKeyFrame<T> {
     #[serde(rename = "s")]
     start_value: T,
-    #[serde(rename = "e", default = "default_none")]
+    #[serde(
+        rename = "e",
+        default = "default_none",
+        skip_serializing_if = "Option::is_none"
+    )]
     end_value: Option<T>,
     #[serde(rename = "t", default)]
     start_frame: f32,
     #[serde(skip)]
     end_frame: f32,
-    #[serde(rename = "o", default)]
+    #[serde(rename = "o", default, skip_serializing_if = "Option::is_none")]
     easing_out: Option<Easing>,
-    #[serde(rename = "i", default)]
+    #[serde(rename = "i", default, skip_serializing_if = "Option::is_none")]
     easing_in: Option<Easing>,
-    #[serde(rename = "h", default, deserialize_with = "super::bool_from_int")]
+    #[serde(
+        rename = "h",
+        default,
+        deserialize_with = "super::bool_from_int",
+        serialize_with = "super::int_from_bool",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
     hold: bool,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(untagged)]
 enum LegacyTolerantKeyFrame {
     LegacyKeyFrame(LegacyKeyFrame<Value>),
     TOnly { t: f32 },
 }
 
-impl<'a, T> From<&'a Vec<KeyFrame<T>>> for AnimatedHelper {
-    fn from(_: &'a Vec<KeyFrame<T>>) -> Self {
-        todo!()
+/// Keyframe values are always wrapped in arrays, while a plain value is not
+fn keyframe_value(value: Value) -> Value {
+    match value {
+        Value::Primitive(v) => Value::List(vec![v]),
+        Value::Bezier(b) => Value::ComplexBezier(vec![b]),
+        v => v,
+    }
+}
+
+impl<'a, T> From<&'a Vec<KeyFrame<T>>> for AnimatedHelper
+where
+    T: FromTo<Value> + Clone,
+{
+    fn from(keyframes: &'a Vec<KeyFrame<T>>) -> Self {
+        if let [keyframe] = keyframes.as_slice() {
+            let value = keyframe.start_value.clone().to();
+            // Text documents are always keyframed
+            if keyframe.easing_in.is_none() && !matches!(value, Value::TextDocument(_)) {
+                return AnimatedHelper {
+                    data: TolerantAnimatedHelper::Plain(value),
+                };
+            }
+        }
+        let mut result = keyframes
+            .iter()
+            .map(|keyframe| {
+                LegacyTolerantKeyFrame::LegacyKeyFrame(LegacyKeyFrame {
+                    start_value: keyframe_value(keyframe.start_value.clone().to()),
+                    // Hold keyframes end with their start value
+                    end_value: (!keyframe.hold)
+                        .then(|| keyframe_value(keyframe.end_value.clone().to())),
+                    start_frame: keyframe.start_frame,
+                    end_frame: keyframe.end_frame,
+                    easing_out: keyframe.easing_out.clone(),
+                    easing_in: keyframe.easing_in.clone(),
+                    hold: keyframe.hold,
+                })
+            })
+            .collect::<Vec<_>>();
+        // End frame of the last keyframe can only be preserved with a `t` only
+        // keyframe
+        if let Some(last) = keyframes.last() {
+            if last.end_frame > last.start_frame {
+                result.push(LegacyTolerantKeyFrame::TOnly { t: last.end_frame });
+            }
+        }
+        AnimatedHelper {
+            data: TolerantAnimatedHelper::AnimatedHelper(result),
+        }
     }
 }
 
@@ -64,6 +120,7 @@ where
                     end_frame: 0.0,
                     easing_in: None,
                     easing_out: None,
+                    hold: false,
                 }]
             }
             TolerantAnimatedHelper::AnimatedHelper(v) => {
@@ -119,6 +176,7 @@ where
                         end_frame: keyframe.end_frame.max(keyframe.start_frame),
                         easing_in: keyframe.easing_in,
                         easing_out: keyframe.easing_out,
+                        hold: keyframe.hold,
                     })
                     .collect()
             }
@@ -1,4 +1,6 @@
-use crate::{font::FontDB, Lottie};
+use glam::Vec2;
+
+use crate::{font::FontDB, timeline::Timeline, Lottie};
 
 pub struct WindowConfig {
     pub show_controls: bool,
@@ -22,6 +24,36 @@ pub enum Config {
     Headless(HeadlessConfig),
 }
 
+/// A rendered frame in RGBA8 format, as produced by a [Renderer]
+pub struct FrameData {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub timestamp: i32,
+}
+
+/// A standalone image in RGBA8 format
+pub struct RgbaImage {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A backend able to rasterize any single frame of a [Timeline] on demand,
+/// see [Lottie::render_frame]
+pub trait FrameRenderer {
+    /// Rasterize `frame` into a `width` x `height` image, lottie coordinates
+    /// are multiplied by `scale`
+    fn render_frame(
+        &mut self,
+        timeline: &Timeline,
+        frame: f32,
+        width: u32,
+        height: u32,
+        scale: Vec2,
+    ) -> RgbaImage;
+}
+
 /// The fundamental trait that every renderer need to implement
 pub trait Renderer<F: FontDB> {
     /// Load a [Lottie] into this renderer
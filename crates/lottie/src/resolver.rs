@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use url::Url;
+
+use crate::Error;
+
+/// Loads external assets referenced by a lottie file, like images and fonts.
+/// Data urls are decoded without going through a resolver
+pub trait AssetResolver {
+    /// Fetch the content of `path`, which is the asset path as written in the
+    /// lottie file, e.g. `images/img_0.png` or `https://example.com/a.ttf`
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error>;
+}
+
+impl<R: AssetResolver + ?Sized> AssetResolver for &R {
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error> {
+        (**self).resolve(path)
+    }
+}
+
+fn is_remote(path: &str) -> bool {
+    matches!(Url::parse(path), Ok(url) if url.scheme() == "http" || url.scheme() == "https")
+}
+
+/// Resolves paths on the local filesystem, relative to a root directory.
+/// Paths escaping the root directory are rejected, so are remote urls
+#[derive(Debug, Clone)]
+pub struct FileResolver {
+    root: PathBuf,
+}
+
+impl FileResolver {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        FileResolver { root: root.into() }
+    }
+}
+
+impl AssetResolver for FileResolver {
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error> {
+        if is_remote(path) {
+            return Err(Error::AssetNotFound(path.to_string()));
+        }
+        // Absolute paths in lottie files are relative to the file itself
+        let relative = Path::new(path.trim_start_matches("file://"));
+        let mut result = self.root.clone();
+        for component in relative.components() {
+            match component {
+                Component::Normal(c) => result.push(c),
+                Component::RootDir | Component::CurDir => {}
+                Component::ParentDir | Component::Prefix(_) => {
+                    return Err(Error::AssetNotFound(path.to_string()))
+                }
+            }
+        }
+        // Symlinks within the root may point out of it
+        let result = result.canonicalize()?;
+        if !result.starts_with(self.root.canonicalize()?) {
+            return Err(Error::AssetNotFound(path.to_string()));
+        }
+        let mut file = std::fs::File::open(result)?;
+        let mut content = vec![];
+        file.read_to_end(&mut content)?;
+        Ok(content)
+    }
+}
+
+/// Largest response accepted by [NetworkResolver]
+const BUFFER_LIMIT: usize = 10 * 1_024 * 1_024;
+
+/// Fetches `http` and `https` urls with a blocking request. Responses larger
+/// than 10 MB are rejected
+#[derive(Debug, Clone, Default)]
+pub struct NetworkResolver;
+
+impl AssetResolver for NetworkResolver {
+    fn resolve(&self, url: &str) -> Result<Vec<u8>, Error> {
+        if !is_remote(url) {
+            return Err(Error::AssetNotFound(url.to_string()));
+        }
+        let response = ureq::get(url).call()?;
+        // The length isn't always sent, and can't be trusted when it is
+        let mut bytes = vec![];
+        response
+            .into_reader()
+            .take((BUFFER_LIMIT + 1) as u64)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() > BUFFER_LIMIT {
+            return Err(Error::NetworkResponseTooLarge(
+                url.to_string(),
+                BUFFER_LIMIT,
+            ));
+        }
+        Ok(bytes)
+    }
+}
+
+/// The resolver used by [crate::Lottie::from_reader], remote urls are
+/// fetched from the network and everything else is read from the filesystem
+#[derive(Debug, Clone)]
+pub struct DefaultResolver {
+    files: FileResolver,
+    network: NetworkResolver,
+}
+
+impl DefaultResolver {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        DefaultResolver {
+            files: FileResolver::new(root),
+            network: NetworkResolver,
+        }
+    }
+}
+
+impl AssetResolver for DefaultResolver {
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error> {
+        if is_remote(path) {
+            self.network.resolve(path)
+        } else {
+            self.files.resolve(path)
+        }
+    }
+}
+
+/// Serves assets from memory, keyed by their path. A leading `/` is ignored
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    assets: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, path: S, content: Vec<u8>) {
+        let path = path.into();
+        self.assets
+            .insert(path.trim_start_matches('/').to_string(), content);
+    }
+}
+
+impl AssetResolver for MemoryResolver {
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error> {
+        self.assets
+            .get(path.trim_start_matches('/'))
+            .cloned()
+            .ok_or_else(|| Error::AssetNotFound(path.to_string()))
+    }
+}
+
+/// Rejects remote urls before they reach the wrapped resolver, useful for
+/// sandboxing untrusted animations
+#[derive(Debug, Clone)]
+pub struct DenyNetwork<R>(pub R);
+
+impl<R: AssetResolver> AssetResolver for DenyNetwork<R> {
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error> {
+        if is_remote(path) {
+            return Err(Error::NetworkDenied(path.to_string()));
+        }
+        self.0.resolve(path)
+    }
+}
+
+/// Remembers assets fetched by the wrapped resolver, so repeated loads of the
+/// same path are served from memory. Failures are not cached
+#[derive(Debug)]
+pub struct CachingResolver<R> {
+    inner: R,
+    cache: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl<R> CachingResolver<R> {
+    pub fn new(inner: R) -> Self {
+        CachingResolver {
+            inner,
+            cache: Mutex::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: AssetResolver> AssetResolver for CachingResolver<R> {
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error> {
+        if let Some(content) = self.cache.lock().unwrap().get(path) {
+            return Ok(content.clone());
+        }
+        let content = self.inner.resolve(path)?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), content.clone());
+        Ok(content)
+    }
+}
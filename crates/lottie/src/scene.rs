@@ -0,0 +1,611 @@
+use glam::Mat4;
+use lyon_path::Path;
+
+use crate::layer::hierarchy::StagedLayerMask;
+use crate::layer::modifier::PathModifier;
+use crate::layer::shape::{
+    AnyFill, AnyStroke, DashPattern, PathFactory, StyledShape, StyledShapeIterator,
+};
+use crate::layer::staged::{RenderableContent, StagedLayer};
+use crate::layer::text::TextFrame;
+use crate::model::*;
+use crate::timeline::{Id, Timeline};
+
+/// A flattened snapshot of a [Timeline] at a given frame, everything is
+/// resolved so a backend only needs to rasterize it
+#[derive(Debug, Clone)]
+pub struct FrameScene {
+    pub frame: f32,
+    /// Visible layers in painting order, from back to front. Layers without
+    /// drawable content are omitted
+    pub layers: Vec<SceneLayer>,
+}
+
+impl FrameScene {
+    pub fn layer(&self, id: Id) -> Option<&SceneLayer> {
+        self.layers.iter().find(|layer| layer.id == id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneLayer {
+    /// Id of the [StagedLayer] this layer is evaluated from
+    pub id: Id,
+    /// Matte source layers are only referenced by other layers, and should
+    /// not be painted by themselves
+    pub is_mask: bool,
+    /// World matrix, including all parent transforms
+    pub transform: Mat4,
+    /// Accumulated opacity of this layer and its parents, from 0 to 1
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+    /// Mattes applied to this layer, a matte whose layer is not in the scene
+    /// is invisible at this frame
+    pub mattes: Vec<StagedLayerMask>,
+    /// Effects applied to the layer once its masks are, in order
+    pub effects: Vec<SceneEffect>,
+    /// Layer styles applied once effects are, color overlays first and drop
+    /// shadows last
+    pub styles: Vec<SceneLayerStyle>,
+    pub content: SceneContent,
+}
+
+#[derive(Debug, Clone)]
+pub enum SceneContent {
+    /// Shapes in painting order, from back to front
+    Shapes(Vec<SceneShape>),
+    /// An image of the given size, its data is the `Media` content of the
+    /// [StagedLayer] with the same id
+    Media { width: u32, height: u32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneShape {
+    /// Path with modifiers and trims applied, in shape space
+    pub path: Path,
+    /// Matrix from shape space to world space
+    pub transform: Mat4,
+    pub fill: Option<SceneFill>,
+    pub stroke: Option<SceneStroke>,
+    /// Standard deviation of the blur of the painted shape, only text
+    /// animators blur single shapes
+    pub blur: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneFill {
+    pub paint: ScenePaint,
+    pub fill_rule: FillRule,
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneStroke {
+    pub paint: ScenePaint,
+    pub width: f32,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+    pub miter_limit: f32,
+    /// `None` for solid strokes
+    pub dash: Option<DashPattern>,
+}
+
+/// Paint of a fill or stroke, with opacity of the style and its groups
+/// already multiplied into colors. Layer opacity is not included
+#[derive(Debug, Clone)]
+pub enum ScenePaint {
+    Solid(Rgba),
+    Gradient(SceneGradient),
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneGradient {
+    pub gradient_ty: GradientType,
+    pub start: Vector2D,
+    pub end: Vector2D,
+    /// Where the first stop of a radial gradient is drawn, see
+    /// [Gradient::focal_point]
+    pub focal: Vector2D,
+    pub stops: Vec<GradientColor>,
+}
+
+/// An [Effect] with its parameters evaluated. Colors and opacities are in
+/// range `0.0..=1.0`, lengths are in layer space
+#[derive(Debug, Clone)]
+pub enum SceneEffect {
+    /// Paint the layer with a single color, keeping its alpha
+    Fill { color: Rgb, opacity: f32 },
+    /// Map the luminance of the layer from `black` to `white`, and mix the
+    /// result with the layer by `amount`
+    Tint { black: Rgb, white: Rgb, amount: f32 },
+    /// Map the luminance of the layer from `shadows` to `midtones` to
+    /// `highlights`, and mix the result with the layer by `1 - blend`
+    Tritone {
+        highlights: Rgb,
+        midtones: Rgb,
+        shadows: Rgb,
+        blend: f32,
+    },
+    /// Draw the alpha of the layer below it, blurred and moved by `offset`
+    DropShadow {
+        color: Rgb,
+        opacity: f32,
+        offset: Vector2D,
+        /// Standard deviation of the blur
+        sigma: f32,
+        shadow_only: bool,
+    },
+    GaussianBlur {
+        /// Standard deviation of the blur
+        sigma: f32,
+        dimensions: BlurDimensions,
+    },
+    /// Stroke mask paths of the layer
+    Stroke {
+        /// Trimmed mask paths
+        paths: Vec<Path>,
+        color: Rgb,
+        opacity: f32,
+        width: f32,
+        style: StrokeEffectStyle,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlurDimensions {
+    Both,
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StrokeEffectStyle {
+    /// Stroke drawn over the layer
+    OnOriginal,
+    /// Only the stroke is drawn
+    OnTransparent,
+    /// The stroke reveals the layer below it
+    RevealOriginal,
+}
+
+impl SceneEffect {
+    /// Evaluate `effect` of a layer with `masks` at `frame`. Effect types
+    /// not supported yet are logged and skipped
+    pub fn new(effect: &Effect, masks: &[Mask], frame: f32) -> Option<Self> {
+        let number = |index| effect.number(index, frame).unwrap_or(0.0);
+        let color = |index| effect.color(index, frame).unwrap_or(Rgb::new_u8(0, 0, 0));
+        Some(match effect.ty {
+            EffectType::Fill => SceneEffect::Fill {
+                color: color(2),
+                opacity: effect.number(6, frame).unwrap_or(1.0).clamp(0.0, 1.0),
+            },
+            EffectType::Tint => SceneEffect::Tint {
+                black: color(0),
+                white: color(1),
+                amount: (number(2) / 100.0).clamp(0.0, 1.0),
+            },
+            EffectType::Tritone => SceneEffect::Tritone {
+                highlights: color(0),
+                midtones: color(1),
+                shadows: color(2),
+                blend: (number(3) / 100.0).clamp(0.0, 1.0),
+            },
+            EffectType::DropShadow => {
+                // Direction is clockwise from the top
+                let angle = (number(2) - 90.0).to_radians();
+                let distance = number(3);
+                SceneEffect::DropShadow {
+                    color: color(0),
+                    opacity: (number(1) / 255.0).clamp(0.0, 1.0),
+                    offset: Vector2D::new(angle.cos(), angle.sin()) * distance,
+                    sigma: number(4).max(0.0) / 4.0,
+                    shadow_only: number(5) != 0.0,
+                }
+            }
+            EffectType::GaussianBlur => SceneEffect::GaussianBlur {
+                sigma: number(0).max(0.0) * 0.3,
+                dimensions: match effect.number(1, frame).unwrap_or(1.0).round() as u32 {
+                    2 => BlurDimensions::Horizontal,
+                    3 => BlurDimensions::Vertical,
+                    _ => BlurDimensions::Both,
+                },
+            },
+            EffectType::Stroke => {
+                // Mask references are one-based
+                let masks = if number(1) != 0.0 {
+                    masks
+                } else {
+                    let index = (number(0).round() as usize).checked_sub(1)?;
+                    std::slice::from_ref(masks.get(index)?)
+                };
+                let paths = masks
+                    .iter()
+                    .map(|mask| mask.points.value(frame).path(frame))
+                    .collect::<Vec<_>>();
+                let trim = Trim {
+                    start: Animated::from_value(number(7)),
+                    end: Animated::from_value(effect.number(8, frame).unwrap_or(100.0)),
+                    offset: Animated::from_value(0.0),
+                    multiple_shape: TrimMultipleShape::Simultaneously,
+                };
+                let paths = if number(2) != 0.0 {
+                    trim.trim_paths(&paths, frame)
+                } else {
+                    paths
+                        .iter()
+                        .map(|path| trim.trim_path(path, frame))
+                        .collect()
+                };
+                SceneEffect::Stroke {
+                    paths,
+                    color: color(3),
+                    opacity: effect.number(6, frame).unwrap_or(1.0).clamp(0.0, 1.0),
+                    width: number(4),
+                    style: match effect.number(10, frame).unwrap_or(1.0).round() as u32 {
+                        2 => StrokeEffectStyle::OnTransparent,
+                        3 => StrokeEffectStyle::RevealOriginal,
+                        _ => StrokeEffectStyle::OnOriginal,
+                    },
+                }
+            }
+            ty => {
+                log::debug!("effect {:?} of type {:?} is not supported", effect.name, ty);
+                return None;
+            }
+        })
+    }
+}
+
+/// A [LayerStyle] with its parameters evaluated. Colors and opacities are
+/// in range `0.0..=1.0`, lengths are in layer space
+#[derive(Debug, Clone)]
+pub enum SceneLayerStyle {
+    /// Blend a single color over the layer
+    ColorOverlay { color: Rgb, opacity: f32 },
+    /// Stroke of the layer outline, drawn below the layer
+    Stroke { color: Rgb, width: f32 },
+    /// Blurred copy of the layer alpha, drawn below the layer and its stroke
+    DropShadow {
+        color: Rgb,
+        opacity: f32,
+        offset: Vector2D,
+        /// Standard deviation of the blur
+        sigma: f32,
+        /// Part of the blur made solid, from 0 to 1
+        spread: f32,
+    },
+}
+
+impl SceneLayerStyle {
+    /// Evaluate `style` at `frame`. Style types not supported yet are
+    /// logged and skipped
+    pub fn new(style: &LayerStyle, frame: f32) -> Option<Self> {
+        Some(match &style.kind {
+            LayerStyleKind::ColorOverlay(overlay) => SceneLayerStyle::ColorOverlay {
+                color: overlay.color.value(frame),
+                opacity: (overlay.opacity.value(frame) / 100.0).clamp(0.0, 1.0),
+            },
+            LayerStyleKind::Stroke(stroke) => SceneLayerStyle::Stroke {
+                color: stroke.color.value(frame),
+                width: stroke.size.value(frame).max(0.0),
+            },
+            LayerStyleKind::DropShadow(shadow) => {
+                // The shadow is cast away from the light, y goes down
+                let angle = shadow.angle.value(frame).to_radians();
+                let distance = shadow.distance.value(frame);
+                SceneLayerStyle::DropShadow {
+                    color: shadow.color.value(frame),
+                    opacity: (shadow.opacity.value(frame) / 100.0).clamp(0.0, 1.0),
+                    offset: Vector2D::new(-angle.cos(), angle.sin()) * distance,
+                    sigma: shadow.size.value(frame).max(0.0) / 2.0,
+                    spread: (shadow.choke.value(frame) / 100.0).clamp(0.0, 1.0),
+                }
+            }
+            _ => {
+                log::debug!("layer style {:?} is not supported", style.name);
+                return None;
+            }
+        })
+    }
+
+    /// Evaluate `styles` in the order they apply
+    fn evaluate(styles: &[LayerStyle], frame: f32) -> Vec<Self> {
+        let mut styles = styles
+            .iter()
+            .filter_map(|style| SceneLayerStyle::new(style, frame))
+            .collect::<Vec<_>>();
+        styles.sort_by_key(|style| match style {
+            SceneLayerStyle::ColorOverlay { .. } => 0,
+            SceneLayerStyle::Stroke { .. } => 1,
+            SceneLayerStyle::DropShadow { .. } => 2,
+        });
+        styles
+    }
+}
+
+impl Timeline {
+    /// Evaluate all layers at the given global `frame`
+    pub fn evaluate(&self, frame: f32) -> FrameScene {
+        let mut layers = self
+            .items()
+            .filter_map(|layer| {
+                Some((
+                    self.global_zindex(layer),
+                    self.evaluate_layer(layer, frame)?,
+                ))
+            })
+            .collect::<Vec<_>>();
+        // Lottie draws the first layer on top, which has the lowest zindex
+        layers.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        FrameScene {
+            frame,
+            layers: layers.into_iter().map(|(_, layer)| layer).collect(),
+        }
+    }
+
+    /// Zindex of `layer` in the whole animation. Zindex of layers are
+    /// relative to their parents, a higher zindex is drawn below
+    pub fn global_zindex(&self, layer: &StagedLayer) -> f32 {
+        self.ancestors(layer).map(|l| l.zindex).sum()
+    }
+
+    fn ancestors<'a>(&'a self, layer: &'a StagedLayer) -> impl Iterator<Item = &'a StagedLayer> {
+        std::iter::successors(Some(layer), |l| l.parent.and_then(|id| self.item(id)))
+    }
+
+    fn evaluate_layer(&self, layer: &StagedLayer, frame: f32) -> Option<SceneLayer> {
+        let local_frame = layer.frame_transform_hierarchy.value(frame)?;
+        let mut transform = Mat4::IDENTITY;
+        let mut opacity = 1.0;
+        // Parents are evaluated at their own local frame
+        for l in self.ancestors(layer) {
+            let frame = l.frame_transform_hierarchy.value(frame).unwrap_or(0.0);
+            transform = l.transform.value(frame) * transform;
+            opacity *= l.transform.opacity.value(frame) / 100.0;
+        }
+        let content = match &layer.content {
+            RenderableContent::Shape(group) => {
+                // Characters of text layers share the animators and the path of the
+                // layer
+                let text =
+                    first_text_range(&group.shapes).map(|info| TextFrame::new(info, local_frame));
+                let mut shapes = vec![];
+                evaluate_group(
+                    group,
+                    local_frame,
+                    Mat4::IDENTITY,
+                    1.0,
+                    &[],
+                    text.as_ref(),
+                    &mut shapes,
+                );
+                SceneContent::Shapes(shapes)
+            }
+            RenderableContent::Media(media) => SceneContent::Media {
+                width: media.width,
+                height: media.height,
+            },
+            RenderableContent::Group => return None,
+        };
+        Some(SceneLayer {
+            id: layer.id,
+            is_mask: layer.is_mask,
+            transform,
+            opacity,
+            blend_mode: layer.blend_mode,
+            mattes: layer.mask_hierarchy.masks().to_vec(),
+            effects: layer
+                .effects
+                .iter()
+                .filter_map(|effect| SceneEffect::new(effect, &layer.masks, local_frame))
+                .collect(),
+            styles: SceneLayerStyle::evaluate(&layer.styles, local_frame),
+            content,
+        })
+    }
+}
+
+/// Range info of the first character in `shapes`, when they are the glyphs
+/// of a text layer
+fn first_text_range(shapes: &[ShapeLayer]) -> Option<&TextRangeInfo> {
+    shapes.iter().find_map(|layer| match &layer.shape {
+        Shape::Group { shapes } => first_text_range(shapes),
+        Shape::Path { text_range, .. } => text_range.as_ref(),
+        _ => None,
+    })
+}
+
+fn evaluate_group(
+    group: &ShapeGroup,
+    frame: f32,
+    transform: Mat4,
+    opacity: f32,
+    parent_modifiers: &[Shape],
+    text: Option<&TextFrame>,
+    result: &mut Vec<SceneShape>,
+) {
+    let shapes = group.styled_shapes().collect::<Vec<_>>();
+    // Scene shapes of each styled shape, in painting order. Trims of this
+    // group apply once all of them are evaluated
+    let mut evaluated = shapes
+        .iter()
+        .map(|shape| {
+            let mut result = vec![];
+            if shape.shape.hidden {
+                return result;
+            }
+            let transform = transform * shape.transform.value(frame);
+            let opacity = opacity * shape.transform.opacity_value(frame) / 100.0;
+            // Modifiers of inner groups apply first
+            let mut modifiers = shape.modifiers.clone();
+            modifiers.extend(parent_modifiers.iter().cloned());
+            if let Shape::Group { shapes } = &shape.shape.shape {
+                let group = ShapeGroup {
+                    shapes: shapes.clone(),
+                };
+                evaluate_group(
+                    &group,
+                    frame,
+                    transform,
+                    opacity,
+                    &modifiers,
+                    text,
+                    &mut result,
+                );
+            } else if let Some(shape) =
+                evaluate_shape(shape, frame, transform, opacity, &modifiers, text)
+            {
+                result.push(shape);
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+    let mut trims = shapes
+        .iter()
+        .flat_map(|shape| shape.trims.iter())
+        .collect::<Vec<_>>();
+    trims.sort_by_key(|trim| trim.index);
+    trims.dedup_by_key(|trim| trim.index);
+    for trim in trims {
+        let targets = shapes
+            .iter()
+            .zip(evaluated.iter_mut())
+            .filter(|(shape, _)| shape.trims.iter().any(|t| t.index == trim.index))
+            // Scene shapes are in painting order, trims follow the group order
+            .flat_map(|(_, evaluated)| evaluated.iter_mut().rev())
+            .collect::<Vec<_>>();
+        match trim.trim.multiple_shape {
+            TrimMultipleShape::Individually => {
+                for shape in targets {
+                    shape.path = trim.trim.trim_path(&shape.path, frame);
+                }
+            }
+            TrimMultipleShape::Simultaneously => {
+                let paths = targets
+                    .iter()
+                    .map(|shape| shape.path.clone())
+                    .collect::<Vec<_>>();
+                let trimmed = trim.trim.trim_paths(&paths, frame);
+                for (shape, path) in targets.into_iter().zip(trimmed) {
+                    shape.path = path;
+                }
+            }
+        }
+    }
+    // The first shape in a group is drawn on top
+    result.extend(evaluated.into_iter().rev().flatten());
+}
+
+fn evaluate_shape(
+    shape: &StyledShape,
+    frame: f32,
+    transform: Mat4,
+    opacity: f32,
+    modifiers: &[Shape],
+    text: Option<&TextFrame>,
+) -> Option<SceneShape> {
+    // Glyphs of text layers are changed by the animators of their character
+    let animator = match (&shape.shape.shape, text) {
+        (
+            Shape::Path {
+                text_range: Some(info),
+                ..
+            },
+            Some(text),
+        ) => Some(info.value(frame, text)),
+        _ => None,
+    };
+    let transform = animator
+        .as_ref()
+        .map_or(transform, |animator| transform * animator.transform);
+    let opacity = opacity * animator.as_ref().map_or(1.0, |animator| animator.opacity);
+    let path = match &shape.shape.shape {
+        Shape::Ellipse(ellipse) => ellipse.path(frame),
+        Shape::PolyStar(star) => star.path(frame),
+        Shape::Rectangle(rect) => rect.path(frame),
+        Shape::Path { d, .. } => d.value(frame).path(frame),
+        Shape::Merge(merge) => merge.path(frame),
+        _ => return None,
+    };
+    let path = modifiers
+        .iter()
+        .fold(path, |path, modifier| modifier.modify(&path, frame));
+    let fill = match &shape.fill {
+        AnyFill::Solid(fill) => {
+            let opacity = opacity * fill.opacity.value(frame) / 100.0;
+            let mut color = fill.color.value(frame);
+            if let Some(animator) = animator.as_ref() {
+                color = animator.fill_color(color);
+            }
+            Some(SceneFill {
+                paint: ScenePaint::Solid(solid_color(color, opacity)),
+                fill_rule: fill.fill_rule,
+            })
+            .filter(|_| opacity > 0.0)
+        }
+        AnyFill::Gradient(fill) => {
+            let opacity = opacity * fill.opacity.value(frame) / 100.0;
+            Some(SceneFill {
+                paint: ScenePaint::Gradient(gradient(&fill.gradient, frame, opacity)),
+                fill_rule: fill.fill_rule,
+            })
+        }
+    };
+    let stroke = shape.stroke.as_ref().map(|stroke| {
+        let mut opacity = opacity * stroke.opacity().value(frame) / 100.0;
+        let mut width = stroke.width().value(frame);
+        if let Some(animator) = animator.as_ref() {
+            opacity *= animator.stroke_opacity;
+            width = animator.stroke_width(width);
+        }
+        let paint = match stroke {
+            AnyStroke::Solid(s) => {
+                let mut color = s.color.value(frame);
+                if let Some(animator) = animator.as_ref() {
+                    color = animator.stroke_color(color);
+                }
+                ScenePaint::Solid(solid_color(color, opacity))
+            }
+            AnyStroke::Gradient(g) => ScenePaint::Gradient(gradient(&g.gradient, frame, opacity)),
+        };
+        SceneStroke {
+            paint,
+            width,
+            line_cap: stroke.line_cap(),
+            line_join: stroke.line_join(),
+            miter_limit: stroke.miter_limit(),
+            dash: stroke.dash_pattern(frame),
+        }
+    });
+    Some(SceneShape {
+        path,
+        transform,
+        fill,
+        stroke,
+        blur: animator.map_or(0.0, |animator| animator.blur),
+    })
+}
+
+fn solid_color(color: Rgb, opacity: f32) -> Rgba {
+    let alpha = (opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgba::new_u8(color.r, color.g, color.b, alpha)
+}
+
+fn gradient(gradient: &Gradient, frame: f32, opacity: f32) -> SceneGradient {
+    let stops = gradient
+        .colors
+        .colors
+        .value(frame)
+        .into_iter()
+        .map(|mut stop| {
+            stop.color.a = (stop.color.a as f32 * opacity.clamp(0.0, 1.0)).round() as u8;
+            stop
+        })
+        .collect();
+    SceneGradient {
+        gradient_ty: gradient.gradient_ty,
+        start: gradient.start.value(frame),
+        end: gradient.end.value(frame),
+        focal: gradient.focal_point(frame),
+        stops,
+    }
+}
@@ -10,7 +10,7 @@ use crate::layer::frame::{FrameInfo, FrameTransformHierarchy};
 use crate::layer::hierarchy::TransformHierarchy;
 use crate::layer::staged::{ContentInfo, StagedLayer, TargetRef};
 use crate::prelude::{RenderableContent, StagedLayerMask};
-use crate::Error;
+use crate::{AssetResolver, Error};
 
 slotmap::new_key_type! {
     pub struct Id;
@@ -74,7 +74,15 @@ impl Timeline {
         self.store.get(id)
     }
 
-    pub(crate) fn new(model: &Model, fontdb: &impl FontDB, root_path: &str) -> Result<Self, Error> {
+    pub(crate) fn new(
+        model: &Model,
+        fontdb: &impl FontDB,
+        resolver: &dyn AssetResolver,
+    ) -> Result<Self, Error> {
+        #[cfg(feature = "expressions")]
+        let baked = crate::expression::bake(model)?;
+        #[cfg(feature = "expressions")]
+        let model = baked.as_ref().unwrap_or(model);
         let mut timeline = Timeline {
             start_frame: 0.0,
             end_frame: 0.0,
@@ -85,6 +93,9 @@ impl Timeline {
         let default_parent_map: Rc<RefCell<HashMap<u32, Id>>> = Rc::default();
         let default_standby_map: Rc<RefCell<HashMap<u32, Vec<Id>>>> = Rc::default();
         let mut force_zindex_ids = HashSet::new();
+        // Precomposition layer holding each item, as `parent` is replaced by
+        // the transform parent
+        let mut containers = HashMap::new();
         let mut layers = model
             .layers
             .iter()
@@ -169,7 +180,7 @@ impl Timeline {
                 _ => {}
             }
 
-            let content = ContentInfo::from_layer(layer.clone(), model, fontdb, root_path)?;
+            let content = ContentInfo::from_layer(layer.clone(), model, fontdb, resolver)?;
             let mut ids = vec![];
             match content {
                 ContentInfo::Simple(c) => ids.push(timeline.add_item(c.into_stage_layer(&layer))),
@@ -184,36 +195,45 @@ impl Timeline {
                         ids.push(timeline.add_item(layer))
                     }
                 }
-                ContentInfo::ContentWithMasks { content, masks } => {
-                    let (layers, matte_modes): (Vec<StagedLayer>, Vec<MatteMode>) = masks
-                        .into_iter()
-                        .map(|m| (m.0.into_stage_layer(&layer), m.1))
-                        .unzip();
-                    let mut target_layer = content.into_stage_layer(&layer);
-                    ids = layers
-                        .into_iter()
-                        .map(|mut layer| {
-                            layer.is_mask = true;
-                            timeline.add_item(layer)
-                        })
-                        .collect();
-                    for (id, matte_mode) in ids.iter().zip(matte_modes.into_iter()) {
-                        target_layer.mask_hierarchy.stack.push(StagedLayerMask {
-                            id: *id,
-                            mode: matte_mode,
-                        })
-                    }
-                    ids.push(timeline.add_item(target_layer));
+            }
+            // Masks are drawn before the content they apply to, and apply to
+            // every item of it
+            let masks = ContentInfo::masks(&layer)
+                .into_iter()
+                .map(|(content, mask)| {
+                    let mut staged = content.into_stage_layer(&layer);
+                    staged.is_mask = true;
+                    staged.matte_mode = None;
+                    staged.effects.clear();
+                    staged.styles.clear();
+                    (timeline.add_item(staged), mask)
+                })
+                .collect::<Vec<_>>();
+            for id in &ids {
+                let staged = timeline.store.get_mut(*id).unwrap();
+                for (mask_id, mask) in &masks {
+                    staged.mask_hierarchy.stack.push(StagedLayerMask {
+                        id: *mask_id,
+                        mode: MatteMode::Alpha,
+                        mask: Some(*mask),
+                    });
                 }
             }
+            ids.splice(0..0, masks.iter().map(|(id, _)| *id));
+            // Track mattes use the layer right above, even for layers
+            // staged as several items
+            let matte_source = previous;
             for id in &ids {
                 let matte_mode = timeline.store.get(*id).unwrap().matte_mode;
-                if let (Some(id), Some(mode)) = (previous, matte_mode) {
+                if let (Some(id), Some(mode)) = (matte_source, matte_mode) {
                     if mode != MatteMode::Normal {
                         let prev = timeline.store.get_mut(id).unwrap();
                         prev.is_mask = true;
                     }
                 }
+                if let Some(container) = parent {
+                    containers.insert(*id, container);
+                }
                 let staged = timeline.store.get_mut(*id).unwrap();
                 staged.target = target_ref.clone();
                 staged.parent = parent;
@@ -221,12 +241,13 @@ impl Timeline {
                 staged.frame_rate = default_frame_rate;
                 staged.frame_transform.time_remapping = time_remapping.clone();
                 staged.frame_transform.frame_rate = default_frame_rate;
-                if let (Some(id), Some(mode)) = (previous, matte_mode) {
+                if let (Some(id), Some(mode)) = (matte_source, matte_mode) {
                     if mode != MatteMode::Normal {
-                        staged
-                            .mask_hierarchy
-                            .stack
-                            .push(StagedLayerMask { id, mode });
+                        staged.mask_hierarchy.stack.push(StagedLayerMask {
+                            id,
+                            mode,
+                            mask: None,
+                        });
                     }
                 }
                 previous = Some(*id);
@@ -269,7 +290,7 @@ impl Timeline {
         timeline.fix_zindex(force_zindex_ids);
         timeline.build_opacity_hierarchy();
         timeline.build_frame_hierarchy();
-        timeline.build_mask_hierarchy();
+        timeline.build_mask_hierarchy(&containers);
 
         // dbg!(&timeline);
         Ok(timeline)
@@ -349,18 +370,25 @@ impl Timeline {
         }
     }
 
-    fn build_mask_hierarchy(&mut self) {
-        let ids = self.store.keys().collect::<Vec<_>>();
-        for id in ids {
-            let mut layer = self.store.get(id);
-            let mut info = vec![];
-            while let Some(l) = layer {
-                for mask in l.mask_hierarchy.masks() {
-                    info.push(*mask);
+    /// Masks of a precomposition layer apply to every layer inside it, unlike
+    /// the masks of transform parents. `containers` maps layers to the
+    /// precomposition layer holding them
+    fn build_mask_hierarchy(&mut self, containers: &HashMap<Id, Id>) {
+        let own = self
+            .store
+            .iter()
+            .map(|(id, layer)| (id, layer.mask_hierarchy.stack.clone()))
+            .collect::<HashMap<_, _>>();
+        for (id, layer) in self.store.iter_mut() {
+            let mut container = containers.get(&id);
+            while let Some(container_id) = container {
+                for mask in own.get(container_id).into_iter().flatten() {
+                    if !layer.mask_hierarchy.stack.iter().any(|m| m.id == mask.id) {
+                        layer.mask_hierarchy.stack.push(*mask);
+                    }
                 }
-                layer = l.parent.and_then(|id| self.store.get(id));
+                container = containers.get(container_id);
             }
-            self.store.get_mut(id).unwrap().mask_hierarchy.stack = info;
         }
     }
 }
@@ -0,0 +1,72 @@
+use std::fs;
+use std::io::{Cursor, Write};
+
+use lottie::prelude::{Asset, Model, RenderableContent};
+use lottie::{AssetResolver, DenyNetwork, DotLottie, Error, FileResolver, Lottie, MockFont};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+#[test]
+fn test_dotlottie_round_trip() {
+    let mut file =
+        fs::File::open("../../fixtures/ui/checked/NonAnimating/base64Test.json").unwrap();
+    let model = Model::from_reader(&mut file).unwrap();
+
+    let mut archive = DotLottie::new();
+    let resolver = DenyNetwork(FileResolver::new("../../"));
+    archive.add_animation("base64", &model, &resolver).unwrap();
+    let mut data = Cursor::new(vec![]);
+    archive.to_writer(&mut data).unwrap();
+
+    data.set_position(0);
+    let archive = DotLottie::from_reader(data).unwrap();
+    assert_eq!(archive.animation_ids().collect::<Vec<_>>(), vec!["base64"]);
+    assert_eq!(archive.default_animation_id(), Some("base64"));
+    assert!(archive.model("missing").is_err());
+
+    // Data urls are moved to `images/`, and resolved by the archive
+    let loaded = archive.model("base64").unwrap();
+    let media = match &loaded.assets[0] {
+        Asset::Media(media) => media,
+        _ => panic!("expecting a media asset"),
+    };
+    assert!(!media.embedded);
+    assert_eq!(media.pwd, "/images/");
+    assert_eq!(media.filename, "image_0.png");
+    let image = archive.resolve("/images/image_0.png").unwrap();
+    assert!(archive.resolve("/images/missing.png").is_err());
+
+    let lottie = Lottie::<MockFont>::from_dotlottie(&archive, "base64").unwrap();
+    assert_eq!(lottie.model.layers.len(), model.layers.len());
+    let content = lottie
+        .timeline()
+        .items()
+        .find_map(|layer| match &layer.content {
+            RenderableContent::Media(media) => Some(media.content.clone()),
+            _ => None,
+        });
+    assert_eq!(content, Some(image));
+}
+
+#[test]
+fn test_dotlottie_size_limit() {
+    let archive = |size: usize| {
+        let mut data = Cursor::new(vec![]);
+        let mut zip = ZipWriter::new(&mut data);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file("manifest.json", options).unwrap();
+        zip.write_all(br#"{ "animations": [] }"#).unwrap();
+        zip.start_file("images/image_0.png", options).unwrap();
+        zip.write_all(&vec![0; size]).unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+        data.set_position(0);
+        DotLottie::from_reader(data)
+    };
+    let limit = 16 * 1_024 * 1_024;
+    assert!(archive(limit).is_ok());
+    assert!(matches!(
+        archive(limit + 1),
+        Err(Error::DotLottieEntryTooLarge(name, _)) if name == "images/image_0.png"
+    ));
+}
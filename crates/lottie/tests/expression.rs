@@ -0,0 +1,168 @@
+#![cfg(feature = "expressions")]
+
+use std::fs;
+
+use lottie::prelude::{
+    Model, RenderableContent, Shape, ShapeLayer, StagedLayer, TextRangeInfo, Vector2D,
+};
+use lottie::{CharsDB, FileResolver, Lottie, MockFont};
+use serde_json::{json, Value};
+
+fn basic_layers() -> Value {
+    let file = fs::File::open("../../fixtures/ui/checked/NonAnimating/BasicLayers.json").unwrap();
+    serde_json::from_reader(file).unwrap()
+}
+
+fn layer<'a>(lottie: &'a Lottie<MockFont>, name: &str) -> &'a StagedLayer {
+    lottie
+        .timeline()
+        .items()
+        .find(|layer| layer.name.as_deref() == Some(name))
+        .unwrap()
+}
+
+#[test]
+fn test_bake_expressions() {
+    let mut json = basic_layers();
+    let transform = &mut json["layers"][0]["ks"];
+    transform["r"]["x"] = json!("$bm_rt = $bm_mul(time, 24);");
+    transform["o"]["x"] = json!("$bm_rt = clamp(linear(time, 0, 1, 0, 200), 0, 100);");
+    transform["p"]["x"] =
+        json!("$bm_rt = $bm_sum(thisComp.layer('SolidLayer').transform.anchorPoint, [10, 0]);");
+    let transform = &mut json["layers"][2]["ks"];
+    transform["r"] = json!({
+        "a": 1,
+        "k": [
+            { "t": 0, "s": [0], "o": { "x": [0], "y": [0] }, "i": { "x": [1], "y": [1] } },
+            { "t": 10, "s": [90] }
+        ],
+        "x": "$bm_rt = loopOut('cycle');"
+    });
+    // Strings can't be baked and are left untouched
+    transform["s"]["x"] = json!("$bm_rt = 'hello';");
+    let model: Model = serde_json::from_value(json).unwrap();
+
+    let lottie = Lottie::<MockFont>::new(model, MockFont, &FileResolver::new("../../")).unwrap();
+    // The model keeps the original expressions
+    assert!(lottie.model.layers[0]
+        .transform
+        .as_ref()
+        .unwrap()
+        .rotation
+        .expression
+        .is_some());
+
+    let shape = &layer(&lottie, "Shape Layer 1").transform;
+    assert_eq!(shape.rotation.value(12.0), 12.0);
+    assert_eq!(shape.rotation.value(60.5), 60.5);
+    assert_eq!(shape.opacity.value(6.0), 50.0);
+    assert_eq!(shape.opacity.value(30.0), 100.0);
+    assert_eq!(
+        shape.position.as_ref().unwrap().value(0.0),
+        Vector2D::new(110.0, 100.0)
+    );
+
+    let solid = &layer(&lottie, "SolidLayer").transform;
+    assert_eq!(solid.rotation.value(5.0), 45.0);
+    assert_eq!(solid.rotation.value(25.0), 45.0);
+    assert_eq!(solid.rotation.value(100.0), 0.0);
+    assert!(solid.scale.expression.is_some());
+}
+
+#[test]
+fn test_bake_layer_start_time() {
+    let mut json = basic_layers();
+    json["layers"][2]["st"] = json!(10);
+    // Keyframes are relative to the start of the layer, `time` isn't
+    json["layers"][2]["ks"]["r"] = json!({
+        "a": 1,
+        "k": [
+            { "t": 0, "s": [0], "o": { "x": [0], "y": [0] }, "i": { "x": [1], "y": [1] } },
+            { "t": 10, "s": [90] }
+        ],
+        "x": "$bm_rt = $bm_sum(value, $bm_mul(time, 24));"
+    });
+    let model: Model = serde_json::from_value(json).unwrap();
+
+    let lottie = Lottie::<MockFont>::new(model, MockFont, &FileResolver::new("../../")).unwrap();
+    let solid = &layer(&lottie, "SolidLayer").transform;
+    assert_eq!(solid.rotation.value(5.0), 60.0);
+    assert_eq!(solid.rotation.value(10.0), 110.0);
+}
+
+#[test]
+fn test_bake_runaway_expressions() {
+    let mut json = basic_layers();
+    let transform = &mut json["layers"][0]["ks"];
+    transform["r"]["x"] = json!("while (true) {}\n$bm_rt = 10;");
+    transform["o"]["x"] = json!("function f(n) { return f(n + 1); }\n$bm_rt = f(0);");
+    let model: Model = serde_json::from_value(json).unwrap();
+
+    // Expressions hitting the runtime limits are left unbaked
+    let lottie = Lottie::<MockFont>::new(model, MockFont, &FileResolver::new("../../")).unwrap();
+    let shape = &layer(&lottie, "Shape Layer 1").transform;
+    assert_eq!(shape.rotation.value(10.0), 0.0);
+    assert!(shape.rotation.keyframes.len() <= 1);
+    assert_eq!(shape.opacity.value(10.0), 100.0);
+    assert!(shape.opacity.keyframes.len() <= 1);
+}
+
+fn text_ranges(shapes: &[ShapeLayer], result: &mut Vec<TextRangeInfo>) {
+    for shape in shapes {
+        match &shape.shape {
+            Shape::Group { shapes } => text_ranges(shapes, result),
+            Shape::Path {
+                text_range: Some(info),
+                ..
+            } => result.push(info.clone()),
+            _ => {}
+        }
+    }
+}
+
+/// Selector amounts at `frame` of the glyphs of Text_Glyph.json with an
+/// expression selector of `expression`
+fn text_selector_amounts(expression: &str, frame: f32) -> Vec<Vec<f32>> {
+    let file = fs::File::open("../../fixtures/ui/checked/NonAnimating/Text_Glyph.json").unwrap();
+    let mut json: Value = serde_json::from_reader(file).unwrap();
+    json["layers"][0]["t"]["a"] = json!([{
+        "s": {
+            "t": 1,
+            "a": { "a": 0, "k": 50, "x": expression },
+            "b": 1, "rn": 0, "sh": 1, "r": 1,
+            "xe": { "a": 0, "k": 0 }, "ne": { "a": 0, "k": 0 }
+        },
+        "a": { "o": { "a": 0, "k": 0 } }
+    }]);
+    let model: Model = serde_json::from_value(json).unwrap();
+
+    let lottie =
+        Lottie::<CharsDB>::new(model, CharsDB::default(), &FileResolver::new("../../")).unwrap();
+    let text = lottie.timeline().items().next().unwrap();
+    let mut infos = vec![];
+    if let RenderableContent::Shape(group) = &text.content {
+        text_ranges(&group.shapes, &mut infos);
+    }
+    assert!(!infos.is_empty());
+    infos[0].animators.amounts(frame)[0].clone()
+}
+
+#[test]
+fn test_bake_text_selector() {
+    let amounts = text_selector_amounts(
+        "$bm_rt = $bm_mul($bm_div(textIndex, textTotal), 100);",
+        10.0,
+    );
+    assert_eq!(amounts, vec![vec![0.25, 0.5, 0.75, 1.0]]);
+}
+
+#[test]
+fn test_wiggle_text_selector() {
+    // Bodymovin doesn't export wiggly selectors, an expression selector
+    // wiggling its amount selects each character by a different amount
+    let amounts = text_selector_amounts("$bm_rt = wiggle(2, 50);", 10.0).remove(0);
+    assert!(amounts.iter().all(|amount| (0.0..=1.0).contains(amount)));
+    assert!(amounts.windows(2).any(|pair| pair[0] != pair[1]));
+    let later = text_selector_amounts("$bm_rt = wiggle(2, 50);", 20.0).remove(0);
+    assert_ne!(amounts, later);
+}
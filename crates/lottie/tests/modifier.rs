@@ -0,0 +1,314 @@
+use lottie::prelude::{
+    AnyFill, PathFactory, PathModifier, Rectangle, Shape, ShapeGroup, StyledShapeIterator,
+};
+use lyon_algorithms::aabb::bounding_box;
+use lyon_path::math::{point, Box2D};
+use lyon_path::{Event, Path};
+use serde_json::json;
+
+fn square() -> Path {
+    let rect: Rectangle = serde_json::from_value(json!({
+        "s": { "a": 0, "k": [100, 100] },
+        "p": { "a": 0, "k": [0, 0] },
+        "r": { "a": 0, "k": 0 }
+    }))
+    .unwrap();
+    rect.path(0.0)
+}
+
+fn modifier(json: serde_json::Value) -> Shape {
+    serde_json::from_value(json).unwrap()
+}
+
+fn count(path: &Path) -> (usize, usize) {
+    path.iter()
+        .fold((0, 0), |(lines, curves), event| match event {
+            Event::Line { .. } => (lines + 1, curves),
+            Event::Cubic { .. } | Event::Quadratic { .. } => (lines, curves + 1),
+            _ => (lines, curves),
+        })
+}
+
+fn assert_box(path: &Path, min: f32, max: f32) {
+    let b = bounding_box(path.iter());
+    let expected = Box2D::new(point(min, min), point(max, max));
+    assert!(
+        (b.min - expected.min).length() < 0.01 && (b.max - expected.max).length() < 0.01,
+        "{:?} != {:?}",
+        b,
+        expected
+    );
+}
+
+fn rectangle(radius: f32, direction: u8) -> Path {
+    let rect: Rectangle = serde_json::from_value(json!({
+        "d": direction,
+        "s": { "a": 0, "k": [100, 60] },
+        "p": { "a": 0, "k": [0, 0] },
+        "r": { "a": 0, "k": radius }
+    }))
+    .unwrap();
+    rect.path(0.0)
+}
+
+fn points(path: &Path) -> Vec<(f32, f32)> {
+    path.iter()
+        .filter_map(|event| match event {
+            Event::Begin { at } => Some(at),
+            Event::Line { to, .. } | Event::Cubic { to, .. } => Some(to),
+            _ => None,
+        })
+        .map(|p| (p.x.round(), p.y.round()))
+        .collect()
+}
+
+#[test]
+fn test_rectangle() {
+    // Starts at the top right corner, then goes down
+    let path = rectangle(0.0, 1);
+    assert_eq!(count(&path), (3, 0));
+    assert_eq!(
+        points(&path),
+        vec![(50.0, -30.0), (50.0, 30.0), (-50.0, 30.0), (-50.0, -30.0)]
+    );
+    // Counter-clockwise goes left
+    assert_eq!(
+        points(&rectangle(0.0, 3)),
+        vec![(50.0, -30.0), (-50.0, -30.0), (-50.0, 30.0), (50.0, 30.0)]
+    );
+
+    let path = rectangle(10.0, 1);
+    assert_eq!(count(&path), (4, 4));
+    assert_eq!(
+        bounding_box(path.iter()).round(),
+        Box2D::new(point(-50.0, -30.0), point(50.0, 30.0))
+    );
+    assert_eq!(
+        points(&path)[..3],
+        [(50.0, -20.0), (50.0, 20.0), (40.0, 30.0)]
+    );
+    let path = rectangle(10.0, 3);
+    assert_eq!(count(&path), (3, 4));
+    assert_eq!(
+        points(&path)[..3],
+        [(50.0, -20.0), (40.0, -30.0), (-40.0, -30.0)]
+    );
+
+    // Radius is limited to half of the shortest side, leaving no straight
+    // vertical edges
+    let path = rectangle(1000.0, 1);
+    assert_eq!(count(&path), (2, 4));
+    assert_eq!(
+        points(&path)[..3],
+        [(50.0, 0.0), (20.0, 30.0), (-20.0, 30.0)]
+    );
+}
+
+#[test]
+fn test_rounded_corners() {
+    let rounded = modifier(json!({ "ty": "rd", "r": { "a": 0, "k": 10 } }));
+    let path = rounded.modify(&square(), 0.0);
+    assert_eq!(count(&path), (4, 4));
+    assert_box(&path, -50.0, 50.0);
+    // Radius is limited to half of the shortest side
+    let rounded = modifier(json!({ "ty": "rd", "r": { "a": 0, "k": 1000 } }));
+    assert_eq!(count(&rounded.modify(&square(), 0.0)), (4, 4));
+}
+
+#[test]
+fn test_pucker_bloat() {
+    let bloat = modifier(json!({ "ty": "pb", "a": { "a": 0, "k": 50 } }));
+    let path = bloat.modify(&square(), 0.0);
+    assert_eq!(count(&path), (0, 4));
+    match path.iter().next() {
+        Some(Event::Begin { at }) => assert_eq!(at, point(25.0, -25.0)),
+        _ => panic!("expecting a subpath"),
+    }
+}
+
+#[test]
+fn test_twist() {
+    let twist = modifier(json!({
+        "ty": "tw",
+        "a": { "a": 0, "k": 90 },
+        "c": { "a": 0, "k": [0, 0] }
+    }));
+    let path = twist.modify(&square(), 0.0);
+    // Corners are the furthest points from the center and stay in place
+    match path.iter().next() {
+        Some(Event::Begin { at }) => assert!((at - point(50.0, -50.0)).length() < 0.01),
+        _ => panic!("expecting a subpath"),
+    }
+    assert_eq!(count(&path), (0, 32));
+}
+
+#[test]
+fn test_zig_zag() {
+    let zig_zag = modifier(json!({
+        "ty": "zz",
+        "r": { "a": 0, "k": 1 },
+        "s": { "a": 0, "k": 5 },
+        "pt": { "a": 0, "k": 1 }
+    }));
+    let path = zig_zag.modify(&square(), 0.0);
+    assert_eq!(count(&path), (8, 0));
+}
+
+#[test]
+fn test_offset_path() {
+    let offset = |amount: f32, join: u8| {
+        modifier(json!({
+            "ty": "op",
+            "a": { "a": 0, "k": amount },
+            "lj": join,
+            "ml": { "a": 0, "k": 4 }
+        }))
+        .modify(&square(), 0.0)
+    };
+    // Miter
+    assert_box(&offset(10.0, 1), -60.0, 60.0);
+    assert_box(&offset(-10.0, 1), -40.0, 40.0);
+    // Round
+    let path = offset(10.0, 2);
+    assert_eq!(count(&path), (4, 4));
+    assert_box(&path, -60.0, 60.0);
+    // Bevel
+    assert_eq!(count(&offset(10.0, 3)), (8, 0));
+}
+
+#[test]
+fn test_animated_modifiers() {
+    let offset = modifier(json!({
+        "ty": "op",
+        "a": { "a": 1, "k": [{ "t": 0, "s": [0] }, { "t": 10, "s": [10] }] },
+        "lj": 2,
+        "ml": { "a": 0, "k": 4 }
+    }));
+    assert!(offset.is_animated());
+    assert_box(&offset.modify(&square(), 10.0), -60.0, 60.0);
+    let rounded = modifier(json!({ "ty": "rd", "r": { "a": 0, "k": 10 } }));
+    assert!(!rounded.is_animated());
+}
+
+#[test]
+fn test_modifiers_of_styled_shapes() {
+    let group: ShapeGroup = serde_json::from_value(json!({
+        "shapes": [
+            {
+                "ty": "rc",
+                "s": { "a": 0, "k": [100, 100] },
+                "p": { "a": 0, "k": [0, 0] },
+                "r": { "a": 0, "k": 0 }
+            },
+            { "ty": "rd", "r": { "a": 0, "k": 10 } },
+            { "ty": "fl", "c": { "a": 0, "k": [1, 0, 0, 1] }, "o": { "a": 0, "k": 100 } },
+            { "ty": "pb", "a": { "a": 0, "k": 10 }, "hd": true },
+            { "ty": "tr" }
+        ]
+    }))
+    .unwrap();
+    let shapes = group.styled_shapes().collect::<Vec<_>>();
+    assert_eq!(shapes.len(), 1);
+    // Hidden modifiers are skipped
+    assert_eq!(shapes[0].modifiers.len(), 1);
+    let path = shapes[0].modify_path(square(), 0.0);
+    assert_eq!(count(&path), (4, 4));
+}
+
+fn rect(x: f32) -> serde_json::Value {
+    json!({
+        "ty": "rc",
+        "s": { "a": 0, "k": [100, 100] },
+        "p": { "a": 0, "k": [x, 0] },
+        "r": { "a": 0, "k": 0 }
+    })
+}
+
+fn merged(mode: u8) -> Path {
+    let group: ShapeGroup = serde_json::from_value(json!({
+        "shapes": [
+            rect(0.0),
+            { "ty": "fl", "c": { "a": 0, "k": [0, 0, 1, 1] }, "o": { "a": 0, "k": 100 } },
+            rect(50.0),
+            { "ty": "mm", "mm": mode },
+            { "ty": "fl", "c": { "a": 0, "k": [1, 0, 0, 1] }, "o": { "a": 0, "k": 100 } },
+            { "ty": "tr" }
+        ]
+    }))
+    .unwrap();
+    let shapes = group.styled_shapes().collect::<Vec<_>>();
+    // Both rectangles are drawn as a single shape, with the fill below
+    assert_eq!(shapes.len(), 1);
+    match (&shapes[0].shape.shape, &shapes[0].fill) {
+        (Shape::Merge(merge), AnyFill::Solid(fill)) => {
+            assert_eq!(merge.shapes.len(), 2);
+            assert_eq!(fill.color.value(0.0).r, 255);
+            merge.path(0.0)
+        }
+        _ => panic!("expecting a merged shape"),
+    }
+}
+
+fn assert_bounds(path: &Path, min: (f32, f32), max: (f32, f32)) {
+    let b = bounding_box(path.iter());
+    let expected = Box2D::new(point(min.0, min.1), point(max.0, max.1));
+    assert!(
+        (b.min - expected.min).length() < 0.01 && (b.max - expected.max).length() < 0.01,
+        "{:?} != {:?}",
+        b,
+        expected
+    );
+}
+
+fn subpaths(path: &Path) -> usize {
+    path.iter()
+        .filter(|event| matches!(event, Event::Begin { .. }))
+        .count()
+}
+
+#[test]
+fn test_merge_paths() {
+    let merge = merged(1);
+    assert_eq!(subpaths(&merge), 2);
+    assert_bounds(&merge, (-50.0, -50.0), (100.0, 50.0));
+
+    let add = merged(2);
+    assert_eq!(subpaths(&add), 1);
+    assert_bounds(&add, (-50.0, -50.0), (100.0, 50.0));
+
+    // Shapes above the last one are cut out of it
+    let subtract = merged(3);
+    assert_eq!(subpaths(&subtract), 1);
+    assert_bounds(&subtract, (50.0, -50.0), (100.0, 50.0));
+
+    let intersect = merged(4);
+    assert_eq!(subpaths(&intersect), 1);
+    assert_bounds(&intersect, (0.0, -50.0), (50.0, 50.0));
+
+    let exclude = merged(5);
+    assert_eq!(subpaths(&exclude), 2);
+    assert_bounds(&exclude, (-50.0, -50.0), (100.0, 50.0));
+}
+
+#[test]
+fn test_nested_merge_paths() {
+    // The intersection of the two first rectangles is cut out of the last one
+    let group: ShapeGroup = serde_json::from_value(json!({
+        "shapes": [
+            { "ty": "gr", "it": [rect(0.0), rect(50.0), { "ty": "mm", "mm": 4 }, { "ty": "tr" }] },
+            rect(25.0),
+            { "ty": "mm", "mm": 3 },
+            { "ty": "fl", "c": { "a": 0, "k": [1, 0, 0, 1] }, "o": { "a": 0, "k": 100 } },
+            { "ty": "tr" }
+        ]
+    }))
+    .unwrap();
+    let shapes = group.styled_shapes().collect::<Vec<_>>();
+    assert_eq!(shapes.len(), 1);
+    let path = match &shapes[0].shape.shape {
+        Shape::Merge(merge) => merge.path(0.0),
+        _ => panic!("expecting a merged shape"),
+    };
+    assert_eq!(subpaths(&path), 2);
+    assert_bounds(&path, (-25.0, -50.0), (75.0, 50.0));
+}
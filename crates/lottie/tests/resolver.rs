@@ -0,0 +1,98 @@
+use std::cell::Cell;
+use std::fs;
+
+use lottie::prelude::{Asset, Model, RenderableContent};
+use lottie::{
+    AssetResolver, CachingResolver, DenyNetwork, Error, FileResolver, Lottie, MemoryResolver,
+    MockFont,
+};
+
+/// Counts how many times the wrapped resolver is hit
+struct Counting<R> {
+    inner: R,
+    hits: Cell<usize>,
+}
+
+impl<R: AssetResolver> AssetResolver for Counting<R> {
+    fn resolve(&self, path: &str) -> Result<Vec<u8>, Error> {
+        self.hits.set(self.hits.get() + 1);
+        self.inner.resolve(path)
+    }
+}
+
+#[test]
+fn test_memory_resolver_as_network_stand_in() {
+    let mut file =
+        fs::File::open("../../fixtures/ui/checked/NonAnimating/base64Test.json").unwrap();
+    let mut model = Model::from_reader(&mut file).unwrap();
+    if let Asset::Media(media) = &mut model.assets[0] {
+        media.pwd = String::from("https://example.com/images/");
+        media.filename = String::from("img_0.png");
+        media.embedded = false;
+    }
+    let mut resolver = MemoryResolver::new();
+    resolver.insert("https://example.com/images/img_0.png", vec![1, 2, 3]);
+
+    let lottie = Lottie::<MockFont>::new(model.clone(), MockFont, &resolver).unwrap();
+    let content = lottie
+        .timeline()
+        .items()
+        .find_map(|layer| match &layer.content {
+            RenderableContent::Media(media) => Some(media.content.clone()),
+            _ => None,
+        });
+    assert_eq!(content, Some(vec![1, 2, 3]));
+
+    let result = Lottie::<MockFont>::new(model, MockFont, &DenyNetwork(&resolver));
+    assert!(matches!(result, Err(Error::NetworkDenied(_))));
+}
+
+#[test]
+fn test_file_resolver_stays_in_root() {
+    let resolver = FileResolver::new("../../fixtures/ui");
+    assert!(resolver
+        .resolve("checked/LottieFiles/bounce_strokes.json")
+        .is_ok());
+    // Absolute paths are relative to the root as well
+    assert!(resolver
+        .resolve("/checked/LottieFiles/bounce_strokes.json")
+        .is_ok());
+    assert!(resolver
+        .resolve("../ui/checked/LottieFiles/bounce_strokes.json")
+        .is_err());
+    assert!(resolver.resolve("https://example.com/a.png").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_file_resolver_rejects_symlinks_out_of_root() {
+    let root = std::env::temp_dir().join(format!("lottie-resolver-{}", std::process::id()));
+    fs::create_dir_all(root.join("images")).unwrap();
+    fs::write(root.join("images/inside.png"), [1]).unwrap();
+    let outside = fs::canonicalize("../../fixtures/ui/checked").unwrap();
+    let link = root.join("images/outside");
+    let _ = fs::remove_file(&link);
+    std::os::unix::fs::symlink(&outside, &link).unwrap();
+
+    let resolver = FileResolver::new(&root);
+    assert_eq!(resolver.resolve("images/inside.png").unwrap(), vec![1]);
+    let result = resolver.resolve("images/outside/LottieFiles/bounce_strokes.json");
+    fs::remove_dir_all(&root).unwrap();
+    assert!(matches!(result, Err(Error::AssetNotFound(_))));
+}
+
+#[test]
+fn test_caching_resolver() {
+    let mut memory = MemoryResolver::new();
+    memory.insert("images/a.png", vec![0]);
+    let resolver = CachingResolver::new(Counting {
+        inner: memory,
+        hits: Cell::new(0),
+    });
+    assert_eq!(resolver.resolve("images/a.png").unwrap(), vec![0]);
+    assert_eq!(resolver.resolve("images/a.png").unwrap(), vec![0]);
+    assert!(resolver.resolve("images/b.png").is_err());
+    assert!(resolver.resolve("images/b.png").is_err());
+    // Only the first load and the failures reach the inner resolver
+    assert_eq!(resolver.inner().hits.get(), 3);
+}
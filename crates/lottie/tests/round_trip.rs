@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lottie::prelude::Model;
+use serde_json::Value;
+
+/// Keys of the source files which the model doesn't keep
+const UNSUPPORTED_KEYS: &[&str] = &[
+    // Property indices and match names, only used by expressions
+    "ix",
+    "mn",
+    "cix",
+    "hix",
+    "ind",
+    // Number of properties of a shape group
+    "np",
+    // Keyframe names and spatial tangents of position keyframes
+    "n",
+    "ti",
+    "to",
+    "l",
+    // Layer and shape settings ignored by lottie renderers
+    "sr",
+    "ddd",
+    "ct",
+    "td",
+    "cl",
+    "bm",
+    "ml2",
+    "fillEnabled",
+    "hidden",
+    // Type of layer transforms, and names of stroke dashes
+    "ty",
+    "nm",
+    // Composition metadata
+    "markers",
+    "meta",
+];
+
+/// Colors and gradients are stored with 8 bits per channel, numbers may be
+/// off by one step
+const TOLERANCE: f64 = 1.0 / 255.0;
+
+fn json_files(dir: &Path, result: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            json_files(&path, result);
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            result.push(path);
+        }
+    }
+}
+
+fn to_value(model: &Model) -> Value {
+    let mut buf = vec![];
+    model.to_writer(&mut buf).unwrap();
+    serde_json::from_slice(&buf).unwrap()
+}
+
+fn has_null(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(items) => items.iter().any(has_null),
+        Value::Object(map) => map.values().any(has_null),
+        _ => false,
+    }
+}
+
+/// Values the model leaves out when serializing
+fn is_default(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Bool(b) => !b,
+        Value::Number(n) => n.as_f64() == Some(0.0),
+        Value::String(s) => s.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(_) => false,
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::Bool(b) => Some(*b as u8 as f64),
+        _ => None,
+    }
+}
+
+/// Numbers of a scalar or of an array of numbers
+fn numbers(value: &Value) -> Option<Vec<f64>> {
+    match value {
+        Value::Array(items) => items.iter().map(number).collect(),
+        value => number(value).map(|n| vec![n]),
+    }
+}
+
+/// `value` with only the first `count` color stops of its gradient colors
+fn color_stops(value: &Value, count: usize) -> Value {
+    match value {
+        Value::Array(items) if items.iter().all(Value::is_number) => {
+            Value::Array(items.iter().take(count * 4).cloned().collect())
+        }
+        Value::Array(items) => items.iter().map(|item| color_stops(item, count)).collect(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| (key.clone(), color_stops(value, count)))
+            .collect(),
+        value => value.clone(),
+    }
+}
+
+/// Differences between a source file and the serialized model, where values
+/// are normalized by the model:
+/// - keyframe values and easings are arrays even for a single number
+/// - 2D vectors drop the z component of 3D ones, and points of effects are
+///   zero filled
+/// - RGB colors drop an alpha of 1, and RGBA colors gain one
+/// - directions other than counter-clockwise (3) are written as clockwise
+/// - composition sizes are rounded to integers
+/// - opacity stops of gradients are merged into color stops, or dropped when
+///   they are opaque
+fn compare(key: &str, source: &Value, output: &Value, path: &str, diffs: &mut Vec<String>) {
+    match (source, output) {
+        (Value::Object(source), Value::Object(output)) => {
+            if key == "g" {
+                let count = source.get("p").and_then(Value::as_u64).unwrap_or(0) as usize;
+                if output.get("p").and_then(Value::as_u64) == Some(count as u64) {
+                    let (source, output) = (&source["k"], &output["k"]);
+                    let (source, output) = (color_stops(source, count), color_stops(output, count));
+                    compare("k", &source, &output, &format!("{}/k", path), diffs);
+                }
+                return;
+            }
+            for (key, value) in source {
+                match output.get(key) {
+                    Some(output) => {
+                        compare(key, value, output, &format!("{}/{}", path, key), diffs)
+                    }
+                    None if is_default(value) || UNSUPPORTED_KEYS.contains(&key.as_str()) => {}
+                    None => diffs.push(format!("{}/{} is missing", path, key)),
+                }
+            }
+        }
+        (Value::Array(source), Value::Array(output)) if source.len() == output.len() => {
+            for (index, (source, output)) in source.iter().zip(output).enumerate() {
+                compare(key, source, output, &format!("{}/{}", path, index), diffs);
+            }
+        }
+        _ => {
+            let equal = match (numbers(source), numbers(output)) {
+                (Some(_), Some(_)) if key == "d" => {
+                    (number(source) == Some(3.0)) == (number(output) == Some(3.0))
+                }
+                (Some(s), Some(o)) if key == "w" || key == "h" => {
+                    s.len() == 1 && o.len() == 1 && (s[0] - o[0]).abs() <= 0.5
+                }
+                (Some(s), Some(o)) => {
+                    let dropped_z = s.len() == 3 && o.len() == 2;
+                    let common = s.len().min(o.len());
+                    s.iter().zip(&o).all(|(s, o)| (s - o).abs() <= TOLERANCE)
+                        && (dropped_z || s[common..].iter().all(|n| *n == 0.0 || *n == 1.0))
+                        && o[common..].iter().all(|n| *n == 0.0 || *n == 1.0)
+                }
+                _ => source == output,
+            };
+            if !equal {
+                diffs.push(format!("{}: {} became {}", path, source, output));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_round_trip_fixtures() {
+    let mut files = vec![];
+    // Segments are parts of files, parsed by the unit tests
+    json_files(Path::new("../../fixtures/ui"), &mut files);
+    files.sort();
+    assert!(files.len() > 100, "only {} fixtures found", files.len());
+    for path in files {
+        let source: Value = serde_json::from_reader(fs::File::open(&path).unwrap()).unwrap();
+        let model: Model = serde_json::from_value(source.clone())
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let first = to_value(&model);
+        // lottie-web treats present keys as set, absent values must be omitted
+        assert!(!has_null(&first), "{}: null in output", path.display());
+        let mut diffs = vec![];
+        compare("", &source, &first, "", &mut diffs);
+        assert!(diffs.is_empty(), "{}: {:#?}", path.display(), diffs);
+
+        let reparsed: Model = serde_json::from_value(first.clone())
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let second = to_value(&reparsed);
+        assert_eq!(first, second, "{}", path.display());
+    }
+}
+
+#[test]
+fn test_round_trip_hold_keyframes() {
+    let source = serde_json::json!({
+        "a": 1,
+        "k": [
+            { "t": 0, "s": [0], "h": 1 },
+            { "t": 10, "s": [100] }
+        ]
+    });
+    let animated: lottie::prelude::Animated<f32> = serde_json::from_value(source).unwrap();
+    assert!(animated.keyframes[0].hold);
+    assert_eq!(animated.value(5.0), 0.0);
+    let output = serde_json::to_value(&animated).unwrap();
+    assert_eq!(output["k"][0]["h"], 1);
+    assert!(output["k"][1].get("h").is_none());
+}
@@ -0,0 +1,339 @@
+use std::fs;
+
+use lottie::prelude::{FrameScene, MaskMode, Model, SceneContent, SceneLayer, ScenePaint};
+use lottie::{FileResolver, Lottie, MockFont};
+use lyon_algorithms::measure::PathMeasurements;
+
+#[test]
+fn test_evaluate_basic_layers() {
+    let mut file =
+        fs::File::open("../../fixtures/ui/checked/NonAnimating/BasicLayers.json").unwrap();
+    let lottie = Lottie::<MockFont>::from_reader(&mut file, "../../").unwrap();
+    let timeline = lottie.timeline();
+    let name = |id| timeline.item(id).and_then(|layer| layer.name.clone());
+
+    let scene = timeline.evaluate(0.0);
+    // Null and text layers have nothing to draw, painting order is back to front
+    let names = scene
+        .layers
+        .iter()
+        .map(|layer| name(layer.id))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            Some("SolidLayer".to_string()),
+            Some("Shape Layer 1".to_string())
+        ]
+    );
+    let solid = &scene.layers[0];
+    assert_eq!(solid.opacity, 1.0);
+    match &solid.content {
+        SceneContent::Shapes(shapes) => {
+            assert_eq!(shapes.len(), 1);
+            assert!(shapes[0].fill.is_some());
+        }
+        _ => panic!("solid layer should be a shape"),
+    }
+
+    // All layers end at frame 120
+    assert!(timeline.evaluate(121.0).layers.is_empty());
+}
+
+#[test]
+fn test_evaluate_out_point() {
+    let lottie = basic_layers(|json| {
+        for layer in json["layers"].as_array_mut().unwrap() {
+            layer["ip"] = 10.into();
+            layer["op"] = 20.into();
+        }
+    });
+    let timeline = lottie.timeline();
+    // Layers show from their in point up to, not including, their out point,
+    // so consecutive layers don't overlap
+    assert!(timeline.evaluate(9.5).layers.is_empty());
+    assert_eq!(timeline.evaluate(10.0).layers.len(), 2);
+    assert_eq!(timeline.evaluate(19.5).layers.len(), 2);
+    assert!(timeline.evaluate(20.0).layers.is_empty());
+}
+
+#[test]
+fn test_evaluate_repeater() {
+    let lottie = shape_layer(serde_json::json!([
+        {
+            "ty": "gr",
+            "it": [
+                { "ty": "rc", "s": { "a": 0, "k": [10, 10] }, "p": { "a": 0, "k": [0, 0] }, "r": { "a": 0, "k": 0 } },
+                { "ty": "fl", "c": { "a": 0, "k": [1, 0, 0, 1] }, "o": { "a": 0, "k": 100 } },
+                { "ty": "tr" }
+            ]
+        },
+        {
+            "ty": "rp",
+            "c": { "a": 1, "k": [{ "t": 0, "s": [3], "h": 1 }, { "t": 10, "s": [2] }] },
+            "o": { "a": 0, "k": 0 },
+            "m": 1,
+            "tr": {
+                "ty": "tr",
+                "p": { "a": 0, "k": [20, 0] },
+                "s": { "a": 0, "k": [100, 100] },
+                "r": { "a": 0, "k": 0 },
+                "so": { "a": 0, "k": 100 },
+                "eo": { "a": 0, "k": 50 }
+            }
+        },
+        { "ty": "tr" }
+    ]));
+
+    let scene = lottie.timeline().evaluate(0.0);
+    let shapes = match &scene.layers[1].content {
+        SceneContent::Shapes(shapes) => shapes,
+        _ => panic!("expecting a shape layer"),
+    };
+    // Copies are composited above the original, fading towards the end
+    // opacity
+    let copies = shapes
+        .iter()
+        .map(|shape| {
+            let alpha = match &shape.fill.as_ref().unwrap().paint {
+                ScenePaint::Solid(color) => color.a,
+                _ => panic!("expecting a solid fill"),
+            };
+            (shape.transform.w_axis.x, alpha)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(copies, vec![(0.0, 255), (20.0, 191), (40.0, 128)]);
+
+    // The number of copies is animated
+    let scene = lottie.timeline().evaluate(11.0);
+    let alphas = match &scene.layers[1].content {
+        SceneContent::Shapes(shapes) => shapes
+            .iter()
+            .map(|s| s.fill.as_ref().map(|f| f.paint.clone())),
+        _ => panic!("expecting a shape layer"),
+    }
+    .filter(|paint| matches!(paint, Some(ScenePaint::Solid(c)) if c.a > 0))
+    .count();
+    assert_eq!(alphas, 2);
+}
+
+/// BasicLayers.json changed by `edit`
+fn basic_layers(edit: impl FnOnce(&mut serde_json::Value)) -> Lottie<MockFont> {
+    let file = fs::File::open("../../fixtures/ui/checked/NonAnimating/BasicLayers.json").unwrap();
+    let mut json: serde_json::Value = serde_json::from_reader(file).unwrap();
+    edit(&mut json);
+    let model: Model = serde_json::from_value(json).unwrap();
+    Lottie::<MockFont>::new(model, MockFont, &FileResolver::new("../../")).unwrap()
+}
+
+/// BasicLayers.json with the shapes of its first layer replaced
+fn shape_layer(shapes: serde_json::Value) -> Lottie<MockFont> {
+    basic_layers(|json| json["layers"][0]["shapes"] = shapes)
+}
+
+fn trimmed_lengths(trim: serde_json::Value) -> Vec<f32> {
+    let rect = |x: f32| {
+        serde_json::json!({
+            "ty": "gr",
+            "it": [
+                { "ty": "rc", "s": { "a": 0, "k": [100, 100] }, "p": { "a": 0, "k": [x, 0] }, "r": { "a": 0, "k": 0 } },
+                { "ty": "st", "c": { "a": 0, "k": [1, 0, 0, 1] }, "o": { "a": 0, "k": 100 }, "w": { "a": 0, "k": 2 }, "lc": 1, "lj": 1 },
+                { "ty": "tr" }
+            ]
+        })
+    };
+    let lottie = shape_layer(serde_json::json!([rect(0.0), rect(200.0), trim, { "ty": "tr" }]));
+    let scene = lottie.timeline().evaluate(0.0);
+    match &scene.layers[1].content {
+        SceneContent::Shapes(shapes) => shapes
+            .iter()
+            .map(|shape| {
+                PathMeasurements::from_path(&shape.path, 1e-3)
+                    .length()
+                    .round()
+            })
+            .collect(),
+        _ => panic!("expecting a shape layer"),
+    }
+}
+
+#[test]
+fn test_evaluate_trims() {
+    let trim = |start: f32, end: f32, offset: f32, mode: u8| {
+        serde_json::json!({
+            "ty": "tm",
+            "s": { "a": 0, "k": start },
+            "e": { "a": 0, "k": end },
+            "o": { "a": 0, "k": offset },
+            "m": mode
+        })
+    };
+    // Shapes are in painting order, the last one is the first in the group
+    assert_eq!(trimmed_lengths(trim(0.0, 75.0, 0.0, 1)), vec![300.0, 300.0]);
+    assert_eq!(trimmed_lengths(trim(0.0, 75.0, 0.0, 2)), vec![200.0, 400.0]);
+    assert_eq!(trimmed_lengths(trim(25.0, 50.0, 0.0, 2)), vec![0.0, 200.0]);
+    // The offset wraps around the end of the combined path
+    assert_eq!(
+        trimmed_lengths(trim(0.0, 50.0, 270.0, 2)),
+        vec![200.0, 200.0]
+    );
+    assert_eq!(
+        trimmed_lengths(trim(0.0, 100.0, 90.0, 2)),
+        vec![400.0, 400.0]
+    );
+    assert_eq!(trimmed_lengths(trim(50.0, 50.0, 0.0, 1)), vec![0.0, 0.0]);
+}
+
+#[test]
+fn test_evaluate_dashes() {
+    let lottie = shape_layer(serde_json::json!([
+        { "ty": "rc", "s": { "a": 0, "k": [100, 100] }, "p": { "a": 0, "k": [0, 0] }, "r": { "a": 0, "k": 0 } },
+        {
+            "ty": "st",
+            "c": { "a": 0, "k": [1, 0, 0, 1] },
+            "o": { "a": 0, "k": 100 },
+            "w": { "a": 0, "k": 2 },
+            "lc": 1,
+            "lj": 1,
+            "d": [
+                { "n": "d", "v": { "a": 0, "k": 10 } },
+                { "n": "g", "v": { "a": 0, "k": 5 } },
+                { "n": "d", "v": { "a": 0, "k": 1 } },
+                { "n": "o", "v": { "a": 1, "k": [{ "t": 0, "s": [0], "o": { "x": [0], "y": [0] }, "i": { "x": [1], "y": [1] } }, { "t": 20, "s": [40] }] } }
+            ]
+        },
+        { "ty": "tr" }
+    ]));
+    let dash = |frame| match &lottie.timeline().evaluate(frame).layers[1].content {
+        SceneContent::Shapes(shapes) => shapes[0].stroke.as_ref().unwrap().dash.clone(),
+        _ => panic!("expecting a shape layer"),
+    };
+    let dash = dash(10.0).unwrap();
+    // An odd number of lengths is repeated
+    assert_eq!(dash.array, vec![10.0, 5.0, 1.0, 10.0, 5.0, 1.0]);
+    assert_eq!(dash.offset, 20.0);
+}
+
+fn mask(mode: &str, inverted: bool, expand: f32) -> serde_json::Value {
+    serde_json::json!({
+        "inv": inverted,
+        "mode": mode,
+        "pt": { "a": 0, "k": {
+            "i": [[0, 0], [0, 0], [0, 0], [0, 0]],
+            "o": [[0, 0], [0, 0], [0, 0], [0, 0]],
+            "v": [[0, 0], [100, 0], [100, 100], [0, 100]],
+            "c": true
+        } },
+        "o": { "a": 0, "k": 100 },
+        "x": { "a": 0, "k": expand },
+        "nm": "Mask"
+    })
+}
+
+/// Layer named `name` in `scene`, other than the masks
+fn scene_layer<'a>(lottie: &Lottie<MockFont>, scene: &'a FrameScene, name: &str) -> &'a SceneLayer {
+    scene
+        .layers
+        .iter()
+        .filter(|layer| !layer.is_mask)
+        .find(|layer| {
+            lottie
+                .timeline()
+                .item(layer.id)
+                .and_then(|l| l.name.as_deref())
+                == Some(name)
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_evaluate_masks() {
+    let lottie = basic_layers(|json| {
+        // Masks on the solid layer, the one with mode "n" is ignored
+        json["layers"][2]["hasMask"] = true.into();
+        json["layers"][2]["masksProperties"] = serde_json::json!([
+            mask("a", false, 0.0),
+            mask("n", false, 0.0),
+            mask("s", true, 0.0),
+            mask("i", false, 10.0)
+        ]);
+        // Children of the solid layer don't inherit its masks
+        json["layers"][0]["parent"] = 3.into();
+    });
+    let scene = lottie.timeline().evaluate(0.0);
+    assert!(scene_layer(&lottie, &scene, "Shape Layer 1")
+        .mattes
+        .is_empty());
+
+    let solid = scene_layer(&lottie, &scene, "SolidLayer");
+    let masks = solid
+        .mattes
+        .iter()
+        .map(|matte| matte.mask.unwrap())
+        .map(|mask| (mask.mode, mask.inverted, mask.index))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        masks,
+        vec![
+            (MaskMode::Add, false, 0),
+            (MaskMode::Subtract, true, 1),
+            (MaskMode::Intersect, false, 2)
+        ]
+    );
+    // Expansion grows the mask path
+    let expanded = scene.layer(solid.mattes[2].id).unwrap();
+    match &expanded.content {
+        SceneContent::Shapes(shapes) => {
+            let bounds = lyon_algorithms::aabb::bounding_box(&shapes[0].path);
+            assert!((bounds.min.x + 10.0).abs() < 1e-3);
+            assert!((bounds.max.x - 110.0).abs() < 1e-3);
+        }
+        _ => panic!("masks should be shapes"),
+    }
+
+    // Add, then subtract the inverted mask, then intersect
+    let coverage = solid
+        .mattes
+        .iter()
+        .fold(MaskMode::Add.initial_coverage(), |coverage, matte| {
+            matte.mask.unwrap().combine(coverage, 1.0)
+        });
+    assert_eq!(coverage, 1.0);
+    assert_eq!(MaskMode::Subtract.initial_coverage(), 1.0);
+    assert_eq!(MaskMode::Difference.combine(0.75, 0.25), 0.5);
+    assert_eq!(MaskMode::Lighten.combine(0.75, 0.25), 0.75);
+    assert_eq!(MaskMode::Darken.combine(0.75, 0.25), 0.25);
+}
+
+#[test]
+fn test_evaluate_precomposition_masks() {
+    let lottie = basic_layers(|json| {
+        let mut shape = json["layers"][0].clone();
+        shape["parent"] = 3.into();
+        let mut solid = json["layers"][2].clone();
+        solid["hasMask"] = true.into();
+        solid["masksProperties"] = serde_json::json!([mask("a", false, 0.0)]);
+        json["assets"] = serde_json::json!([{ "id": "comp", "layers": [shape, solid] }]);
+        let (w, h) = (json["w"].clone(), json["h"].clone());
+        let precomp = &mut json["layers"][2];
+        precomp["ty"] = 0.into();
+        precomp["refId"] = "comp".into();
+        precomp["w"] = w;
+        precomp["h"] = h;
+        precomp["nm"] = "Precomp".into();
+        precomp["hasMask"] = true.into();
+        precomp["masksProperties"] = serde_json::json!([mask("s", false, 0.0)]);
+    });
+    let scene = lottie.timeline().evaluate(0.0);
+    let modes = |name: &str| {
+        scene_layer(&lottie, &scene, name)
+            .mattes
+            .iter()
+            .map(|matte| matte.mask.unwrap().mode)
+            .collect::<Vec<_>>()
+    };
+    // Layers of the precomposition inherit its masks once, and not the ones
+    // of their transform parent
+    assert_eq!(modes("Shape Layer 1"), vec![MaskMode::Subtract]);
+    assert_eq!(modes("SolidLayer"), vec![MaskMode::Add, MaskMode::Subtract]);
+}
@@ -0,0 +1,150 @@
+use lottie::prelude::{Model, SceneContent};
+use lottie::{CharsDB, FileResolver, Lottie};
+use lyon_algorithms::aabb::bounding_box;
+use serde_json::{json, Value};
+
+/// Glyphs of a monospace font, squares 50 units wide drawn from the start of
+/// an advance of 60 units, at a font size of 100
+fn font_chars(chars: &str) -> Value {
+    chars
+        .chars()
+        .map(|c| {
+            let shapes = if c == ' ' {
+                json!([])
+            } else {
+                json!([{ "ty": "gr", "it": [{ "ty": "sh", "ks": { "a": 0, "k": {
+                    "c": true,
+                    "v": [[0, 0], [50, 0], [50, -50], [0, -50]],
+                    "i": [[0, 0], [0, 0], [0, 0], [0, 0]],
+                    "o": [[0, 0], [0, 0], [0, 0], [0, 0]]
+                } } }] }])
+            };
+            json!({
+                "ch": c.to_string(), "size": 100, "style": "Regular", "w": 60,
+                "fFamily": "Mono", "data": { "shapes": shapes }
+            })
+        })
+        .collect()
+}
+
+/// Left ends, baselines and sizes of the glyphs of a text layer at the
+/// origin showing `document`, by line from left to right
+fn glyphs(document: Value) -> Vec<(f32, f32, f32)> {
+    let mut document = document;
+    document.as_object_mut().unwrap().extend(
+        json!({ "f": "Mono", "fc": [0, 0, 0] })
+            .as_object()
+            .unwrap()
+            .clone(),
+    );
+    let model: Model = serde_json::from_value(json!({
+        "v": "5.7.0", "fr": 30, "ip": 0, "op": 10, "w": 500, "h": 500,
+        "fonts": { "list": [{
+            "fName": "Mono", "fFamily": "Mono", "fStyle": "Regular", "ascent": 75
+        }] },
+        "chars": font_chars("abcABC "),
+        "layers": [{
+            "ty": 5, "ind": 1, "ip": 0, "op": 10, "st": 0, "ks": {},
+            "t": {
+                "d": { "k": [{ "s": document, "t": 0 }] },
+                "p": {}, "m": { "g": 1, "a": { "a": 0, "k": [0, 0] } }, "a": []
+            }
+        }]
+    }))
+    .unwrap();
+    let lottie = Lottie::new(model, CharsDB::default(), &FileResolver::new("../../")).unwrap();
+    let scene = lottie.timeline().evaluate(0.0);
+    let round = |v: f32| (v * 100.0).round() / 100.0;
+    let mut result = vec![];
+    for layer in &scene.layers {
+        let SceneContent::Shapes(shapes) = &layer.content else {
+            continue;
+        };
+        for shape in shapes
+            .iter()
+            .filter(|shape| shape.path.iter().next().is_some())
+        {
+            let bounds = bounding_box(shape.path.iter());
+            let transform = layer.transform * shape.transform;
+            let min = transform.transform_point3(glam::Vec3::new(bounds.min.x, bounds.min.y, 0.0));
+            let max = transform.transform_point3(glam::Vec3::new(bounds.max.x, bounds.max.y, 0.0));
+            result.push((round(min.x), round(max.y), round((max.x - min.x) * 2.0)));
+        }
+    }
+    result.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0)));
+    result
+}
+
+#[test]
+fn test_text_tracking() {
+    // Tracking is in thousandths of an em, added after each character
+    let result = glyphs(json!({ "t": "abc", "s": 100, "tr": 100 }));
+    assert_eq!(
+        result,
+        vec![(0.0, 0.0, 100.0), (70.0, 0.0, 100.0), (140.0, 0.0, 100.0)]
+    );
+    // Centered point text is centered on its advances, including tracking
+    let result = glyphs(json!({ "t": "ab", "s": 100, "tr": -100, "j": 2 }));
+    assert_eq!(result, vec![(-50.0, 0.0, 100.0), (0.0, 0.0, 100.0)]);
+}
+
+#[test]
+fn test_text_caps() {
+    let result = glyphs(json!({ "t": "aB", "s": 100, "ca": 1 }));
+    assert_eq!(result, vec![(0.0, 0.0, 100.0), (60.0, 0.0, 100.0)]);
+    // Lowercase letters are drawn as capitals at 70% of the font size
+    let result = glyphs(json!({ "t": "aBc", "s": 100, "ca": 2 }));
+    assert_eq!(
+        result,
+        vec![(0.0, 0.0, 70.0), (42.0, 0.0, 100.0), (102.0, 0.0, 70.0)]
+    );
+}
+
+#[test]
+fn test_text_box_wrap() {
+    // Lines break at the last space before the character overflowing the
+    // box, the space isn't drawn. Box text starts an ascent below the top
+    // of its box
+    let result = glyphs(json!({
+        "t": "ab c abc", "s": 100, "lh": 120, "sz": [250, 500], "ps": [10, 20]
+    }));
+    let x = |index: usize| 10.0 + 60.0 * index as f32;
+    assert_eq!(
+        result,
+        vec![
+            (x(0), 95.0, 100.0),
+            (x(1), 95.0, 100.0),
+            (x(3), 95.0, 100.0),
+            (x(0), 215.0, 100.0),
+            (x(1), 215.0, 100.0),
+            (x(2), 215.0, 100.0),
+        ]
+    );
+    // Words wider than the box break between characters
+    let result =
+        glyphs(json!({ "t": "abcabc", "s": 100, "lh": 120, "sz": [250, 500], "ps": [0, 0] }));
+    let lines = result.iter().map(|glyph| glyph.1).collect::<Vec<_>>();
+    assert_eq!(lines, vec![75.0, 75.0, 75.0, 75.0, 195.0, 195.0]);
+}
+
+#[test]
+fn test_text_box_justify() {
+    let lines = |justify: u8| {
+        glyphs(json!({
+            "t": "ab c abc", "s": 100, "lh": 120, "sz": [300, 500], "ps": [0, 0], "j": justify
+        }))
+        .into_iter()
+        .map(|glyph| glyph.0)
+        .collect::<Vec<_>>()
+    };
+    // Lines of 240 and 180 units in a box of 300
+    assert_eq!(lines(1), vec![60.0, 120.0, 240.0, 120.0, 180.0, 240.0]);
+    assert_eq!(lines(2), vec![30.0, 90.0, 210.0, 60.0, 120.0, 180.0]);
+    // Justified lines spread the remaining width over their spaces, the
+    // last line of a paragraph is aligned
+    assert_eq!(lines(3), vec![0.0, 60.0, 240.0, 0.0, 60.0, 120.0]);
+    assert_eq!(lines(4), vec![0.0, 60.0, 240.0, 120.0, 180.0, 240.0]);
+    assert_eq!(lines(5), vec![0.0, 60.0, 240.0, 60.0, 120.0, 180.0]);
+    // Single words spread it between their characters
+    assert_eq!(lines(6), vec![0.0, 60.0, 240.0, 0.0, 120.0, 240.0]);
+}
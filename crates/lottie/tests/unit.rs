@@ -1,7 +1,15 @@
 use std::fs;
 use std::io::Error;
+use std::sync::Arc;
 
-use lottie::prelude::{Animated, GradientFill, Stroke, TextRange, Transform, Vector2D};
+use lottie::glam::Vec3;
+use lottie::prelude::{
+    Animated, Bezier, Effect, EffectType, EffectValueKind, GradientFill, LayerStyle,
+    LayerStyleKind, Model, Position, Rgb, Stroke, TextAnimators, TextFollowPath, TextFrame,
+    TextPath, TextPathInfo, TextRange, TextRangeInfo, TextRangeSelector, Transform, Vector2D,
+};
+use lottie::{CharsDB, FileResolver, FontDB};
+use serde_json::json;
 
 #[test]
 fn test_transform_complex() -> Result<(), Error> {
@@ -17,6 +25,50 @@ fn test_transform_complex() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_transform_skew_and_rotation_3d() {
+    let transform: Transform = serde_json::from_value(json!({
+        "sk": { "a": 0, "k": 45 },
+        "sa": { "a": 0, "k": 0 }
+    }))
+    .unwrap();
+    let point = transform
+        .value(0.0)
+        .transform_point3(Vec3::new(0.0, -10.0, 0.0));
+    assert!(point.abs_diff_eq(Vec3::new(10.0, -10.0, 0.0), 1e-4));
+
+    let transform: Transform = serde_json::from_value(json!({
+        "rz": { "a": 0, "k": 90 },
+        "ry": { "a": 0, "k": 180 }
+    }))
+    .unwrap();
+    let point = transform
+        .value(0.0)
+        .transform_point3(Vec3::new(10.0, 0.0, 0.0));
+    assert!(point.abs_diff_eq(Vec3::new(0.0, 10.0, 0.0), 1e-4));
+    let point = transform
+        .value(0.0)
+        .transform_point3(Vec3::new(0.0, 10.0, 0.0));
+    assert!(point.abs_diff_eq(Vec3::new(10.0, 0.0, 0.0), 1e-4));
+}
+
+#[test]
+fn test_split_position() {
+    let position = json!({
+        "s": true,
+        "x": { "a": 0, "k": 10 },
+        "y": { "a": 0, "k": 20 }
+    });
+    let transform: Transform = serde_json::from_value(json!({ "p": position })).unwrap();
+    assert!(matches!(transform.position, Some(Position::Split { .. })));
+    let point = transform.value(0.0).transform_point3(Vec3::ZERO);
+    assert!(point.abs_diff_eq(Vec3::new(10.0, 20.0, 0.0), 1e-4));
+
+    let value = serde_json::to_value(&transform).unwrap();
+    assert_eq!(value["p"]["s"], true);
+    assert_eq!(value["p"]["y"]["k"], 20.0);
+}
+
 #[test]
 fn test_stroke() -> Result<(), Error> {
     let file = fs::File::open("../../fixtures/segments/stroke.json")?;
@@ -39,6 +91,106 @@ fn test_gradient_fill() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_gradient_opacity_stops() {
+    // 2 color stops and 3 opacity stops at different offsets
+    let fill = json!({
+        "o": { "a": 0, "k": 100 },
+        "r": 1,
+        "s": { "a": 0, "k": [0, 0] },
+        "e": { "a": 0, "k": [100, 0] },
+        "t": 2,
+        "h": { "a": 0, "k": 50 },
+        "a": { "a": 0, "k": 90 },
+        "g": {
+            "p": 2,
+            "k": { "a": 0, "k": [0, 1, 0, 0, 1, 0, 0, 1, 0, 1, 0.5, 0.5, 1, 0] }
+        }
+    });
+    let fill: GradientFill = serde_json::from_value(fill).unwrap();
+    let stops = fill.gradient.colors.colors.value(0.0);
+    let offsets = stops.iter().map(|s| s.offset).collect::<Vec<_>>();
+    assert_eq!(offsets, vec![0.0, 0.5, 1.0]);
+    let alphas = stops.iter().map(|s| s.color.a).collect::<Vec<_>>();
+    assert_eq!(alphas, vec![255, 128, 0]);
+    // Red fades into blue
+    assert_eq!((stops[1].color.r, stops[1].color.b), (128, 128));
+
+    let focal = fill.gradient.focal_point(0.0);
+    assert!(focal.x.abs() < 1e-3);
+    assert!((focal.y - 50.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_effect() {
+    let effect = json!({
+        "ty": 25,
+        "nm": "Drop Shadow",
+        "mn": "ADBE Drop Shadow",
+        "ix": 1,
+        "en": 1,
+        "ef": [
+            { "ty": 2, "nm": "Shadow Color", "mn": "ADBE Drop Shadow-0001", "ix": 1, "v": { "a": 0, "k": [1, 0, 0, 1] } },
+            { "ty": 0, "nm": "Opacity", "mn": "ADBE Drop Shadow-0002", "ix": 2, "v": { "a": 1, "k": [
+                { "t": 0, "s": [0], "i": { "x": [1], "y": [1] }, "o": { "x": [0], "y": [0] } },
+                { "t": 10, "s": [200] }
+            ] } },
+            { "ty": 6, "nm": "", "mn": "ADBE Group End", "ix": 3, "v": 0 }
+        ]
+    });
+    let parsed: Effect = serde_json::from_value(effect).unwrap();
+    assert_eq!(parsed.ty, EffectType::DropShadow);
+    assert!(parsed.enabled);
+    assert!(parsed.is_animated());
+    assert_eq!(
+        parsed.color(0, 0.0).map(|c| (c.r, c.g, c.b)),
+        Some((255, 0, 0))
+    );
+    assert_eq!(parsed.number(1, 5.0), Some(100.0));
+    assert!(matches!(
+        parsed.values[2].value,
+        EffectValueKind::Other { ty: 6, .. }
+    ));
+
+    // Unknown effect types survive a round trip
+    let unknown: Effect = serde_json::from_value(json!({ "ty": 99, "ef": [] })).unwrap();
+    assert_eq!(unknown.ty, EffectType::Other(99));
+    let value = serde_json::to_value(&parsed).unwrap();
+    let again: Effect = serde_json::from_value(value).unwrap();
+    assert_eq!(again.values.len(), 3);
+    assert_eq!(again.number(1, 5.0), Some(100.0));
+    assert_eq!(serde_json::to_value(&unknown).unwrap()["ty"], 99);
+}
+
+#[test]
+fn test_layer_styles() {
+    let styles = json!([
+        { "ty": 0, "nm": "Stroke", "s": { "a": 0, "k": 4 }, "c": { "a": 0, "k": [1, 0, 0, 1] } },
+        {
+            "ty": 1, "nm": "Drop Shadow",
+            "c": { "a": 0, "k": [0, 0, 0, 1] }, "o": { "a": 0, "k": 75 },
+            "a": { "a": 0, "k": 120 }, "s": { "a": 0, "k": 5 }, "d": { "a": 0, "k": 5 },
+            "ch": { "a": 0, "k": 0 }, "bm": { "a": 0, "k": 5 }, "lc": { "a": 0, "k": 1 }
+        },
+        { "ty": 7, "c": { "a": 0, "k": [0, 0, 1, 1] }, "so": { "a": 0, "k": 50 } },
+        { "ty": 42, "nm": "Future" }
+    ]);
+    let parsed: Vec<LayerStyle> = serde_json::from_value(styles.clone()).unwrap();
+    assert!(matches!(&parsed[0].kind, LayerStyleKind::Stroke(s) if s.size.value(0.0) == 4.0));
+    assert!(
+        matches!(&parsed[1].kind, LayerStyleKind::DropShadow(s) if s.angle.value(0.0) == 120.0)
+    );
+    assert!(
+        matches!(&parsed[2].kind, LayerStyleKind::ColorOverlay(s) if s.opacity.value(0.0) == 50.0)
+    );
+    assert!(matches!(&parsed[3].kind, LayerStyleKind::Unknown(_)));
+
+    let value = serde_json::to_value(&parsed).unwrap();
+    assert_eq!(value[0]["ty"], 0);
+    assert_eq!(value[1]["nm"], "Drop Shadow");
+    assert_eq!(value[3], styles[3]);
+}
+
 #[test]
 fn test_text_range() -> Result<(), Error> {
     let file = fs::File::open("../../fixtures/segments/text_range.json")?;
@@ -48,6 +200,142 @@ fn test_text_range() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_text_range_selector() {
+    let lines = vec!["ab c".chars().collect::<Vec<_>>()];
+    let selector = |json: serde_json::Value| -> TextRangeSelector {
+        let mut value = json!({
+            "t": 0, "xe": { "a": 0, "k": 0 }, "ne": { "a": 0, "k": 0 },
+            "a": { "a": 0, "k": 100 }, "b": 1, "rn": 0, "sh": 1, "r": 1
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(json.as_object().unwrap().clone());
+        serde_json::from_value(value).unwrap()
+    };
+    // Percent of the characters
+    let s = selector(json!({ "e": { "a": 0, "k": 50 } }));
+    assert_eq!(s.amounts(&lines, 0.0), vec![vec![1.0, 1.0, 0.0, 0.0]]);
+    // Second word, spaces belong to the word before them
+    let s = selector(json!({ "b": 3, "r": 2, "s": { "a": 0, "k": 1 }, "e": { "a": 0, "k": 2 } }));
+    assert_eq!(s.amounts(&lines, 0.0), vec![vec![0.0, 0.0, 0.0, 1.0]]);
+    let s = selector(json!({ "sh": 2, "a": { "a": 0, "k": 50 } }));
+    assert_eq!(
+        s.amounts(&lines, 0.0),
+        vec![vec![0.0625, 0.1875, 0.3125, 0.4375]]
+    );
+    let s = selector(json!({ "sh": 2, "sm": { "a": 0, "k": 0 } }));
+    assert_eq!(s.amounts(&lines, 0.0), vec![vec![0.0, 0.0, 1.0, 1.0]]);
+    let s = selector(json!({ "rn": 1 }));
+    let mut amounts = s.amounts(&lines, 0.0).remove(0);
+    amounts.sort_by(f32::total_cmp);
+    assert_eq!(amounts, vec![1.0; 4]);
+
+    let range: TextRange = serde_json::from_value(json!({
+        "s": serde_json::to_value(selector(json!({ "e": { "a": 0, "k": 50 } }))).unwrap(),
+        "a": {
+            "p": { "a": 0, "k": [10, 0] },
+            "fc": { "a": 0, "k": [0, 0, 1] },
+            "o": { "a": 0, "k": 50 }
+        }
+    }))
+    .unwrap();
+    let animators = Arc::new(TextAnimators::new(lines.clone(), vec![range]));
+    let info = |index| TextRangeInfo {
+        animators: animators.clone(),
+        index: (0, index),
+        advance: 10.0,
+        align: 0.0,
+        path: None,
+    };
+    // Characters share the amounts of the selectors at a frame
+    let text = TextFrame::new(&info(0), 0.0);
+    assert_eq!(text.amounts, vec![vec![vec![1.0, 1.0, 0.0, 0.0]]]);
+    let selected = info(1).value(0.0, &text);
+    assert_eq!(selected.transform.w_axis.x, 10.0);
+    assert_eq!(selected.opacity, 0.5);
+    assert_eq!(selected.fill_color(Rgb::new_u8(0, 0, 0)).b, 255);
+    let unselected = info(2).value(0.0, &text);
+    assert_eq!(unselected.transform.w_axis.x, 0.0);
+    assert_eq!(unselected.opacity, 1.0);
+}
+
+#[test]
+fn test_text_follow_path() {
+    // A vertical line, 100 long
+    let mask: Animated<Vec<Bezier>> = serde_json::from_value(json!({
+        "a": 0,
+        "k": { "v": [[0, 0], [0, 100]], "i": [[0, 0], [0, 0]], "o": [[0, 0], [0, 0]], "c": false }
+    }))
+    .unwrap();
+    let options: TextFollowPath = serde_json::from_value(json!({
+        "m": 0, "f": { "a": 0, "k": 5 }, "p": { "a": 0, "k": 1 }
+    }))
+    .unwrap();
+    let animators = Arc::new(TextAnimators::new(vec!["ab".chars().collect()], vec![]));
+    let info = |path: &Arc<TextPath>, index| TextRangeInfo {
+        animators: animators.clone(),
+        index: (0, index),
+        advance: 10.0,
+        align: 0.0,
+        path: Some(TextPathInfo {
+            path: path.clone(),
+            position: index as f32 * 10.0,
+            line_width: 20.0,
+            line_offset: 0.0,
+        }),
+    };
+    // The middle of the second character is 20 along the path, after the
+    // margin, and it's rotated along the path
+    let path = Arc::new(TextPath::new(options.clone(), mask.clone()));
+    let text = TextFrame::new(&info(&path, 0), 0.0);
+    let transform = info(&path, 1).value(0.0, &text).transform;
+    let origin = transform.transform_point3(Vec3::ZERO);
+    assert!((origin - Vec3::new(0.0, 15.0, 0.0)).length() < 1e-3);
+    let end = transform.transform_point3(Vec3::new(10.0, 0.0, 0.0));
+    assert!((end - Vec3::new(0.0, 25.0, 0.0)).length() < 1e-3);
+
+    // Characters stay upright, and start from the end of a reversed path
+    let options = TextFollowPath {
+        perpendicular: None,
+        reverse: Some(Animated::from_value(1.0)),
+        ..options
+    };
+    let path = Arc::new(TextPath::new(options, mask));
+    let text = TextFrame::new(&info(&path, 0), 0.0);
+    let transform = info(&path, 0).value(0.0, &text).transform;
+    let origin = transform.transform_point3(Vec3::ZERO);
+    assert!((origin - Vec3::new(-5.0, 90.0, 0.0)).length() < 1e-3);
+}
+
+#[test]
+fn test_embedded_chars() -> Result<(), Error> {
+    let mut file = fs::File::open("../../fixtures/ui/checked/NonAnimating/Text_Glyph.json")?;
+    let model = Model::from_reader(&mut file).unwrap();
+    assert_eq!(model.chars.len(), 6);
+    assert_eq!(model.chars[0].family, "Avenir Next");
+
+    let mut fontdb = CharsDB::default();
+    fontdb
+        .load_fonts_from_model(&model, &FileResolver::new("../../"))
+        .unwrap();
+    let font = model.font("AvenirNext-Medium").unwrap();
+    let glyph = fontdb.glyph(font, 'T').unwrap();
+    let width = model
+        .chars
+        .iter()
+        .find(|c| c.character == "T")
+        .unwrap()
+        .width;
+    assert_eq!(glyph.advance, width / 100.0);
+    assert!(!glyph.beziers.is_empty());
+    // Outlines are above the baseline
+    assert!(glyph.beziers[0].verticies.iter().all(|v| v.y <= 0.0));
+    assert!(fontdb.glyph(font, 'Z').is_err());
+    Ok(())
+}
+
 #[test]
 fn test_legacy_animated_position() -> Result<(), Error> {
     let file = fs::File::open("../../fixtures/segments/animated_position_legacy.json")?;
@@ -6,7 +6,11 @@ use std::path::Path;
 // use bevy_prototype_debug_lines::{DebugLines, DebugLinesPlugin};
 use anyhow::Error;
 use clap::Parser;
-use lottie::{Config, HeadlessConfig, Lottie, MockFont, Renderer, Target, WindowConfig};
+#[cfg(feature = "text")]
+use lottie::FontKitDB;
+use lottie::{
+    CharsDB, Config, DotLottie, FontDB, HeadlessConfig, Lottie, Renderer, Target, WindowConfig,
+};
 use lottie_renderer_bevy::BevyRenderer;
 use smol::pin;
 use smol::stream::StreamExt;
@@ -15,9 +19,13 @@ use webp_animation::Encoder;
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Input file, should be a Lottie JSON
+    /// Input file, should be a Lottie JSON or a dotLottie archive
     #[clap(short, long)]
     input: String,
+    /// Id of the animation to play in a dotLottie archive, defaults to the
+    /// active animation of the archive
+    #[clap(long)]
+    animation: Option<String>,
     /// Run in headless mode, a animation file with the same name as the input
     /// will be generated
     #[clap(long, action)]
@@ -35,6 +43,9 @@ struct Args {
     inspector: bool,
     #[clap(long)]
     scale: Option<f32>,
+    /// Render text layers with installed fonts, requires the `text` feature
+    #[clap(long, action)]
+    fonts: bool,
 }
 
 // fn axis_system(mut lines: ResMut<DebugLines>) {
@@ -44,6 +55,16 @@ struct Args {
 
 fn main() -> Result<(), Error> {
     let args = Args::parse();
+    if args.fonts {
+        #[cfg(feature = "text")]
+        return play::<FontKitDB>(args);
+        #[cfg(not(feature = "text"))]
+        anyhow::bail!("--fonts requires the `text` feature");
+    }
+    play::<CharsDB>(args)
+}
+
+fn play<F: FontDB + Send + Sync + 'static>(args: Args) -> Result<(), Error> {
     let path = Path::new(&args.input);
     let mut root_path = path.to_path_buf();
     root_path.pop();
@@ -57,7 +78,16 @@ fn main() -> Result<(), Error> {
     }
     let root_path = &*root_path.to_string_lossy();
     let mut f = fs::File::open(path).unwrap();
-    let mut lottie = Lottie::<MockFont>::from_reader(&mut f, root_path).unwrap();
+    let mut lottie = if path.extension().map_or(false, |ext| ext == "lottie") {
+        let archive = DotLottie::from_reader(f)?;
+        let id = match args.animation.as_deref().or(archive.default_animation_id()) {
+            Some(id) => id.to_string(),
+            None => anyhow::bail!("{} contains no animation", args.input),
+        };
+        Lottie::<F>::from_dotlottie(&archive, &id)?
+    } else {
+        Lottie::<F>::from_reader(&mut f, root_path).unwrap()
+    };
     lottie.scale = args.scale.unwrap_or(1.0);
     let final_timestamp = (lottie.model.end_frame / lottie.model.frame_rate * 1000.0) as i32;
     let (mut renderer, frame_stream) = BevyRenderer::new();
@@ -93,7 +123,7 @@ fn main() -> Result<(), Error> {
         // renderer.add_plugin(DebugLinesPlugin::default());
         // renderer.add_system(axis_system);
         renderer.load_lottie(lottie, config);
-        <lottie_renderer_bevy::BevyRenderer as lottie::Renderer<MockFont>>::render(&mut renderer);
+        <lottie_renderer_bevy::BevyRenderer as lottie::Renderer<F>>::render(&mut renderer);
         pin!(frame_stream);
         let mut i = 0;
         if all_frames {
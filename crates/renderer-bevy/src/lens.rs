@@ -1,20 +1,18 @@
-use bevy::prelude::{Transform, Vec2};
+use bevy::prelude::{Color, Transform, Vec2};
 use bevy_tweening_captured::{Lens, Targetable};
 use lottie::prelude::{
-    Animated, Bezier, OpacityHierarchy, PathFactory, TextBased, TextRangeInfo, TextRangeSelector,
-    Transform as LottieTransform, TransformHierarchy, TrimInfo,
+    Animated, Bezier, DashPattern, LayerStyle, OpacityHierarchy, PathFactory, PathModifier, Rgb,
+    SceneLayerStyle, Shape, StrokeDash, TextFrame, TextRangeInfo, Transform as LottieTransform,
+    TransformHierarchy, TrimInfo,
 };
-use lyon::algorithms::measure::PathMeasurements;
-use lyon::algorithms::measure::SampleType::Normalized;
-use lyon::geom::euclid::approxeq::ApproxEq;
-use lyon::path::Path as LyonPath;
 
-use crate::shape::{DrawMode, Path};
+use crate::shape::{DrawMode, Path, SolidOrGradient};
 
 pub struct PathLens {
     pub(crate) start: Vec<Bezier>,
     pub(crate) end: Vec<Bezier>,
     pub(crate) trims: Vec<TrimInfo>,
+    pub(crate) modifiers: Vec<Shape>,
     pub(crate) start_frame: f32,
     pub(crate) end_frame: f32,
 }
@@ -39,41 +37,16 @@ impl Lens<Path> for PathLens {
                 result
             })
             .collect::<Vec<_>>();
-        let path = beziers.path(0.0);
-        let mut start = 0.0f32;
-        let mut end = 1.0f32;
-        for trim in &self.trims {
-            let offset = norm(trim.trim.offset.value(frame) / 360.0);
-            let mut s = norm(trim.trim.start.value(frame) / 100.0 + offset);
-            let mut e = norm(trim.trim.end.value(frame) / 100.0 + offset);
-            if s < 0.0 {
-                s += 1.0;
-            }
-            if e < 0.0 {
-                e += 1.0;
-            }
-            let (s, e) = (s.min(e), s.max(e));
-            start = start.max(s);
-            end = end.min(e);
-        }
-        if start.approx_eq(&0.0) && end.approx_eq(&1.0) {
-            target.0 = path;
-        } else {
-            let measures = PathMeasurements::from_path(&path, 1e-3);
-            let mut sampler = measures.create_sampler(&path, Normalized);
-            let mut builder = LyonPath::builder();
-            sampler.split_range(start..end, &mut builder);
-            let path = builder.build();
-            target.0 = path;
-        }
-    }
-}
-
-fn norm(value: f32) -> f32 {
-    if value > 0.0 && value.fract() == 0.0 {
-        1.0
-    } else {
-        value.fract()
+        let path = self
+            .modifiers
+            .iter()
+            .fold(beziers.path(0.0), |path, modifier| {
+                modifier.modify(&path, frame)
+            });
+        target.0 = self
+            .trims
+            .iter()
+            .fold(path, |path, trim| trim.trim.trim_path(&path, frame));
     }
 }
 
@@ -91,6 +64,20 @@ impl Lens<DrawMode> for StrokeWidthLens {
     }
 }
 
+pub struct StrokeDashLens {
+    pub(crate) dashes: Vec<StrokeDash>,
+    pub(crate) frames: f32,
+}
+
+impl Lens<DrawMode> for StrokeDashLens {
+    fn lerp(&mut self, target: &mut dyn Targetable<DrawMode>, ratio: f32) {
+        let frame = self.frames * ratio;
+        if let Some(stroke) = target.stroke.as_mut() {
+            stroke.dash = DashPattern::new(&self.dashes, frame);
+        }
+    }
+}
+
 /// Lerp [LottieTransform] as a whole
 pub struct TransformLens {
     pub(crate) data: LottieTransform,
@@ -104,41 +91,42 @@ pub struct TransformLens {
 impl Lens<Transform> for TransformLens {
     fn lerp(&mut self, target: &mut dyn Targetable<Transform>, ratio: f32) {
         let frame = self.frames * ratio;
-        let value = self.data.value(frame);
+        let mut value = self.data.value(frame);
+        // Glyphs of text layers are moved by the animators of their character.
+        // Each glyph is tweened on its own, so it evaluates them itself
+        if let Some(info) = self.text_range.as_ref() {
+            value = value * info.value(frame, &TextFrame::new(info, frame)).transform;
+        }
         *target.target_mut() = Transform::from_matrix(value);
         target.translation.z = self.zindex;
         target.translation.x += self.mask_offset.x / self.transform_hierarchy.scale_x(frame);
+    }
+}
 
-        if let Some(info) = self.text_range.as_ref() {
-            for range in &info.ranges {
-                let appliable = lerp_index_in_text_range(
-                    &range.selector,
-                    frame,
-                    &info.value,
-                    info.index.0,
-                    info.index.1,
-                );
-                if !appliable {
-                    continue;
-                }
-                // TODO: support more selector attributes
-                let styles = match range.style.as_ref() {
-                    Some(s) => s,
-                    None => continue,
-                };
-                let letter_spacing = styles
-                    .letter_spacing
-                    .as_ref()
-                    .map(|l| l.value(frame))
-                    .unwrap_or(0.0);
-                target.translation.x += info.index.1 as f32 * letter_spacing;
-            }
+/// Move the copy of the layer shapes drawing a drop shadow style by the
+/// offset of the shadow
+pub struct StyleOffsetLens {
+    pub(crate) style: LayerStyle,
+    pub(crate) frames: f32,
+    pub(crate) zindex: f32,
+}
+
+impl Lens<Transform> for StyleOffsetLens {
+    fn lerp(&mut self, target: &mut dyn Targetable<Transform>, ratio: f32) {
+        let frame = self.frames * ratio;
+        if let Some(SceneLayerStyle::DropShadow { offset, .. }) =
+            SceneLayerStyle::new(&self.style, frame)
+        {
+            *target.target_mut() = Transform::from_xyz(offset.x, offset.y, self.zindex);
         }
     }
 }
 
 pub struct OpacityLens {
     pub(crate) opacity: OpacityHierarchy,
+    /// Transform of the shape, the opacity of repeater copies depends on
+    /// their index
+    pub(crate) transform: LottieTransform,
     pub(crate) frames: f32,
     pub(crate) fill_opacity: Animated<f32>,
     pub(crate) stroke_opacity: Option<Animated<f32>>,
@@ -147,7 +135,7 @@ pub struct OpacityLens {
 impl Lens<DrawMode> for OpacityLens {
     fn lerp(&mut self, target: &mut dyn Targetable<DrawMode>, ratio: f32) {
         let frame = self.frames as f32 * ratio;
-        let value = self.opacity.value(frame);
+        let value = self.opacity.value(frame) * self.transform.opacity_value(frame) / 100.0;
         let fill_opacity = self.fill_opacity.value(frame) / 100.0;
 
         if let Some(fill) = target.fill.as_mut() {
@@ -163,31 +151,50 @@ impl Lens<DrawMode> for OpacityLens {
     }
 }
 
-fn lerp_index_in_text_range(
-    selector: &TextRangeSelector,
-    frame: f32,
-    value: &Vec<Vec<char>>,
-    line: usize,
-    c: usize,
-) -> bool {
-    let start = selector
-        .start
-        .as_ref()
-        .map(|start| start.value(frame))
-        .unwrap_or(0.0)
-        .round() as usize;
-    let end = selector
-        .end
-        .as_ref()
-        .map(|end| end.value(frame))
-        .unwrap_or(std::f32::MAX)
-        .round() as usize;
-    match selector.range_units {
-        TextBased::Characters => {
-            let index = (0..line).map(|i| value[i].len()).sum::<usize>() + c;
-            return index >= start && index < end;
+/// Paint a glyph of a text layer with the animators of its character, in
+/// place of [OpacityLens]
+pub struct TextStyleLens {
+    pub(crate) text_range: TextRangeInfo,
+    pub(crate) opacity: OpacityHierarchy,
+    pub(crate) transform: LottieTransform,
+    pub(crate) frames: f32,
+    pub(crate) fill_color: Option<Animated<Rgb>>,
+    pub(crate) stroke_color: Option<Animated<Rgb>>,
+    pub(crate) stroke_width: Option<Animated<f32>>,
+    pub(crate) stroke_opacity: Option<Animated<f32>>,
+}
+
+impl Lens<DrawMode> for TextStyleLens {
+    fn lerp(&mut self, target: &mut dyn Targetable<DrawMode>, ratio: f32) {
+        let frame = self.frames * ratio;
+        let text = TextFrame::new(&self.text_range, frame);
+        let animator = self.text_range.value(frame, &text);
+        let opacity = self.opacity.value(frame) * self.transform.opacity_value(frame) / 100.0
+            * animator.opacity;
+        let solid = |color: &mut SolidOrGradient, rgb: Rgb| {
+            if let SolidOrGradient::Solid(color) = color {
+                *color = Color::rgb_u8(rgb.r, rgb.g, rgb.b).with_a(color.a());
+            }
+        };
+        if let Some(fill) = target.fill.as_mut() {
+            fill.opacity = opacity;
+            if let Some(color) = self.fill_color.as_ref() {
+                solid(&mut fill.color, animator.fill_color(color.value(frame)));
+            }
+        }
+        if let Some(stroke) = target.stroke.as_mut() {
+            let stroke_opacity = self
+                .stroke_opacity
+                .as_ref()
+                .map_or(1.0, |o| o.value(frame) / 100.0);
+            stroke.opacity = opacity * stroke_opacity * animator.stroke_opacity;
+            if let Some(color) = self.stroke_color.as_ref() {
+                solid(&mut stroke.color, animator.stroke_color(color.value(frame)));
+            }
+            if let Some(width) = self.stroke_width.as_ref() {
+                stroke.options.line_width = animator.stroke_width(width.value(frame));
+            }
         }
-        _ => unimplemented!(),
     }
 }
 
@@ -195,11 +202,22 @@ pub struct PathFactoryLens {
     pub(crate) start_frame: f32,
     pub(crate) end_frame: f32,
     pub(crate) factory: Box<dyn PathFactory + Send + Sync>,
+    pub(crate) modifiers: Vec<Shape>,
+    pub(crate) trims: Vec<TrimInfo>,
 }
 
 impl Lens<Path> for PathFactoryLens {
     fn lerp(&mut self, target: &mut dyn Targetable<Path>, ratio: f32) {
         let frame = (self.end_frame - self.start_frame) * ratio + self.start_frame;
-        target.0 = self.factory.path(frame);
+        let path = self
+            .modifiers
+            .iter()
+            .fold(self.factory.path(frame), |path, modifier| {
+                modifier.modify(&path, frame)
+            });
+        target.0 = self
+            .trims
+            .iter()
+            .fold(path, |path, trim| trim.trim.trim_path(&path, frame));
     }
 }
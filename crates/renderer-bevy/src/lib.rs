@@ -23,7 +23,8 @@ pub use bevy;
 use bevy::prelude::Transform;
 use bevy::render::texture::{BevyDefault, Image};
 use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
-use lottie::prelude::{Id as TimelineItemId, StyledShape};
+use lottie::prelude::{BlendMode, Id as TimelineItemId, StagedLayer, StyledShape};
+pub use lottie::FrameData;
 use lottie::*;
 use shape::{DrawMode, Path};
 use system::component_animator_system;
@@ -46,6 +47,10 @@ use ordered_float::OrderedFloat;
 use plugin::LottiePlugin;
 use render::*;
 
+/// Render layer of the first backdrop camera, layer 0 is the main camera and
+/// layer 1 the mask camera
+const FIRST_BACKDROP_LAYER: u8 = 2;
+
 #[derive(Component)]
 pub struct LottieComp<F: FontDB> {
     lottie: Lottie<F>,
@@ -112,13 +117,6 @@ impl FrameSender {
     }
 }
 
-pub struct FrameData {
-    pub data: Vec<u8>,
-    pub width: u32,
-    pub height: u32,
-    pub timestamp: i32,
-}
-
 pub struct BevyRenderer {
     app: App,
     frame_sender: UnboundedSender<FrameData>,
@@ -238,7 +236,7 @@ impl<F: FontDB + Send + Sync + 'static> Renderer<F> for BevyRenderer {
     }
 }
 
-fn setup_system<F: FontDB + Send + Sync + 'static> (
+fn setup_system<F: FontDB + Send + Sync + 'static>(
     mut commands: Commands,
     mut lottie_globals: ResMut<LottieGlobals<F>>,
     mut image_assets: ResMut<Assets<Image>>,
@@ -297,7 +295,7 @@ fn setup_system<F: FontDB + Send + Sync + 'static> (
     let mask_camera = Camera2dBundle {
         camera: Camera {
             target: RenderTarget::Image(mask_texture_handle.clone()),
-            order: -1,
+            order: -1 - RenderLayers::TOTAL_LAYERS as isize,
             clear_color: ClearColorConfig::Custom(Color::NONE),
             ..default()
         },
@@ -310,6 +308,73 @@ fn setup_system<F: FontDB + Send + Sync + 'static> (
     };
     commands.spawn(mask_camera).insert(RenderLayers::layer(1));
 
+    // Layers with a blend mode read the layers below them from a backdrop
+    // texture, rendered by their own camera on a dedicated render layer
+    let timeline = lottie.timeline();
+    let mut blended = timeline
+        .items()
+        .filter(|layer| !layer.is_mask && layer.blend_mode != BlendMode::Normal)
+        .map(|layer| (layer.id, timeline.global_zindex(layer)))
+        .collect::<Vec<_>>();
+    // Backdrops of lower layers are rendered first
+    blended.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let max_blended = RenderLayers::TOTAL_LAYERS - FIRST_BACKDROP_LAYER as usize;
+    if blended.len() > max_blended {
+        log::warn!("only {} layers with blend modes are supported", max_blended);
+        blended.truncate(max_blended);
+    }
+    let mut backdrops = HashMap::new();
+    for (index, (id, _)) in blended.iter().enumerate() {
+        let size = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let mut backdrop = Image {
+            texture_descriptor: TextureDescriptor {
+                label: Some("backdrop_texture"),
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::bevy_default(),
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+            ..default()
+        };
+        backdrop.resize(size);
+        let handle = image_assets.add(backdrop);
+        let backdrop_camera = Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Image(handle.clone()),
+                // After the mask camera, before the main camera
+                order: index as isize - blended.len() as isize,
+                clear_color: ClearColorConfig::Custom(Color::WHITE),
+                ..default()
+            },
+            transform: camera.transform,
+            ..default()
+        };
+        let render_layer = FIRST_BACKDROP_LAYER + index as u8;
+        commands
+            .spawn(backdrop_camera)
+            .insert(RenderLayers::layer(render_layer));
+        backdrops.insert(*id, (handle, render_layer));
+    }
+    // Every layer is drawn on the backdrops of the blended layers above it
+    let render_layers = |layer: &StagedLayer| {
+        let zindex = timeline.global_zindex(layer);
+        blended
+            .iter()
+            .filter(|(_, blended_zindex)| *blended_zindex < zindex)
+            .fold(RenderLayers::layer(0), |layers, (id, _)| {
+                layers.with(backdrops[id].1)
+            })
+    };
+
     if lottie_globals.capturing {
         let target = if let Config::Headless(headless) = &lottie_globals.config {
             headless.target
@@ -435,6 +500,9 @@ fn setup_system<F: FontDB + Send + Sync + 'static> (
                 mask_count,
                 model_size: Vec2::new(lottie.model.width as f32, lottie.model.height as f32),
                 scale,
+                render_layers: render_layers(layer),
+                backdrop: backdrops.get(&layer.id).map(|(handle, _)| handle.clone()),
+                style_paint: None,
             }
             .spawn(&mut commands)
             .unwrap();
@@ -469,6 +537,9 @@ fn setup_system<F: FontDB + Send + Sync + 'static> (
                 mask_count,
                 model_size: Vec2::new(lottie.model.width as f32, lottie.model.height as f32),
                 scale,
+                render_layers: render_layers(layer),
+                backdrop: backdrops.get(&layer.id).map(|(handle, _)| handle.clone()),
+                style_paint: None,
             }
             .spawn(&mut commands)
             .unwrap();
@@ -513,6 +584,9 @@ fn animate_system<F: FontDB + Send + Sync + 'static>(
     mut transform_animation: Query<(&mut Animator<Transform>, &FrameTracker)>,
     mut path_animation: Query<(&mut Animator<Path>, &FrameTracker)>,
     mut draw_mode_animation: Query<(&mut Animator<DrawMode>, &FrameTracker)>,
+    gradient_animation: Query<(&GradientAnimator, &Handle<LottieMaterial>, &FrameTracker)>,
+    effect_animation: Query<(&EffectAnimator, &Handle<LottieMaterial>, &FrameTracker)>,
+    mut material_assets: ResMut<Assets<LottieMaterial>>,
     mut info: ResMut<LottieAnimationInfo>,
     lottie: Res<LottieGlobals<F>>,
     time: Res<Time>,
@@ -584,6 +658,22 @@ fn animate_system<F: FontDB + Send + Sync + 'static>(
         }
     }
 
+    for (animator, handle, tracker) in gradient_animation.iter() {
+        if let Some(frame) = tracker.value(current_frame) {
+            if let Some(material) = material_assets.get_mut(handle) {
+                animator.update(material, frame);
+            }
+        }
+    }
+
+    for (animator, handle, tracker) in effect_animation.iter() {
+        if let Some(frame) = tracker.value(current_frame) {
+            if let Some(material) = material_assets.get_mut(handle) {
+                animator.update(material, frame);
+            }
+        }
+    }
+
     for (_, mut visibility, inherited_visibility, audio_sink, _, tracker) in
         visibility_query.iter_mut()
     {
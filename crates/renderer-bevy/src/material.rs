@@ -8,7 +8,9 @@ use bevy::render::render_resource::{
     VertexBufferLayout,
 };
 use bevy::sprite::{Material2d, Material2dKey};
-use lottie::prelude::GradientColor;
+use lottie::prelude::{
+    Effect, Gradient, GradientColor, LayerStyle, Rgb, SceneEffect, SceneLayerStyle,
+};
 use wgpu::*;
 
 #[derive(AsBindGroup, Clone, TypePath, Asset)]
@@ -26,6 +28,17 @@ pub struct LottieMaterial {
     pub mask_info: MaskDataUniform,
     #[uniform(4)]
     pub gradient: GradientDataUniform,
+    #[uniform(5)]
+    pub stroke_gradient: GradientDataUniform,
+    /// Layers below this one, only set for layers with a blend mode
+    #[texture(6)]
+    #[sampler(7)]
+    pub backdrop: Option<Handle<Image>>,
+    /// [BlendMode](lottie::prelude::BlendMode) of the layer
+    #[uniform(8)]
+    pub blend_mode: u32,
+    #[uniform(9)]
+    pub effects: EffectDataUniform,
 }
 
 impl Material2d for LottieMaterial {
@@ -98,15 +111,166 @@ pub struct GradientDataKey {
     use_gradient: bool,
 }
 
+/// Maximum number of stops of a gradient, extra stops are dropped
+pub const MAX_GRADIENT_STOPS: usize = 16;
+
 #[derive(Clone, Default, ShaderType)]
 pub struct GradientDataUniform {
     pub start: Vec2,
     pub end: Vec2,
+    /// Focal point of radial gradients
+    pub focal: Vec2,
+    pub use_gradient: u32,
+    /// 1 for linear gradients, 2 for radial gradients
+    pub gradient_ty: u32,
+    pub stop_count: u32,
     // #[size(runtime)]
     // TODO: change this to a Vec (which compiles to a storage buffer) when bevy supports it
     // tracking: https://github.com/bevyengine/bevy/issues/5499
-    pub use_gradient: u32,
-    pub stops: [GradientDataStop; 2],
+    pub stops: [GradientDataStop; MAX_GRADIENT_STOPS],
+}
+
+impl GradientDataUniform {
+    pub fn new(gradient: &Gradient, frame: f32) -> Self {
+        let start = gradient.start.value(frame);
+        let end = gradient.end.value(frame);
+        let focal = gradient.focal_point(frame);
+        let colors = gradient.colors.colors.value(frame);
+        if colors.len() > MAX_GRADIENT_STOPS {
+            log::warn!(
+                "gradient has {} stops, only {} are used",
+                colors.len(),
+                MAX_GRADIENT_STOPS
+            );
+        }
+        let mut stops: [GradientDataStop; MAX_GRADIENT_STOPS] = Default::default();
+        for (stop, color) in stops.iter_mut().zip(colors.iter()) {
+            *stop = GradientDataStop::from(color);
+        }
+        GradientDataUniform {
+            start: Vec2::new(start.x, start.y),
+            end: Vec2::new(end.x, end.y),
+            focal: Vec2::new(focal.x, focal.y),
+            use_gradient: 1,
+            gradient_ty: gradient.gradient_ty as u32,
+            stop_count: colors.len().min(MAX_GRADIENT_STOPS) as u32,
+            stops,
+        }
+    }
+}
+
+/// Maximum number of color effects of a layer, extra effects are dropped
+pub const MAX_COLOR_EFFECTS: usize = 4;
+
+/// Layer effects and color overlays mapping each pixel on its own, which are
+/// applied to every fragment of the layer
+#[derive(Clone, Default, ShaderType)]
+pub struct EffectDataUniform {
+    pub effect_count: u32,
+    pub effects: [EffectData; MAX_COLOR_EFFECTS],
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct EffectData {
+    /// 1 for fill, 2 for tint, 3 for tritone and 4 for color overlay
+    pub ty: u32,
+    /// Opacity of fill and color overlay, amount of tint or blend with
+    /// original of tritone
+    pub amount: f32,
+    /// Fill color, tint black and white, or tritone shadows, midtones and
+    /// highlights
+    pub colors: [Vec4; 3],
+}
+
+fn color_vec4(color: Rgb) -> Vec4 {
+    Vec4::new(color.r as f32, color.g as f32, color.b as f32, 255.0) / 255.0
+}
+
+impl EffectDataUniform {
+    pub fn new(effects: &[Effect], styles: &[LayerStyle], frame: f32) -> Self {
+        let color = color_vec4;
+        let overlays = styles.iter().filter_map(|style| {
+            let (c, opacity) = match SceneLayerStyle::new(style, frame)? {
+                SceneLayerStyle::ColorOverlay { color, opacity } => (color, opacity),
+                _ => return None,
+            };
+            Some(EffectData {
+                ty: 4,
+                amount: opacity,
+                colors: [color(c), Vec4::ZERO, Vec4::ZERO],
+            })
+        });
+        let effects = effects
+            .iter()
+            .filter_map(|effect| match SceneEffect::new(effect, &[], frame)? {
+                SceneEffect::Fill { color: c, opacity } => Some(EffectData {
+                    ty: 1,
+                    amount: opacity,
+                    colors: [color(c), Vec4::ZERO, Vec4::ZERO],
+                }),
+                SceneEffect::Tint {
+                    black,
+                    white,
+                    amount,
+                } => Some(EffectData {
+                    ty: 2,
+                    amount,
+                    colors: [color(black), color(white), Vec4::ZERO],
+                }),
+                SceneEffect::Tritone {
+                    highlights,
+                    midtones,
+                    shadows,
+                    blend,
+                } => Some(EffectData {
+                    ty: 3,
+                    amount: blend,
+                    colors: [color(shadows), color(midtones), color(highlights)],
+                }),
+                _ => None,
+            })
+            .chain(overlays)
+            .collect::<Vec<_>>();
+        if effects.len() > MAX_COLOR_EFFECTS {
+            log::warn!(
+                "layer has {} color effects, only {} are used",
+                effects.len(),
+                MAX_COLOR_EFFECTS
+            );
+        }
+        let mut result = EffectDataUniform {
+            effect_count: effects.len().min(MAX_COLOR_EFFECTS) as u32,
+            ..Default::default()
+        };
+        for (data, effect) in result.effects.iter_mut().zip(effects) {
+            *data = effect;
+        }
+        result
+    }
+
+    /// Paint every fragment with the color of a stroke or drop shadow
+    /// `style` at `frame`
+    pub fn style_paint(style: &LayerStyle, frame: f32) -> Self {
+        match SceneLayerStyle::new(style, frame) {
+            Some(SceneLayerStyle::Stroke { color, .. }) => Self::solid(color, 1.0),
+            Some(SceneLayerStyle::DropShadow { color, opacity, .. }) => Self::solid(color, opacity),
+            _ => Self::default(),
+        }
+    }
+
+    /// Paint every fragment with `color`, its alpha multiplied by `opacity`
+    pub fn solid(color: Rgb, opacity: f32) -> Self {
+        let mut result = EffectDataUniform {
+            effect_count: 1,
+            ..Default::default()
+        };
+        result.effects[0] = EffectData {
+            ty: 1,
+            amount: opacity,
+            colors: [color_vec4(color), Vec4::ZERO, Vec4::ZERO],
+        };
+        result
+    }
 }
 
 #[derive(Clone, Default, ShaderType)]
@@ -142,7 +306,8 @@ impl<'a> From<&'a GradientColor> for GradientDataStop {
 impl From<&LottieMaterial> for GradientDataKey {
     fn from(material: &LottieMaterial) -> Self {
         Self {
-            use_gradient: material.gradient.stops.is_empty(),
+            use_gradient: material.gradient.use_gradient == 1
+                || material.stroke_gradient.use_gradient == 1,
         }
     }
 }
@@ -3,10 +3,11 @@ use bevy::render::mesh::{Indices, MeshVertexAttribute};
 use bevy::render::render_asset::RenderAssetUsages;
 use bevy::render::render_resource::*;
 use bevy::sprite::*;
-use tiny_skia as ts;
+use lottie::prelude::DashPattern;
 use lyon::geom::euclid::point2;
 use lyon::lyon_tessellation::*;
 use lyon::path::{Event, Path as LyonPath};
+use tiny_skia as ts;
 
 use crate::material::LottieMaterial;
 use crate::shape::*;
@@ -23,6 +24,9 @@ pub struct Vertex {
     /// Use a texture instead of solid color, when this field contains a valid
     /// coord, `color` is ignored
     pub texture_anchor: [f32; 2],
+    /// [GradientSlot] of the material to compute colors from, `color` is
+    /// multiplied with the gradient. 0 for solid colors
+    pub gradient: u32,
 }
 
 type IndexType = u32;
@@ -34,6 +38,7 @@ pub type VertexBuffers = lyon::tessellation::VertexBuffers<Vertex, IndexType>;
 pub enum VertexConstructor {
     Solid(Color),
     Texture { anchor: [f32; 2], opacity: f32 },
+    Gradient { slot: GradientSlot, opacity: f32 },
 }
 
 impl VertexConstructor {
@@ -44,7 +49,10 @@ impl VertexConstructor {
                 c.set_a(c.a() * opacity);
                 VertexConstructor::Solid(c)
             }
-            SolidOrGradient::Gradient(_) => unimplemented!(),
+            SolidOrGradient::Gradient(slot) => VertexConstructor::Gradient {
+                slot: *slot,
+                opacity,
+            },
         }
     }
 }
@@ -57,6 +65,7 @@ impl FillVertexConstructor<Vertex> for VertexConstructor {
                 position: vertex.position().to_array(),
                 color: color.as_linear_rgba_u32(),
                 texture_anchor: [-1.0, -1.0],
+                gradient: 0,
             },
             VertexConstructor::Texture { anchor, opacity } => {
                 let mut color = Color::WHITE;
@@ -65,6 +74,17 @@ impl FillVertexConstructor<Vertex> for VertexConstructor {
                     position: vertex.position().to_array(),
                     color: color.as_linear_rgba_u32(),
                     texture_anchor: *anchor,
+                    gradient: 0,
+                }
+            }
+            VertexConstructor::Gradient { slot, opacity } => {
+                let mut color = Color::WHITE;
+                color.set_a(*opacity);
+                Vertex {
+                    position: vertex.position().to_array(),
+                    color: color.as_linear_rgba_u32(),
+                    texture_anchor: [-1.0, -1.0],
+                    gradient: *slot as u32,
                 }
             }
         }
@@ -79,6 +99,7 @@ impl StrokeVertexConstructor<Vertex> for VertexConstructor {
                 position: vertex.position().to_array(),
                 color: color.as_linear_rgba_u32(),
                 texture_anchor: [-1.0, -1.0],
+                gradient: 0,
             },
             VertexConstructor::Texture { anchor, opacity } => {
                 let mut color = Color::WHITE;
@@ -87,6 +108,17 @@ impl StrokeVertexConstructor<Vertex> for VertexConstructor {
                     position: vertex.position().to_array(),
                     color: color.as_linear_rgba_u32(),
                     texture_anchor: *anchor,
+                    gradient: 0,
+                }
+            }
+            VertexConstructor::Gradient { slot, opacity } => {
+                let mut color = Color::WHITE;
+                color.set_a(*opacity);
+                Vertex {
+                    position: vertex.position().to_array(),
+                    color: color.as_linear_rgba_u32(),
+                    texture_anchor: [-1.0, -1.0],
+                    gradient: *slot as u32,
                 }
             }
         }
@@ -168,7 +200,7 @@ fn stroke(
     mode: &Stroke,
     buffers: &mut VertexBuffers,
 ) {
-    let path = stroke_path(path, &mode.options);
+    let path = stroke_path(path, &mode.options, mode.dash.as_ref());
     let mut opts = FillOptions::default();
     opts.fill_rule = FillRule::NonZero;
     if let Err(e) = tess.tessellate_path(
@@ -209,11 +241,19 @@ fn build_mesh(buffers: &VertexBuffers) -> Mesh {
             .map(|v| v.color)
             .collect::<Vec<u32>>(),
     );
-    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; len]);
+    // The shader picks a gradient from the first uv coordinate
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        buffers
+            .vertices
+            .iter()
+            .map(|v| [v.gradient as f32, 0.0])
+            .collect::<Vec<[f32; 2]>>(),
+    );
     mesh
 }
 
-fn stroke_path(path: &LyonPath, opt: &StrokeOptions) -> LyonPath {
+fn stroke_path(path: &LyonPath, opt: &StrokeOptions, dash: Option<&DashPattern>) -> LyonPath {
     let mut ts_path_builder = ts::PathBuilder::new();
     for seg in path.iter() {
         match seg {
@@ -251,7 +291,7 @@ fn stroke_path(path: &LyonPath, opt: &StrokeOptions) -> LyonPath {
                 LineJoin::Round => ts::LineJoin::Round,
                 LineJoin::Bevel => ts::LineJoin::Bevel,
             },
-            dash: None,
+            dash: dash.and_then(|dash| ts::StrokeDash::new(dash.array.clone(), dash.offset)),
         },
         1.0,
     );
@@ -8,7 +8,10 @@ use bevy::render::view::RenderLayers;
 use bevy_tweening_captured::{Animator, EaseMethod, Sequence, Tracks, Tween};
 use lottie::prelude::{Transform as LottieTransform, *};
 
-use crate::lens::{OpacityLens, PathFactoryLens, PathLens, StrokeWidthLens, TransformLens};
+use crate::lens::{
+    OpacityLens, PathFactoryLens, PathLens, StrokeDashLens, StrokeWidthLens, StyleOffsetLens,
+    TextStyleLens, TransformLens,
+};
 use crate::material::*;
 use crate::plugin::MaskMarker;
 use crate::shape::ShapeBundle;
@@ -28,6 +31,14 @@ pub struct BevyStagedLayer<'a> {
     pub mask_count: u32,
     pub mask_registry: &'a mut HashMap<Id, u32>,
     pub zindex_window: f32,
+    /// Render layers of the main camera and of the backdrop cameras this
+    /// layer is visible to
+    pub render_layers: RenderLayers,
+    /// Backdrop of this layer if it has a blend mode
+    pub backdrop: Option<Handle<Image>>,
+    /// Set while spawning copies of the layer shapes for a layer style, which
+    /// paints them with a single color
+    pub style_paint: Option<LayerStyle>,
 }
 
 impl<'a> BevyStagedLayer<'a> {
@@ -39,6 +50,15 @@ impl<'a> BevyStagedLayer<'a> {
             .map(|s| s.as_str())
             .unwrap_or("Layer")
             .to_string();
+        // Other effects need the whole layer rendered first
+        if self.layer.effects.iter().any(|effect| {
+            !matches!(
+                effect.ty,
+                EffectType::Fill | EffectType::Tint | EffectType::Tritone
+            )
+        }) {
+            log::warn!("only fill, tint and tritone effects are supported");
+        }
         let mut c = commands.spawn(Name::new(name));
         let mut initial_transform = Transform::from_matrix(self.layer.transform.value(0.0));
         initial_transform.translation.z = self.layer.zindex as f32 * -1.0;
@@ -57,6 +77,9 @@ impl<'a> BevyStagedLayer<'a> {
         );
         match &self.layer.content {
             RenderableContent::Shape(shapes) => {
+                if !self.layer.is_mask {
+                    self.spawn_styles(shapes, &mut c);
+                }
                 self.spawn_shapes(&shapes, self.zindex_window, &mut c);
             }
             RenderableContent::Media(media) => {
@@ -94,6 +117,9 @@ impl<'a> BevyStagedLayer<'a> {
                     };
                     bundle.sprite.flip_x = true;
                     c.insert(bundle);
+                    if !self.layer.is_mask {
+                        c.insert(self.render_layers);
+                    }
                 } else if mime.mime_type().starts_with("audio") {
                     let source = AudioSource {
                         bytes: media.content.as_slice().into(),
@@ -134,6 +160,66 @@ impl<'a> BevyStagedLayer<'a> {
         Ok(id)
     }
 
+    /// Stroke and drop shadow styles are drawn by copies of the layer shapes
+    /// painted with a single color, in the last zindex slot of the layer.
+    /// Color overlays are applied by the material of each shape instead
+    fn spawn_styles(&mut self, group: &ShapeGroup, c: &mut EntityCommands) {
+        let count = group.styled_shapes().shape_count() as f32 + 1.0;
+        let step = self.zindex_window / count;
+        for style in self.layer.styles.clone() {
+            let (copy, offset, zindex) = match (&style.kind, SceneLayerStyle::new(&style, 0.0)) {
+                (LayerStyleKind::Stroke(stroke), Some(SceneLayerStyle::Stroke { .. })) => {
+                    // The outline is grown by the stroke size and filled
+                    let mut copy = group.clone();
+                    copy.shapes.push(ShapeLayer {
+                        name: None,
+                        hidden: false,
+                        shape: Shape::OffsetPath(OffsetPath {
+                            amount: stroke.size.clone(),
+                            line_join: LineJoin::Round,
+                            miter_limit: Animated::from_value(4.0),
+                        }),
+                    });
+                    (copy, Vector2D::zero(), count - 1.0)
+                }
+                (_, Some(SceneLayerStyle::DropShadow { offset, .. })) => {
+                    (group.clone(), offset, count - 0.5)
+                }
+                (_, Some(SceneLayerStyle::ColorOverlay { .. })) => continue,
+                _ => {
+                    log::warn!("layer style {:?} is not supported", style.name);
+                    continue;
+                }
+            };
+            let mut commands = c.commands();
+            let mut entity = commands.spawn(Name::new(style.name.clone().unwrap_or_default()));
+            entity.insert(VisibilityBundle::default());
+            entity.insert(TransformBundle::from_transform(Transform::from_xyz(
+                offset.x,
+                offset.y,
+                -zindex * step,
+            )));
+            if let LayerStyleKind::DropShadow(shadow) = &style.kind {
+                if shadow.angle.is_animated() || shadow.distance.is_animated() {
+                    let lens = StyleOffsetLens {
+                        style: style.clone(),
+                        frames: self.layer.end_frame,
+                        zindex: -zindex * step,
+                    };
+                    let secs = (lens.frames / self.layer.frame_rate).max(f32::EPSILON);
+                    let tween = Tween::new(EaseMethod::Linear, Duration::from_secs_f32(secs), lens);
+                    entity.insert(Animator::new(tween).with_state(AnimatorState::Paused));
+                    entity.insert(FrameTracker(self.layer.frame_transform_hierarchy.clone()));
+                }
+            }
+            self.style_paint = Some(style);
+            self.spawn_shapes(&copy, step / 2.0, &mut entity);
+            self.style_paint = None;
+            let id = entity.id();
+            c.add_child(id);
+        }
+    }
+
     fn spawn_shapes(&mut self, group: &ShapeGroup, zindex_window: f32, c: &mut EntityCommands) {
         let shapes = group.styled_shapes();
         let count = shapes.shape_count() as f32 + 1.0;
@@ -170,6 +256,14 @@ impl<'a> BevyStagedLayer<'a> {
                             })
                         }
                     }
+                    // same for modifiers, which apply after the ones inside the group
+                    for modifier in &shape.modifiers {
+                        new_group.shapes.push(ShapeLayer {
+                            name: None,
+                            hidden: false,
+                            shape: modifier.clone(),
+                        })
+                    }
                     self.spawn_shapes(&new_group, step, &mut group);
                     Some(group.id())
                 }
@@ -191,9 +285,14 @@ impl<'a> BevyStagedLayer<'a> {
         if shape.shape.hidden {
             return None;
         }
+        if !shape.shape.shape.is_shape() {
+            log::warn!("shape {:?} is not supported", shape.shape.shape);
+            return None;
+        }
         let mut draw_mode = utils::shape_draw_mode(&shape);
         let opacity = OpacityHierarchy::from(&self.layer.transform_hierarchy);
-        let global_opacity = opacity.initial_value();
+        // Repeater copies fade from their start to their end opacity
+        let global_opacity = opacity.initial_value() * shape.transform.opacity_value(0.0) / 100.0;
         if global_opacity < 1.0 {
             if let Some(fill) = draw_mode.fill.as_mut() {
                 fill.opacity *= global_opacity;
@@ -212,7 +311,7 @@ impl<'a> BevyStagedLayer<'a> {
                     UVec4::default(),
                     UVec4::default(),
                 ],
-                mask_count: self.layer.mask_hierarchy.len() as u32,
+                mask_count: self.layer.mask_hierarchy.len().min(4) as u32,
                 mask_total_count: self.mask_count,
             },
             mask: if !self.layer.is_mask {
@@ -221,13 +320,35 @@ impl<'a> BevyStagedLayer<'a> {
                 None
             },
             gradient: GradientDataUniform::default(),
+            stroke_gradient: GradientDataUniform::default(),
+            backdrop: self.backdrop.clone(),
+            blend_mode: self.layer.blend_mode as u32,
+            effects: match self.style_paint.as_ref() {
+                Some(style) => EffectDataUniform::style_paint(style, 0.0),
+                None => EffectDataUniform::new(&self.layer.effects, &self.layer.styles, 0.0),
+            },
         };
 
         if !self.layer.is_mask {
-            for (index, item) in self.layer.mask_hierarchy.masks().iter().enumerate() {
+            let masks = self.layer.mask_hierarchy.masks();
+            if masks.len() > material.mask_info.masks.len() {
+                log::warn!(
+                    "only {} masks per layer are supported",
+                    material.mask_info.masks.len()
+                );
+            }
+            for (info, item) in material.mask_info.masks.iter_mut().zip(masks) {
                 let mask_index = *self.mask_registry.get(&item.id).unwrap();
                 let mode = item.mode as u32;
-                material.mask_info.masks[index] = UVec4::new(mask_index, mode, 0, 0);
+                // Layer masks are combined in the shader, see `combine_mask`
+                let (mask_mode, flags) = match item.mask {
+                    Some(mask) => (
+                        mask.mode as u32,
+                        mask.inverted as u32 | ((mask.index == 0) as u32) << 1,
+                    ),
+                    None => (0, 0),
+                };
+                *info = UVec4::new(mask_index, mode, mask_mode, flags);
             }
         }
 
@@ -246,12 +367,14 @@ impl<'a> BevyStagedLayer<'a> {
 
         if self.layer.is_mask {
             c.insert(MaskMarker).insert(RenderLayers::from_layers(&[1]));
+        } else {
+            c.insert(self.render_layers);
         }
 
         let mut initial_pos = Vector2D::new(0.0, 0.0);
         match &shape.shape.shape {
             Shape::Ellipse(ellipse) => {
-                let path = ellipse.path(0.0);
+                let path = shape.trim_path(shape.modify_path(ellipse.path(0.0), 0.0), 0.0);
                 c.insert(ShapeBundle::new(path, draw_mode, transform));
 
                 if let Some(animator) = self.transform_animator(&shape.transform, zindex, None) {
@@ -260,13 +383,13 @@ impl<'a> BevyStagedLayer<'a> {
                 if let Some(animator) = self.draw_mode_animator(&shape) {
                     c.insert(animator);
                 }
-                if let Some(animator) = self.path_animator(ellipse.clone()) {
+                if let Some(animator) = self.path_animator(ellipse.clone(), &shape) {
                     c.insert(animator);
                 }
             }
             Shape::PolyStar(star) => {
                 initial_pos = star.position.initial_value();
-                let path = star.path(0.0);
+                let path = shape.trim_path(shape.modify_path(star.path(0.0), 0.0), 0.0);
                 c.insert(ShapeBundle::new(path, draw_mode, transform));
                 if let Some(animator) = self.transform_animator(&shape.transform, zindex, None) {
                     c.insert(animator);
@@ -274,10 +397,13 @@ impl<'a> BevyStagedLayer<'a> {
                 if let Some(animator) = self.draw_mode_animator(&shape) {
                     c.insert(animator);
                 }
+                if let Some(animator) = self.path_animator(star.clone(), &shape) {
+                    c.insert(animator);
+                }
             }
             Shape::Rectangle(rect) => {
                 initial_pos = rect.position.initial_value();
-                let path = rect.path(0.0);
+                let path = shape.trim_path(shape.modify_path(rect.path(0.0), 0.0), 0.0);
                 c.insert(ShapeBundle::new(path, draw_mode, transform));
                 if let Some(animator) = self.transform_animator(&shape.transform, zindex, None) {
                     c.insert(animator);
@@ -285,10 +411,13 @@ impl<'a> BevyStagedLayer<'a> {
                 if let Some(animator) = self.draw_mode_animator(&shape) {
                     c.insert(animator);
                 }
+                if let Some(animator) = self.path_animator(rect.clone(), &shape) {
+                    c.insert(animator);
+                }
             }
             Shape::Path { d, text_range } => {
                 let beziers = d.initial_value();
-                let path = beziers.path(0.0);
+                let path = shape.trim_path(shape.modify_path(beziers.path(0.0), 0.0), 0.0);
                 c.insert(ShapeBundle::new(path, draw_mode, transform));
 
                 if let Some(animator) =
@@ -301,7 +430,10 @@ impl<'a> BevyStagedLayer<'a> {
                 }
 
                 // Add bezier tween
-                if d.is_animated() || !shape.trims.is_empty() {
+                if d.is_animated()
+                    || !shape.trims.is_empty()
+                    || shape.modifiers.iter().any(PathModifier::is_animated)
+                {
                     let tween = d.keyframes.tween(
                         self.layer.end_frame,
                         self.layer.frame_rate,
@@ -311,40 +443,79 @@ impl<'a> BevyStagedLayer<'a> {
                             start_frame,
                             end_frame,
                             trims: shape.trims.clone(),
+                            modifiers: shape.modifiers.clone(),
                         },
                     );
                     let animator = Animator::new(tween).with_state(AnimatorState::Paused);
                     c.insert(animator);
                 }
             }
-            Shape::Group { .. } => {
-                unreachable!()
-            }
-            _ => {
-                println!("{:?}", shape.shape.shape);
-                todo!()
+            Shape::Merge(merge) => {
+                let path = shape.trim_path(shape.modify_path(merge.path(0.0), 0.0), 0.0);
+                c.insert(ShapeBundle::new(path, draw_mode, transform));
+                if let Some(animator) = self.transform_animator(&shape.transform, zindex, None) {
+                    c.insert(animator);
+                }
+                if let Some(animator) = self.draw_mode_animator(&shape) {
+                    c.insert(animator);
+                }
+                if let Some(animator) = self.path_animator(merge.clone(), &shape) {
+                    c.insert(animator);
+                }
             }
+            // Groups are spawned by `spawn_shapes`, other shapes were skipped
+            _ => unreachable!(),
         }
 
-        // register gradient texture if any
-        if let AnyFill::Gradient(g) = &shape.fill {
-            let start = g.gradient.start.initial_value();
-            let end = g.gradient.end.initial_value();
-            let stops = g.gradient.colors.colors.initial_value();
-            assert!(stops.len() >= 2, "gradient stops must be at least 2");
-            let stops = stops.iter().map(GradientDataStop::from).collect::<Vec<_>>();
-            material.gradient.stops = [stops[0].clone(), stops[1].clone()];
-            material.gradient.start = Vec2::new(start.x, start.y);
-            material.gradient.end = Vec2::new(end.x, end.y);
-            material.gradient.use_gradient = 1;
+        // gradient stops and positions are uploaded as material uniforms
+        let fill_gradient = match &shape.fill {
+            AnyFill::Gradient(g) => Some(g.gradient.clone()),
+            _ => None,
+        };
+        let stroke_gradient = match &shape.stroke {
+            Some(AnyStroke::Gradient(g)) => Some(g.gradient.clone()),
+            _ => None,
+        };
+        if let Some(gradient) = fill_gradient.as_ref() {
+            material.gradient = GradientDataUniform::new(gradient, 0.0);
+        }
+        if let Some(gradient) = stroke_gradient.as_ref() {
+            material.stroke_gradient = GradientDataUniform::new(gradient, 0.0);
+        }
+        if fill_gradient
+            .iter()
+            .chain(stroke_gradient.iter())
+            .any(Gradient::is_animated)
+        {
+            c.insert(GradientAnimator {
+                fill: fill_gradient,
+                stroke: stroke_gradient,
+            });
+        }
+
+        let animated = match self.style_paint.as_ref().map(|style| &style.kind) {
+            Some(LayerStyleKind::Stroke(stroke)) => stroke.color.is_animated(),
+            Some(LayerStyleKind::DropShadow(shadow)) => {
+                shadow.color.is_animated() || shadow.opacity.is_animated()
+            }
+            Some(_) => false,
+            None => {
+                self.layer.effects.iter().any(Effect::is_animated)
+                    || self.layer.styles.iter().any(|style| match &style.kind {
+                        LayerStyleKind::ColorOverlay(overlay) => {
+                            overlay.color.is_animated() || overlay.opacity.is_animated()
+                        }
+                        _ => false,
+                    })
+            }
+        };
+        if animated {
+            c.insert(EffectAnimator {
+                effects: self.layer.effects.clone(),
+                styles: self.layer.styles.clone(),
+                style_paint: self.style_paint.clone(),
+            });
         }
-        // let stroke_index = if let AnyFill::Gradient(g) = &shape.fill {
-        //     self.gradient
-        //         .register(&g.gradient, self.meshes, self.gradient_assets, commands)
-        //         as f32
-        // } else {
-        //     -1.0
-        // };
 
         let handle = self.material_assets.add(material);
         c.insert(handle);
@@ -405,12 +576,48 @@ impl<'a> BevyStagedLayer<'a> {
                     |start, end, _, _| StrokeWidthLens { start, end },
                 ));
             }
+            // Dashes are recomputed every frame, "marching ants" usually
+            // animate the offset
+            if stroke.dashes().iter().any(|dash| dash.length.is_animated()) {
+                let lens = StrokeDashLens {
+                    dashes: stroke.dashes().to_vec(),
+                    frames: self.layer.end_frame,
+                };
+                let secs = (lens.frames / self.layer.frame_rate).max(f32::EPSILON);
+                let tween = Tween::new(EaseMethod::Linear, Duration::from_secs_f32(secs), lens);
+                tweens.push(Sequence::from_single(tween));
+            }
         }
 
         let opacity = OpacityHierarchy::from(&self.layer.transform_hierarchy);
-        if opacity.is_animated() {
+        if let Shape::Path {
+            text_range: Some(text_range),
+            ..
+        } = &shape.shape.shape
+        {
+            let lens = TextStyleLens {
+                text_range: text_range.clone(),
+                opacity,
+                transform: shape.transform.clone(),
+                frames: self.layer.end_frame,
+                fill_color: match &shape.fill {
+                    AnyFill::Solid(fill) => Some(fill.color.clone()),
+                    AnyFill::Gradient(_) => None,
+                },
+                stroke_color: match &shape.stroke {
+                    Some(AnyStroke::Solid(stroke)) => Some(stroke.color.clone()),
+                    _ => None,
+                },
+                stroke_width: shape.stroke.as_ref().map(|s| s.width().clone()),
+                stroke_opacity: shape.stroke.as_ref().map(|s| s.opacity().clone()),
+            };
+            let secs = (lens.frames / self.layer.frame_rate).max(f32::EPSILON);
+            let tween = Tween::new(EaseMethod::Linear, Duration::from_secs_f32(secs), lens);
+            tweens.push(Sequence::from_single(tween));
+        } else if opacity.is_animated() || shape.transform.is_opacity_animated() {
             let opacity_lens = OpacityLens {
                 opacity,
+                transform: shape.transform.clone(),
                 frames: self.layer.end_frame,
                 fill_opacity: shape.fill.opacity().clone(),
                 stroke_opacity: shape.stroke.as_ref().map(|s| s.opacity().clone()),
@@ -436,8 +643,12 @@ impl<'a> BevyStagedLayer<'a> {
     fn path_animator(
         &self,
         factory: impl PathFactory + Send + Sync + 'static,
+        shape: &StyledShape,
     ) -> Option<Animator<Path>> {
-        if !factory.is_animated() {
+        if !factory.is_animated()
+            && shape.trims.is_empty()
+            && !shape.modifiers.iter().any(PathModifier::is_animated)
+        {
             return None;
         }
         let frames = self.layer.end_frame - self.layer.start_frame;
@@ -449,6 +660,8 @@ impl<'a> BevyStagedLayer<'a> {
                 start_frame: self.layer.start_frame,
                 end_frame: self.layer.end_frame,
                 factory: Box::new(factory),
+                modifiers: shape.modifiers.clone(),
+                trims: shape.trims.clone(),
             },
         )))
     }
@@ -459,3 +672,42 @@ pub struct FrameTracker(FrameTransformHierarchy);
 
 #[derive(Component)]
 pub struct LottieAudio;
+
+/// Gradients of a shape whose stops or positions are animated, the material
+/// uniforms are recomputed every frame
+#[derive(Component)]
+pub struct GradientAnimator {
+    pub fill: Option<Gradient>,
+    pub stroke: Option<Gradient>,
+}
+
+/// Effects and styles of a layer whose parameters are animated, the material
+/// uniforms are recomputed every frame
+#[derive(Component)]
+pub struct EffectAnimator {
+    pub effects: Vec<Effect>,
+    pub styles: Vec<LayerStyle>,
+    /// Style painting a copy of the layer shapes, which replaces the effects
+    /// and styles of the layer
+    pub style_paint: Option<LayerStyle>,
+}
+
+impl EffectAnimator {
+    pub fn update(&self, material: &mut LottieMaterial, frame: f32) {
+        material.effects = match self.style_paint.as_ref() {
+            Some(style) => EffectDataUniform::style_paint(style, frame),
+            None => EffectDataUniform::new(&self.effects, &self.styles, frame),
+        };
+    }
+}
+
+impl GradientAnimator {
+    pub fn update(&self, material: &mut LottieMaterial, frame: f32) {
+        if let Some(gradient) = self.fill.as_ref() {
+            material.gradient = GradientDataUniform::new(gradient, frame);
+        }
+        if let Some(gradient) = self.stroke.as_ref() {
+            material.stroke_gradient = GradientDataUniform::new(gradient, frame);
+        }
+    }
+}
@@ -1,9 +1,9 @@
 use bevy::ecs::reflect::ReflectComponent;
-use bevy::math::Vec2;
 use bevy::prelude::{Bundle, Color, Component, Deref, GlobalTransform, Transform, Visibility};
 use bevy::reflect::Reflect;
 use bevy::render::view::{InheritedVisibility, ViewVisibility};
 use bevy::sprite::Mesh2dHandle;
+use lottie::prelude::DashPattern;
 use lyon::path::Path as LyonPath;
 use lyon::tessellation::{FillOptions, StrokeOptions};
 
@@ -36,6 +36,8 @@ pub struct Stroke {
     pub color: SolidOrGradient,
     #[reflect(ignore)]
     pub options: StrokeOptions,
+    #[reflect(ignore)]
+    pub dash: Option<DashPattern>,
     pub opacity: f32,
 }
 
@@ -43,15 +45,16 @@ pub struct Stroke {
 #[reflect(PartialEq)]
 pub enum SolidOrGradient {
     Solid(Color),
-    Gradient(Gradient),
+    /// Colors are computed by the shader from a gradient of the material
+    Gradient(GradientSlot),
 }
 
-#[derive(Clone, PartialEq, Reflect, Component)]
-#[reflect(Component)]
-pub struct Gradient {
-    start: Vec2,
-    end: Vec2,
-    ty: (),
+/// Gradient uniform of [LottieMaterial](crate::material::LottieMaterial)
+/// used by a fill or stroke
+#[derive(Clone, Copy, PartialEq, Reflect)]
+pub enum GradientSlot {
+    Fill = 1,
+    Stroke = 2,
 }
 
 #[derive(Bundle)]
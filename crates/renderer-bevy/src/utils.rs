@@ -1,23 +1,29 @@
 use bevy::prelude::Color;
 use lottie::prelude::{
     AnyFill, AnyStroke, FillRule as LottieFillRule, LineCap as LottieLineCap,
-    LineJoin as LottieLineJoin, Rgb, StyledShape,
+    LineJoin as LottieLineJoin, StyledShape,
 };
 use lyon::path::FillRule;
 use lyon::tessellation::{FillOptions, LineCap, LineJoin, StrokeOptions};
 
-use crate::shape::{DrawMode, Fill, SolidOrGradient, Stroke};
+use crate::shape::{DrawMode, Fill, GradientSlot, SolidOrGradient, Stroke};
 
-/// Get an initial draw mode for a shape. If fill/stroke uses gradient fill, a
-/// default white color is used in this method
+/// Get an initial draw mode for a shape. Gradient colors are computed by the
+/// shader, only their opacity is part of the draw mode
 pub fn shape_draw_mode(shape: &StyledShape) -> DrawMode {
-    let (fill, fill_opacity) = match &shape.fill {
+    let (color, fill_opacity, opacity) = match &shape.fill {
         AnyFill::Solid(fill) => {
-            let fill_opacity = (fill.opacity.initial_value() * 255.0) as u8;
+            let fill_opacity = (fill.opacity.initial_value() / 100.0 * 255.0) as u8;
             let fill = fill.color.initial_value();
-            (fill, fill_opacity)
+            let color = Color::rgba_u8(fill.r, fill.g, fill.b, fill_opacity);
+            (SolidOrGradient::Solid(color), fill_opacity, 1.0)
+        }
+        AnyFill::Gradient(gradient) => {
+            let opacity = gradient.opacity.initial_value() / 100.0;
+            let fill_opacity = (opacity * 255.0) as u8;
+            let color = SolidOrGradient::Gradient(GradientSlot::Fill);
+            (color, fill_opacity, opacity)
         }
-        AnyFill::Gradient(gradient) => (Rgb::new_u8(255, 255, 255), 255),
     };
     let fill_rule = match &shape.fill {
         AnyFill::Solid(fill) => &fill.fill_rule,
@@ -28,9 +34,9 @@ pub fn shape_draw_mode(shape: &StyledShape) -> DrawMode {
             None
         } else {
             let mut fill = Fill {
-                color: SolidOrGradient::Solid(Color::rgba_u8(fill.r, fill.g, fill.b, fill_opacity)),
+                color,
                 options: FillOptions::default(),
-                opacity: 1.0,
+                opacity,
             };
             fill.options.fill_rule = match fill_rule {
                 LottieFillRule::NonZero => FillRule::NonZero,
@@ -40,24 +46,24 @@ pub fn shape_draw_mode(shape: &StyledShape) -> DrawMode {
         },
         stroke: shape.stroke.as_ref().map(|stroke| {
             let stroke_width: f32 = stroke.width().initial_value();
-            let (color, stroke_opacity) = match &stroke {
+            let (color, opacity) = match &stroke {
                 AnyStroke::Solid(stroke) => {
-                    let stroke_opacity = (stroke.opacity.initial_value() * 255.0) as u8;
+                    let stroke_opacity = (stroke.opacity.initial_value() / 100.0 * 255.0) as u8;
                     let stroke = stroke.color.initial_value();
-                    (stroke, stroke_opacity)
+                    let color = Color::rgba_u8(stroke.r, stroke.g, stroke.b, stroke_opacity);
+                    (SolidOrGradient::Solid(color), 1.0)
                 }
-                AnyStroke::Gradient(gradient) => (Rgb::new_u8(255, 255, 255), 255),
+                AnyStroke::Gradient(gradient) => (
+                    SolidOrGradient::Gradient(GradientSlot::Stroke),
+                    gradient.opacity.initial_value() / 100.0,
+                ),
             };
 
             let mut result = Stroke {
-                color: SolidOrGradient::Solid(Color::rgba_u8(
-                    color.r,
-                    color.g,
-                    color.b,
-                    stroke_opacity,
-                )),
+                color,
                 options: StrokeOptions::default().with_line_width(stroke_width),
-                opacity: 1.0,
+                dash: stroke.dash_pattern(0.0),
+                opacity,
             };
             let line_cap = match stroke.line_cap() {
                 LottieLineCap::Butt => LineCap::Butt,
@@ -1,6 +1,7 @@
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use lottie::prelude::BlendMode;
 use lottie::{Config, Error, HeadlessConfig, Lottie, MockFont, Renderer};
 use lottie_renderer_bevy::BevyRenderer;
 use rstest::rstest;
@@ -70,3 +71,321 @@ fn check_fixture(
     });
     Ok(())
 }
+
+/// Colors are quantized to 8 bits and converted between sRGB and linear light
+/// by the GPU, so computed channels may be off by a few steps
+const QUANTIZATION_TOLERANCE: i32 = 4;
+
+/// First frame of a checked fixture, for features checked against values
+/// computed from their definition since no frame of this renderer is checked
+/// in for them
+struct FirstFrame {
+    name: String,
+    data: Vec<u8>,
+    width: usize,
+}
+
+impl FirstFrame {
+    fn new(path: &Path) -> Self {
+        let mut f = File::open(path).unwrap();
+        let lottie = Lottie::<MockFont>::from_reader(&mut f, "../../").unwrap();
+        let width = lottie.model.width as usize;
+        let (mut renderer, frame_stream) = BevyRenderer::new();
+        renderer.load_lottie(
+            lottie,
+            Config::Headless(HeadlessConfig {
+                target: lottie::Target::Default,
+                filename: String::from("test.webp"),
+                frame: None,
+            }),
+        );
+        <BevyRenderer as Renderer<MockFont>>::render(&mut renderer);
+        let frame = smol::block_on(async {
+            smol::pin!(frame_stream);
+            frame_stream.next().await.unwrap()
+        });
+        FirstFrame {
+            name: path.file_stem().unwrap().to_string_lossy().to_string(),
+            data: frame.data,
+            width,
+        }
+    }
+
+    /// RGBA at `(x, y)`
+    fn pixel(&self, (x, y): (usize, usize)) -> &[u8] {
+        &self.data[(y * self.width + x) * 4..][..4]
+    }
+
+    fn assert_color(&self, at: (usize, usize), expected: [f32; 3], tolerance: i32) {
+        let pixel = &self.pixel(at)[..3];
+        let expected = expected.map(|c| (c * 255.0).round() as i32);
+        assert!(
+            pixel
+                .iter()
+                .zip(expected)
+                .all(|(c, e)| (*c as i32 - e).abs() <= tolerance),
+            "{}: {:?} at {:?} should be {:?}",
+            self.name,
+            pixel,
+            at,
+            expected
+        );
+    }
+}
+
+/// Source colors of the columns and backdrop colors of the rows of the blend
+/// mode fixtures, cells are 30 pixels wide
+const BLEND_SOURCES: [[f32; 3]; 3] = [[0.9, 0.2, 0.2], [0.2, 0.8, 0.3], [0.3, 0.3, 0.9]];
+const BLEND_BACKDROPS: [[f32; 3]; 3] = [[0.15, 0.15, 0.15], [0.5, 0.5, 0.5], [0.95, 0.85, 0.3]];
+
+/// No frame of the blend mode fixtures is checked in for this renderer, so
+/// blended cells are checked against the formulas of the W3C compositing
+/// specification, applied to sRGB values like lottie-web
+#[rstest]
+fn check_blend_mode(#[files("../../fixtures/ui/checked/BlendModes/*.json")] path: PathBuf) {
+    let model = lottie::prelude::Model::from_reader(&mut File::open(&path).unwrap()).unwrap();
+    let mode = model.layers[0].blend_mode.unwrap();
+    let frame = FirstFrame::new(&path);
+    for (row, backdrop) in BLEND_BACKDROPS.into_iter().enumerate() {
+        for (column, source) in BLEND_SOURCES.into_iter().enumerate() {
+            let at = (column * 30 + 15, row * 30 + 15);
+            frame.assert_color(at, blend(mode, backdrop, source), QUANTIZATION_TOLERANCE);
+        }
+    }
+}
+
+/// Background of the effect fixtures
+const EFFECT_BACKGROUND: [f32; 3] = [0.95, 0.95, 0.85];
+/// Gray levels of the strips of the color effect fixtures, 20 pixels wide
+const EFFECT_STRIPS: [f32; 5] = [1.0, 0.75, 0.5, 0.25, 0.0];
+
+fn effect_frame(name: &str) -> FirstFrame {
+    let path = format!("../../fixtures/ui/checked/Effects/{}.json", name);
+    FirstFrame::new(Path::new(&path))
+}
+
+/// Colors of the gray strips mapped by `f`, above and below the colored bar
+/// across them
+fn assert_strips(frame: &FirstFrame, f: impl Fn(f32) -> [f32; 3]) {
+    for y in [10, 90] {
+        for (strip, gray) in EFFECT_STRIPS.into_iter().enumerate() {
+            frame.assert_color((strip * 20 + 10, y), f(gray), QUANTIZATION_TOLERANCE);
+        }
+    }
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+/// Translucent shapes are composited in linear light by the GPU, unlike the
+/// effects themselves
+fn composite(backdrop: [f32; 3], source: [f32; 3], alpha: f32) -> [f32; 3] {
+    let linear = |c: f32| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let srgb = |c: f32| {
+        if c <= 0.0031308 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    [0, 1, 2].map(|i| srgb(linear(backdrop[i]) + (linear(source[i]) - linear(backdrop[i])) * alpha))
+}
+
+#[test]
+fn check_fill_effect() {
+    let frame = effect_frame("Fill");
+    // The fill replaces the colors of the layer, at 75% opacity
+    let fill = composite(EFFECT_BACKGROUND, [0.1, 0.4, 0.9], 0.75);
+    frame.assert_color((50, 50), fill, QUANTIZATION_TOLERANCE);
+    frame.assert_color((5, 5), EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+}
+
+#[test]
+fn check_tint_effect() {
+    // Black and white are mapped to the tint colors, mixed at 80% with the
+    // original
+    assert_strips(&effect_frame("Tint"), |gray| {
+        let mapped = mix([0.2, 0.0, 0.4], [1.0, 0.9, 0.5], gray);
+        mix([gray; 3], mapped, 0.8)
+    });
+}
+
+#[test]
+fn check_tritone_effect() {
+    // Shadows, midtones and highlights are mapped to their colors, blended
+    // with 20% of the original
+    let (highlights, midtones, shadows) = ([1.0, 1.0, 0.8], [0.9, 0.3, 0.3], [0.1, 0.0, 0.3]);
+    assert_strips(&effect_frame("Tritone"), |gray| {
+        let mapped = if gray < 0.5 {
+            mix(shadows, midtones, gray * 2.0)
+        } else {
+            mix(midtones, highlights, gray * 2.0 - 1.0)
+        };
+        mix(mapped, [gray; 3], 0.2)
+    });
+}
+
+/// Colors of the rectangle and of the ellipse of the layer style fixtures,
+/// the rectangle is above the ellipse
+const STYLE_RECTANGLE: [f32; 3] = [0.2, 0.6, 0.9];
+const STYLE_ELLIPSE: [f32; 3] = [0.9, 0.5, 0.1];
+
+fn style_frame(name: &str) -> FirstFrame {
+    let path = format!("../../fixtures/ui/checked/LayerStyles/{}.json", name);
+    FirstFrame::new(Path::new(&path))
+}
+
+#[test]
+fn check_color_overlay_style() {
+    let frame = style_frame("ColorOverlay");
+    // The overlay color covers the shapes at 70% of its opacity
+    let overlay = [0.1, 0.7, 0.3];
+    frame.assert_color(
+        (40, 45),
+        mix(STYLE_RECTANGLE, overlay, 0.7),
+        QUANTIZATION_TOLERANCE,
+    );
+    frame.assert_color(
+        (72, 67),
+        mix(STYLE_ELLIPSE, overlay, 0.7),
+        QUANTIZATION_TOLERANCE,
+    );
+    frame.assert_color((90, 10), EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+}
+
+#[test]
+fn check_stroke_style() {
+    let frame = style_frame("Stroke");
+    // The stroke grows the outline of the shapes by its size of 4 pixels,
+    // below them
+    let stroke = [0.6, 0.1, 0.5];
+    for at in [(18, 45), (40, 22), (65, 77), (81, 60)] {
+        frame.assert_color(at, stroke, QUANTIZATION_TOLERANCE);
+    }
+    for at in [(14, 45), (40, 18), (65, 81), (86, 60)] {
+        frame.assert_color(at, EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+    }
+    frame.assert_color((22, 45), STYLE_RECTANGLE, QUANTIZATION_TOLERANCE);
+    frame.assert_color((70, 70), STYLE_ELLIPSE, QUANTIZATION_TOLERANCE);
+}
+
+#[test]
+fn check_drop_shadow_style() {
+    let frame = style_frame("DropShadow");
+    // Drop shadows are not blurred, the shadow of the rectangle is a copy of
+    // it cast 8 pixels away from a light at 120 degrees, at 60% opacity
+    let shadow = composite(EFFECT_BACKGROUND, [0.0; 3], 0.6);
+    for at in [(40, 68), (40, 70), (62, 38)] {
+        frame.assert_color(at, shadow, QUANTIZATION_TOLERANCE);
+    }
+    for at in [(40, 74), (66, 40)] {
+        frame.assert_color(at, EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+    }
+    frame.assert_color((40, 45), STYLE_RECTANGLE, QUANTIZATION_TOLERANCE);
+    frame.assert_color((70, 70), STYLE_ELLIPSE, QUANTIZATION_TOLERANCE);
+    frame.assert_color((10, 10), EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+}
+
+/// Result of an opaque `source` blended over an opaque `backdrop`
+fn blend(mode: BlendMode, backdrop: [f32; 3], source: [f32; 3]) -> [f32; 3] {
+    let separable =
+        |f: fn(f32, f32) -> f32| [0, 1, 2].map(|i| f(backdrop[i], source[i]).clamp(0.0, 1.0));
+    match mode {
+        BlendMode::Normal | BlendMode::HardMix => source,
+        BlendMode::Multiply => separable(|b, s| b * s),
+        BlendMode::Screen => separable(screen),
+        BlendMode::Overlay => separable(|b, s| hard_light(s, b)),
+        BlendMode::Darken => separable(f32::min),
+        BlendMode::Lighten => separable(f32::max),
+        BlendMode::ColorDodge => separable(|b, s| {
+            if b == 0.0 {
+                0.0
+            } else if s == 1.0 {
+                1.0
+            } else {
+                (b / (1.0 - s)).min(1.0)
+            }
+        }),
+        BlendMode::ColorBurn => separable(|b, s| {
+            if b == 1.0 {
+                1.0
+            } else if s == 0.0 {
+                0.0
+            } else {
+                1.0 - ((1.0 - b) / s).min(1.0)
+            }
+        }),
+        BlendMode::HighLight => separable(hard_light),
+        BlendMode::SoftLight => separable(|b, s| {
+            if s <= 0.5 {
+                b - (1.0 - 2.0 * s) * b * (1.0 - b)
+            } else {
+                let d = if b <= 0.25 {
+                    ((16.0 * b - 12.0) * b + 4.0) * b
+                } else {
+                    b.sqrt()
+                };
+                b + (2.0 * s - 1.0) * (d - b)
+            }
+        }),
+        BlendMode::Difference => separable(|b, s| (b - s).abs()),
+        BlendMode::Exclusion => separable(|b, s| b + s - 2.0 * b * s),
+        BlendMode::Add => separable(|b, s| b + s),
+        BlendMode::Hue => set_lum(set_sat(source, sat(backdrop)), lum(backdrop)),
+        BlendMode::Saturation => set_lum(set_sat(backdrop, sat(source)), lum(backdrop)),
+        BlendMode::Color => set_lum(source, lum(backdrop)),
+        BlendMode::Luminosity => set_lum(backdrop, lum(source)),
+    }
+}
+
+fn screen(b: f32, s: f32) -> f32 {
+    b + s - b * s
+}
+
+fn hard_light(b: f32, s: f32) -> f32 {
+    if s <= 0.5 {
+        b * 2.0 * s
+    } else {
+        screen(b, 2.0 * s - 1.0)
+    }
+}
+
+fn lum(c: [f32; 3]) -> f32 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(c);
+    let c = c.map(|x| x + d);
+    let l = lum(c);
+    let (n, x) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    c.map(|v| {
+        if n < 0.0 {
+            l + (v - l) * l / (l - n)
+        } else if x > 1.0 {
+            l + (v - l) * (1.0 - l) / (x - l)
+        } else {
+            v
+        }
+    })
+}
+
+fn sat(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let (min, max) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    if max > min {
+        c.map(|v| (v - min) * s / (max - min))
+    } else {
+        [0.0; 3]
+    }
+}
@@ -0,0 +1,90 @@
+use lottie::prelude::BlendMode;
+use tiny_skia as ts;
+use ts::{Pixmap, PixmapPaint};
+
+use crate::utils::skia_blend_mode;
+
+/// Composite the isolated `layer` over `pixmap` with its blend mode
+pub fn draw_blended(pixmap: &mut Pixmap, layer: &Pixmap, mode: BlendMode) {
+    // tiny-skia doesn't clip the colors of non-separable modes when a
+    // component goes below zero, these are composited here instead
+    let blend: fn([f32; 3], [f32; 3]) -> [f32; 3] = match mode {
+        BlendMode::Hue => |b, s| set_lum(set_sat(s, sat(b)), lum(b)),
+        BlendMode::Saturation => |b, s| set_lum(set_sat(b, sat(s)), lum(b)),
+        BlendMode::Color => |b, s| set_lum(s, lum(b)),
+        BlendMode::Luminosity => |b, s| set_lum(b, lum(s)),
+        _ => {
+            let paint = PixmapPaint {
+                blend_mode: skia_blend_mode(mode),
+                ..Default::default()
+            };
+            pixmap.draw_pixmap(
+                0,
+                0,
+                layer.as_ref(),
+                &paint,
+                ts::Transform::identity(),
+                None,
+            );
+            return;
+        }
+    };
+    let rgb = |c: ts::ColorU8| [c.red(), c.green(), c.blue()].map(|v| v as f32 / 255.0);
+    for (pixel, source) in pixmap.pixels_mut().iter_mut().zip(layer.pixels()) {
+        if source.alpha() == 0 {
+            continue;
+        }
+        let (backdrop, source) = (pixel.demultiply(), source.demultiply());
+        let (alpha_b, alpha_s) = (
+            backdrop.alpha() as f32 / 255.0,
+            source.alpha() as f32 / 255.0,
+        );
+        let (cb, cs) = (rgb(backdrop), rgb(source));
+        let mixed = blend(cb, cs);
+        let alpha = alpha_s + alpha_b - alpha_s * alpha_b;
+        // Both colors show where the other one is transparent
+        let [r, g, b] = [0, 1, 2].map(|i| {
+            let c = (1.0 - alpha_s) * alpha_b * cb[i]
+                + (1.0 - alpha_b) * alpha_s * cs[i]
+                + alpha_s * alpha_b * mixed[i];
+            ((c / alpha).clamp(0.0, 1.0) * 255.0).round() as u8
+        });
+        let alpha = (alpha * 255.0).round() as u8;
+        *pixel = ts::ColorU8::from_rgba(r, g, b, alpha).premultiply();
+    }
+}
+
+fn lum(c: [f32; 3]) -> f32 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+
+/// `c` with its luminosity set to `l`, brought back within [0, 1] while
+/// keeping its luminosity
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(c);
+    let c = c.map(|v| v + d);
+    let l = lum(c);
+    let (min, max) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    c.map(|v| {
+        if min < 0.0 {
+            l + (v - l) * l / (l - min)
+        } else if max > 1.0 {
+            l + (v - l) * (1.0 - l) / (max - l)
+        } else {
+            v
+        }
+    })
+}
+
+fn sat(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let (min, max) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    if max > min {
+        c.map(|v| (v - min) * s / (max - min))
+    } else {
+        [0.0; 3]
+    }
+}
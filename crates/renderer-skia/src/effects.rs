@@ -0,0 +1,333 @@
+use lottie::glam::{Mat4, Vec3};
+use lottie::prelude::{BlurDimensions, Rgb, SceneEffect, SceneLayerStyle, StrokeEffectStyle};
+use tiny_skia as ts;
+use ts::{Mask, MaskType, Paint, Pixmap, PixmapPaint};
+
+use crate::utils::*;
+
+/// Apply `effects` of a layer to its rendered `pixmap`. `matrix` maps layer
+/// space to pixels
+pub fn apply_effects(pixmap: &mut Pixmap, effects: &[SceneEffect], matrix: Mat4) {
+    let scale = length_scale(matrix);
+    for effect in effects {
+        match effect {
+            SceneEffect::Fill { color, opacity } => {
+                map_colors(pixmap, |_, alpha| (color_f32(*color), alpha * opacity))
+            }
+            SceneEffect::Tint {
+                black,
+                white,
+                amount,
+            } => map_colors(pixmap, |rgb, alpha| {
+                let mapped = mix(color_f32(*black), color_f32(*white), luminance(rgb));
+                (mix(rgb, mapped, *amount), alpha)
+            }),
+            SceneEffect::Tritone {
+                highlights,
+                midtones,
+                shadows,
+                blend,
+            } => map_colors(pixmap, |rgb, alpha| {
+                let lum = luminance(rgb);
+                let mapped = if lum < 0.5 {
+                    mix(color_f32(*shadows), color_f32(*midtones), lum * 2.0)
+                } else {
+                    mix(
+                        color_f32(*midtones),
+                        color_f32(*highlights),
+                        lum * 2.0 - 1.0,
+                    )
+                };
+                (mix(mapped, rgb, *blend), alpha)
+            }),
+            SceneEffect::DropShadow {
+                color,
+                opacity,
+                offset,
+                sigma,
+                shadow_only,
+            } => {
+                let offset = matrix.transform_vector3(Vec3::new(offset.x, offset.y, 0.0));
+                let mut result = shadow(pixmap, *color, *opacity, offset, sigma * scale);
+                if !shadow_only {
+                    draw_over(&mut result, pixmap);
+                }
+                *pixmap = result;
+            }
+            SceneEffect::GaussianBlur { sigma, dimensions } => {
+                let sigma = sigma * scale;
+                match dimensions {
+                    BlurDimensions::Both => blur(pixmap, sigma, sigma),
+                    BlurDimensions::Horizontal => blur(pixmap, sigma, 0.0),
+                    BlurDimensions::Vertical => blur(pixmap, 0.0, sigma),
+                }
+            }
+            SceneEffect::Stroke {
+                paths,
+                color,
+                opacity,
+                width,
+                style,
+            } => {
+                let mut stroke = Pixmap::new(pixmap.width(), pixmap.height()).unwrap();
+                let paint = Paint {
+                    shader: ts::Shader::SolidColor(ts::Color::from_rgba8(
+                        color.r,
+                        color.g,
+                        color.b,
+                        (opacity * 255.0).round() as u8,
+                    )),
+                    anti_alias: true,
+                    ..Default::default()
+                };
+                // Strokes are painted with a round brush
+                let options = ts::Stroke {
+                    width: *width,
+                    line_cap: ts::LineCap::Round,
+                    line_join: ts::LineJoin::Round,
+                    ..Default::default()
+                };
+                for path in paths.iter().filter_map(skia_path) {
+                    stroke.stroke_path(&path, &paint, &options, skia_transform(matrix), None);
+                }
+                match style {
+                    StrokeEffectStyle::OnOriginal => draw_over(pixmap, &stroke),
+                    StrokeEffectStyle::OnTransparent => *pixmap = stroke,
+                    StrokeEffectStyle::RevealOriginal => {
+                        pixmap.apply_mask(&Mask::from_pixmap(stroke.as_ref(), MaskType::Alpha))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Apply layer `styles` to a rendered `pixmap`, once its effects are.
+/// `matrix` maps layer space to pixels
+pub fn apply_styles(pixmap: &mut Pixmap, styles: &[SceneLayerStyle], matrix: Mat4) {
+    let scale = length_scale(matrix);
+    for style in styles {
+        match style {
+            SceneLayerStyle::ColorOverlay { color, opacity } => map_colors(pixmap, |rgb, alpha| {
+                (mix(rgb, color_f32(*color), *opacity), alpha)
+            }),
+            SceneLayerStyle::Stroke { color, width } => {
+                let mut result = pixmap.clone();
+                dilate(&mut result, width * scale);
+                map_colors(&mut result, |_, alpha| (color_f32(*color), alpha));
+                draw_over(&mut result, pixmap);
+                *pixmap = result;
+            }
+            SceneLayerStyle::DropShadow {
+                color,
+                opacity,
+                offset,
+                sigma,
+                spread,
+            } => {
+                let offset = matrix.transform_vector3(Vec3::new(offset.x, offset.y, 0.0));
+                let mut result = shadow(pixmap, *color, 1.0, offset, sigma * scale);
+                map_colors(&mut result, |rgb, alpha| {
+                    let alpha = if *spread < 1.0 {
+                        alpha / (1.0 - spread)
+                    } else {
+                        alpha.ceil()
+                    };
+                    (rgb, alpha.min(1.0) * opacity)
+                });
+                draw_over(&mut result, pixmap);
+                *pixmap = result;
+            }
+        }
+    }
+}
+
+/// Ratio of lengths in pixels to lengths in layer space
+pub(crate) fn length_scale(matrix: Mat4) -> f32 {
+    (matrix.x_axis.x * matrix.y_axis.y - matrix.x_axis.y * matrix.y_axis.x)
+        .abs()
+        .sqrt()
+}
+
+fn draw_over(pixmap: &mut Pixmap, top: &Pixmap) {
+    pixmap.draw_pixmap(
+        0,
+        0,
+        top.as_ref(),
+        &PixmapPaint::default(),
+        ts::Transform::identity(),
+        None,
+    );
+}
+
+/// Alpha of `pixmap` painted with `color`, moved by `offset` and blurred,
+/// all in pixels
+fn shadow(pixmap: &Pixmap, color: Rgb, opacity: f32, offset: Vec3, sigma: f32) -> Pixmap {
+    let mut shadow = pixmap.clone();
+    map_colors(&mut shadow, |_, alpha| (color_f32(color), alpha * opacity));
+    let mut result = Pixmap::new(pixmap.width(), pixmap.height()).unwrap();
+    result.draw_pixmap(
+        0,
+        0,
+        shadow.as_ref(),
+        &PixmapPaint::default(),
+        ts::Transform::from_translate(offset.x, offset.y),
+        None,
+    );
+    blur(&mut result, sigma, sigma);
+    result
+}
+
+/// Grow opaque areas of `pixmap` by `radius` pixels, keeping colors of the
+/// most opaque pixel nearby
+fn dilate(pixmap: &mut Pixmap, radius: f32) {
+    if radius <= 0.0 {
+        return;
+    }
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let source = pixmap.pixels().to_vec();
+    let reach = radius.ceil() as i32 + 1;
+    let pixels = pixmap.pixels_mut();
+    for y in 0..height {
+        for x in 0..width {
+            let color = source[(y * width + x) as usize];
+            if color.alpha() == 0 {
+                continue;
+            }
+            // Opaque pixels inside opaque areas are covered by the edges
+            let opaque = |x: i32, y: i32| {
+                x >= 0
+                    && y >= 0
+                    && x < width
+                    && y < height
+                    && source[(y * width + x) as usize].alpha() == 255
+            };
+            if opaque(x, y)
+                && opaque(x - 1, y)
+                && opaque(x + 1, y)
+                && opaque(x, y - 1)
+                && opaque(x, y + 1)
+            {
+                continue;
+            }
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let (tx, ty) = (x + dx, y + dy);
+                    if tx < 0 || ty < 0 || tx >= width || ty >= height {
+                        continue;
+                    }
+                    // Anti-aliased edge of the disc
+                    let coverage =
+                        (radius + 0.5 - ((dx * dx + dy * dy) as f32).sqrt()).clamp(0.0, 1.0);
+                    let alpha = (color.alpha() as f32 * coverage).round() as u8;
+                    let target = &mut pixels[(ty * width + tx) as usize];
+                    if alpha > target.alpha() {
+                        let demultiplied = color.demultiply();
+                        *target = ts::ColorU8::from_rgba(
+                            demultiplied.red(),
+                            demultiplied.green(),
+                            demultiplied.blue(),
+                            alpha,
+                        )
+                        .premultiply();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replace every pixel by `f(rgb, alpha)`, with unpremultiplied colors
+fn map_colors(pixmap: &mut Pixmap, f: impl Fn([f32; 3], f32) -> ([f32; 3], f32)) {
+    for pixel in pixmap.pixels_mut() {
+        if pixel.alpha() == 0 {
+            continue;
+        }
+        let color = pixel.demultiply();
+        let rgb = [color.red(), color.green(), color.blue()].map(|c| c as f32 / 255.0);
+        let (rgb, alpha) = f(rgb, color.alpha() as f32 / 255.0);
+        let [r, g, b] = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        let alpha = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+        *pixel = ts::ColorU8::from_rgba(r, g, b, alpha).premultiply();
+    }
+}
+
+fn color_f32(color: Rgb) -> [f32; 3] {
+    [color.r, color.g, color.b].map(|c| c as f32 / 255.0)
+}
+
+fn luminance(rgb: [f32; 3]) -> f32 {
+    rgb[0] * 0.2126 + rgb[1] * 0.7152 + rgb[2] * 0.0722
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+/// Approximate a gaussian blur with standard deviations `sigma_x` and
+/// `sigma_y` in pixels, by three successive box blurs
+pub(crate) fn blur(pixmap: &mut Pixmap, sigma_x: f32, sigma_y: f32) {
+    let (width, height) = (pixmap.width() as usize, pixmap.height() as usize);
+    let data = pixmap.data_mut();
+    let mut buffer = vec![0; data.len()];
+    for extents in box_extents(sigma_x) {
+        box_blur(data, &mut buffer, width, height, 4, width * 4, extents);
+    }
+    for extents in box_extents(sigma_y) {
+        box_blur(data, &mut buffer, height, width, width * 4, 4, extents);
+    }
+}
+
+/// Pixels before and after the center of three box blurs approximating a
+/// gaussian blur, as in the SVG specification of feGaussianBlur
+fn box_extents(sigma: f32) -> [(usize, usize); 3] {
+    let size = (sigma * 3.0 * (2.0 * std::f32::consts::PI).sqrt() / 4.0 + 0.5).floor() as usize;
+    let half = size / 2;
+    if size % 2 == 1 {
+        [(half, half); 3]
+    } else if size > 0 {
+        // Boxes of an even size are off center by half a pixel on each
+        // side in turn, then a centered one is a pixel larger
+        [(half, half - 1), (half - 1, half), (half, half)]
+    } else {
+        [(0, 0); 3]
+    }
+}
+
+/// Blur premultiplied RGBA `data` along lines of `len` pixels, `step` bytes
+/// apart, with lines `stride` bytes apart. Each pixel averages `before`
+/// pixels and `after` pixels around it, pixels outside are transparent
+fn box_blur(
+    data: &mut [u8],
+    buffer: &mut [u8],
+    len: usize,
+    lines: usize,
+    step: usize,
+    stride: usize,
+    (before, after): (usize, usize),
+) {
+    if before == 0 && after == 0 {
+        return;
+    }
+    let size = (before + after + 1) as u32;
+    for line in 0..lines {
+        let start = line * stride;
+        for channel in 0..4 {
+            let at = |i: usize| start + i * step + channel;
+            let mut sum = (0..after.min(len)).map(|i| data[at(i)] as u32).sum::<u32>();
+            for i in 0..len {
+                if i + after < len {
+                    sum += data[at(i + after)] as u32;
+                }
+                if i > before {
+                    sum -= data[at(i - before - 1)] as u32;
+                }
+                buffer[at(i)] = ((sum + size / 2) / size) as u8;
+            }
+        }
+        for i in 0..len {
+            let at = start + i * step;
+            data[at..at + 4].copy_from_slice(&buffer[at..at + 4]);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use lottie::glam::Vec2;
+use lottie::prelude::{Model, Timeline};
+use lottie::*;
+use tiny_skia::Pixmap;
+
+mod blend;
+mod effects;
+mod render;
+mod utils;
+
+use render::FrameRasterizer;
+
+struct LottieData {
+    model: Model,
+    timeline: Timeline,
+    scale: f32,
+}
+
+/// A software renderer rasterizing lottie frames with `tiny-skia`, no GPU is
+/// needed
+pub struct SkiaRenderer {
+    lottie: Option<LottieData>,
+    frame_sender: UnboundedSender<FrameData>,
+}
+
+impl SkiaRenderer {
+    pub fn new() -> (Self, UnboundedReceiver<FrameData>) {
+        let (sender, receiver) = unbounded();
+        (
+            Self {
+                lottie: None,
+                frame_sender: sender,
+            },
+            receiver,
+        )
+    }
+}
+
+/// Rasterizes single frames on demand with `tiny-skia`, see
+/// [Lottie::render_frame]
+#[derive(Default)]
+pub struct SkiaFrameRenderer;
+
+impl FrameRenderer for SkiaFrameRenderer {
+    fn render_frame(
+        &mut self,
+        timeline: &Timeline,
+        frame: f32,
+        width: u32,
+        height: u32,
+        scale: Vec2,
+    ) -> RgbaImage {
+        let mut pixmap = match Pixmap::new(width, height) {
+            Some(pixmap) => pixmap,
+            None => {
+                return RgbaImage {
+                    data: vec![],
+                    width: 0,
+                    height: 0,
+                }
+            }
+        };
+        FrameRasterizer::new(timeline, scale).render(&mut pixmap, frame);
+        RgbaImage {
+            data: utils::rgba_data(&pixmap),
+            width,
+            height,
+        }
+    }
+}
+
+impl<F: FontDB> Renderer<F> for SkiaRenderer {
+    fn load_lottie(&mut self, lottie: Lottie<F>, config: Config) {
+        if let Config::Window(_) = config {
+            log::warn!("SkiaRenderer has no window support, rendering headlessly");
+        }
+        self.lottie = Some(LottieData {
+            timeline: lottie.timeline().clone(),
+            scale: lottie.scale,
+            model: lottie.model,
+        });
+    }
+
+    fn render(&mut self) {
+        let data = match self.lottie.take() {
+            Some(data) => data,
+            None => return,
+        };
+        let rasterizer = FrameRasterizer::new(&data.timeline, Vec2::splat(data.scale));
+        let width = (data.model.width as f32 * data.scale).round() as u32;
+        let height = (data.model.height as f32 * data.scale).round() as u32;
+        let mut frame = data.model.start_frame;
+        while frame < data.model.end_frame {
+            let mut pixmap = match Pixmap::new(width, height) {
+                Some(pixmap) => pixmap,
+                None => break,
+            };
+            rasterizer.render(&mut pixmap, frame);
+            let timestamp = (frame - data.model.start_frame) / data.model.frame_rate * 1000.0;
+            let frame_data = FrameData {
+                data: utils::rgba_data(&pixmap),
+                width,
+                height,
+                timestamp: timestamp as i32,
+            };
+            if self.frame_sender.unbounded_send(frame_data).is_err() {
+                break;
+            }
+            frame += 1.0;
+        }
+        self.frame_sender.close_channel();
+    }
+}
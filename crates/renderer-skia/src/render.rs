@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use lottie::glam::{Mat4, Vec2};
+use lottie::prelude::*;
+use tiny_skia as ts;
+use ts::{Mask, MaskType, Paint, Pixmap, PixmapMut, PixmapPaint, Shader};
+
+use crate::blend::draw_blended;
+use crate::effects::{apply_effects, apply_styles, blur, length_scale};
+use crate::utils::*;
+
+/// Rasterizes [FrameScene]s of a [Timeline] onto a [Pixmap]
+pub(crate) struct FrameRasterizer<'a> {
+    timeline: &'a Timeline,
+    scale: Vec2,
+    images: HashMap<Id, Pixmap>,
+}
+
+impl<'a> FrameRasterizer<'a> {
+    pub fn new(timeline: &'a Timeline, scale: Vec2) -> Self {
+        let mut images = HashMap::new();
+        for layer in timeline.items() {
+            if let RenderableContent::Media(media) = &layer.content {
+                if let Some(image) = decode_image(&media.content) {
+                    images.insert(layer.id, image);
+                }
+            }
+        }
+        FrameRasterizer {
+            timeline,
+            scale,
+            images,
+        }
+    }
+
+    pub fn render(&self, pixmap: &mut Pixmap, frame: f32) {
+        let scene = self.timeline.evaluate(frame);
+        for layer in scene.layers.iter().filter(|layer| !layer.is_mask) {
+            // Blend modes, effects and styles apply to the layer as a whole,
+            // so it is rendered on its own first
+            if layer.mattes.is_empty()
+                && layer.effects.is_empty()
+                && layer.styles.is_empty()
+                && layer.blend_mode == BlendMode::Normal
+            {
+                self.paint_layer(&mut pixmap.as_mut(), layer);
+            } else if let Some(isolated) =
+                self.isolated_layer(&scene, layer, pixmap.width(), pixmap.height())
+            {
+                draw_blended(pixmap, &isolated, layer.blend_mode);
+            }
+        }
+    }
+
+    /// Render a layer into its own pixmap, with all its masks, effects and
+    /// mattes applied
+    fn isolated_layer(
+        &self,
+        scene: &FrameScene,
+        layer: &SceneLayer,
+        width: u32,
+        height: u32,
+    ) -> Option<Pixmap> {
+        let mut pixmap = Pixmap::new(width, height)?;
+        self.paint_layer(&mut pixmap.as_mut(), layer);
+        // Coverage of the layer masks combined so far
+        let mut coverage: Option<Mask> = None;
+        // Effects and styles apply once the layer is masked, before track
+        // mattes
+        let mut effects_applied = false;
+        for matte in &layer.mattes {
+            if let Some(layer_mask) = matte.mask {
+                if layer_mask.index == 0 {
+                    if let Some(coverage) = coverage.take() {
+                        pixmap.apply_mask(&coverage);
+                    }
+                }
+                let coverage = coverage.get_or_insert_with(|| {
+                    let mut mask = Mask::new(width, height).unwrap();
+                    let initial = layer_mask.mode.initial_coverage();
+                    mask.data_mut().fill((initial * 255.0).round() as u8);
+                    mask
+                });
+                let source = match self.matte_source(scene, layer, matte, width, height) {
+                    Some(source) => source,
+                    None => continue,
+                };
+                let source = Mask::from_pixmap(source.as_ref(), MaskType::Alpha);
+                for (c, m) in coverage.data_mut().iter_mut().zip(source.data()) {
+                    let value = layer_mask.combine(*c as f32 / 255.0, *m as f32 / 255.0);
+                    *c = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+                continue;
+            }
+            if let Some(coverage) = coverage.take() {
+                pixmap.apply_mask(&coverage);
+            }
+            if !effects_applied {
+                self.apply_effects(&mut pixmap, layer);
+                effects_applied = true;
+            }
+            let (mask_type, inverted) = match matte.mode {
+                MatteMode::Normal => continue,
+                MatteMode::Alpha => (MaskType::Alpha, false),
+                MatteMode::InvertedAlpha => (MaskType::Alpha, true),
+                MatteMode::Luma => (MaskType::Luminance, false),
+                MatteMode::InvertedLuma => (MaskType::Luminance, true),
+            };
+            let source = match self.matte_source(scene, layer, matte, width, height) {
+                Some(source) => source,
+                None => continue,
+            };
+            let mut mask = Mask::from_pixmap(source.as_ref(), mask_type);
+            if inverted {
+                mask.invert();
+            }
+            pixmap.apply_mask(&mask);
+        }
+        if let Some(coverage) = coverage {
+            pixmap.apply_mask(&coverage);
+        }
+        if !effects_applied {
+            self.apply_effects(&mut pixmap, layer);
+        }
+        Some(pixmap)
+    }
+
+    fn apply_effects(&self, pixmap: &mut Pixmap, layer: &SceneLayer) {
+        let matrix = Mat4::from_scale(self.scale.extend(1.0)) * layer.transform;
+        if !layer.effects.is_empty() {
+            apply_effects(pixmap, &layer.effects, matrix);
+        }
+        if !layer.styles.is_empty() {
+            apply_styles(pixmap, &layer.styles, matrix);
+        }
+    }
+
+    /// Rendered source of a mask or matte, [None] if the layer is its own
+    /// source. An invisible source leaves an empty pixmap
+    fn matte_source(
+        &self,
+        scene: &FrameScene,
+        layer: &SceneLayer,
+        matte: &StagedLayerMask,
+        width: u32,
+        height: u32,
+    ) -> Option<Pixmap> {
+        match scene.layer(matte.id) {
+            Some(l) if l.id != layer.id => self.isolated_layer(scene, l, width, height),
+            Some(_) => None,
+            None => Pixmap::new(width, height),
+        }
+    }
+
+    fn paint_layer(&self, canvas: &mut PixmapMut, layer: &SceneLayer) {
+        let matrix = Mat4::from_scale(self.scale.extend(1.0)) * layer.transform;
+        match &layer.content {
+            SceneContent::Shapes(shapes) => {
+                for shape in shapes {
+                    paint_shape(canvas, shape, matrix, layer.opacity);
+                }
+            }
+            SceneContent::Media { width, height } => {
+                if let Some(image) = self.images.get(&layer.id) {
+                    let sx = if *width > 0 {
+                        *width as f32 / image.width() as f32
+                    } else {
+                        1.0
+                    };
+                    let sy = if *height > 0 {
+                        *height as f32 / image.height() as f32
+                    } else {
+                        1.0
+                    };
+                    let paint = PixmapPaint {
+                        opacity: layer.opacity,
+                        quality: ts::FilterQuality::Bilinear,
+                        ..Default::default()
+                    };
+                    canvas.draw_pixmap(
+                        0,
+                        0,
+                        image.as_ref(),
+                        &paint,
+                        skia_transform(matrix).pre_scale(sx, sy),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn paint_shape(canvas: &mut PixmapMut, shape: &SceneShape, matrix: Mat4, opacity: f32) {
+    if shape.blur > 0.0 {
+        // Blurred shapes are painted alone, so the blur doesn't spread what
+        // is below them
+        if let Some(mut pixmap) = Pixmap::new(canvas.width(), canvas.height()) {
+            let sharp = SceneShape {
+                blur: 0.0,
+                ..shape.clone()
+            };
+            paint_shape(&mut pixmap.as_mut(), &sharp, matrix, opacity);
+            let sigma = shape.blur * length_scale(matrix * shape.transform);
+            blur(&mut pixmap, sigma, sigma);
+            canvas.draw_pixmap(
+                0,
+                0,
+                pixmap.as_ref(),
+                &PixmapPaint::default(),
+                ts::Transform::identity(),
+                None,
+            );
+        }
+        return;
+    }
+    let path = match skia_path(&shape.path) {
+        Some(path) => path,
+        None => return,
+    };
+    let transform = skia_transform(matrix * shape.transform);
+
+    if let Some(fill) = shape.fill.as_ref() {
+        if let Some(shader) = skia_shader(&fill.paint, opacity) {
+            let paint = Paint {
+                shader,
+                anti_alias: true,
+                ..Default::default()
+            };
+            canvas.fill_path(
+                &path,
+                &paint,
+                skia_fill_rule(fill.fill_rule),
+                transform,
+                None,
+            );
+        }
+    }
+
+    if let Some(stroke) = shape.stroke.as_ref() {
+        let options = skia_stroke(stroke);
+        if let (Some(shader), true) = (skia_shader(&stroke.paint, opacity), options.width > 0.0) {
+            let paint = Paint {
+                shader,
+                anti_alias: true,
+                ..Default::default()
+            };
+            canvas.stroke_path(&path, &paint, &options, transform, None);
+        }
+    }
+}
+
+fn skia_shader(paint: &ScenePaint, opacity: f32) -> Option<Shader<'static>> {
+    let gradient = match paint {
+        ScenePaint::Solid(color) => return Some(Shader::SolidColor(skia_color(*color, opacity))),
+        ScenePaint::Gradient(gradient) => gradient,
+    };
+    let stops = gradient
+        .stops
+        .iter()
+        .map(|stop| ts::GradientStop::new(stop.offset, skia_color(stop.color, 1.0)))
+        .collect::<Vec<_>>();
+    let start = ts::Point::from_xy(gradient.start.x, gradient.start.y);
+    let mut shader = match gradient.gradient_ty {
+        GradientType::Linear => ts::LinearGradient::new(
+            start,
+            ts::Point::from_xy(gradient.end.x, gradient.end.y),
+            stops,
+            ts::SpreadMode::Pad,
+            ts::Transform::identity(),
+        ),
+        GradientType::Radial => ts::RadialGradient::new(
+            ts::Point::from_xy(gradient.focal.x, gradient.focal.y),
+            start,
+            (gradient.end - gradient.start).length(),
+            stops,
+            ts::SpreadMode::Pad,
+            ts::Transform::identity(),
+        ),
+    }?;
+    shader.apply_opacity(opacity.clamp(0.0, 1.0));
+    Some(shader)
+}
+
+fn decode_image(content: &[u8]) -> Option<Pixmap> {
+    let image = match image::load_from_memory(content) {
+        Ok(image) => image.to_rgba8(),
+        Err(e) => {
+            log::debug!("media is not an image: {:?}", e);
+            return None;
+        }
+    };
+    let (width, height) = image.dimensions();
+    let mut data = image.into_raw();
+    for pixel in data.chunks_exact_mut(4) {
+        let color = ts::ColorU8::from_rgba(pixel[0], pixel[1], pixel[2], pixel[3]).premultiply();
+        pixel.copy_from_slice(&[color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+    Pixmap::from_vec(data, ts::IntSize::from_wh(width, height)?)
+}
@@ -0,0 +1,121 @@
+use lottie::glam::Mat4;
+use lottie::prelude::{
+    BlendMode as LottieBlendMode, FillRule as LottieFillRule, LineCap as LottieLineCap,
+    LineJoin as LottieLineJoin, Rgba, SceneStroke,
+};
+use lyon::path::{Event, Path as LyonPath};
+use tiny_skia as ts;
+
+/// Convert a lyon [Path](LyonPath) to a `tiny-skia` path, returns `None` if the
+/// path is empty
+pub fn skia_path(path: &LyonPath) -> Option<ts::Path> {
+    let mut builder = ts::PathBuilder::new();
+    for seg in path.iter() {
+        match seg {
+            Event::Begin { at } => builder.move_to(at.x, at.y),
+            Event::Line { to, .. } => builder.line_to(to.x, to.y),
+            Event::Quadratic { ctrl, to, .. } => builder.quad_to(ctrl.x, ctrl.y, to.x, to.y),
+            Event::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => builder.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y),
+            Event::End { close, .. } => {
+                if close {
+                    builder.close()
+                }
+            }
+        }
+    }
+    builder.finish()
+}
+
+/// Convert a 3D matrix produced by lottie transforms to a 2D affine transform
+pub fn skia_transform(matrix: Mat4) -> ts::Transform {
+    ts::Transform::from_row(
+        matrix.x_axis.x,
+        matrix.x_axis.y,
+        matrix.y_axis.x,
+        matrix.y_axis.y,
+        matrix.w_axis.x,
+        matrix.w_axis.y,
+    )
+}
+
+/// Unpremultiplied RGBA8 data of a [Pixmap](ts::Pixmap)
+pub fn rgba_data(pixmap: &ts::Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+pub fn skia_color(color: Rgba, opacity: f32) -> ts::Color {
+    ts::Color::from_rgba8(
+        color.r,
+        color.g,
+        color.b,
+        (color.a as f32 * opacity.clamp(0.0, 1.0)).round() as u8,
+    )
+}
+
+pub fn skia_blend_mode(mode: LottieBlendMode) -> ts::BlendMode {
+    match mode {
+        LottieBlendMode::Normal => ts::BlendMode::SourceOver,
+        LottieBlendMode::Multiply => ts::BlendMode::Multiply,
+        LottieBlendMode::Screen => ts::BlendMode::Screen,
+        LottieBlendMode::Overlay => ts::BlendMode::Overlay,
+        LottieBlendMode::Darken => ts::BlendMode::Darken,
+        LottieBlendMode::Lighten => ts::BlendMode::Lighten,
+        LottieBlendMode::ColorDodge => ts::BlendMode::ColorDodge,
+        LottieBlendMode::ColorBurn => ts::BlendMode::ColorBurn,
+        LottieBlendMode::HighLight => ts::BlendMode::HardLight,
+        LottieBlendMode::SoftLight => ts::BlendMode::SoftLight,
+        LottieBlendMode::Difference => ts::BlendMode::Difference,
+        LottieBlendMode::Exclusion => ts::BlendMode::Exclusion,
+        LottieBlendMode::Hue => ts::BlendMode::Hue,
+        LottieBlendMode::Saturation => ts::BlendMode::Saturation,
+        LottieBlendMode::Color => ts::BlendMode::Color,
+        LottieBlendMode::Luminosity => ts::BlendMode::Luminosity,
+        LottieBlendMode::Add => ts::BlendMode::Plus,
+        LottieBlendMode::HardMix => {
+            log::warn!("hard mix blend mode is not supported, ignored");
+            ts::BlendMode::SourceOver
+        }
+    }
+}
+
+pub fn skia_fill_rule(rule: LottieFillRule) -> ts::FillRule {
+    match rule {
+        LottieFillRule::NonZero => ts::FillRule::Winding,
+        LottieFillRule::EvenOdd => ts::FillRule::EvenOdd,
+    }
+}
+
+pub fn skia_stroke(stroke: &SceneStroke) -> ts::Stroke {
+    ts::Stroke {
+        width: stroke.width,
+        // lottie files may omit miter limit, which is parsed as 0
+        miter_limit: if stroke.miter_limit > 0.0 {
+            stroke.miter_limit
+        } else {
+            4.0
+        },
+        line_cap: match stroke.line_cap {
+            LottieLineCap::Butt => ts::LineCap::Butt,
+            LottieLineCap::Round => ts::LineCap::Round,
+            LottieLineCap::Square => ts::LineCap::Square,
+        },
+        line_join: match stroke.line_join {
+            LottieLineJoin::Miter => ts::LineJoin::Miter,
+            LottieLineJoin::Round => ts::LineJoin::Round,
+            LottieLineJoin::Bevel => ts::LineJoin::Bevel,
+        },
+        dash: stroke
+            .dash
+            .as_ref()
+            .and_then(|dash| ts::StrokeDash::new(dash.array.clone(), dash.offset)),
+    }
+}
@@ -0,0 +1,1212 @@
+use std::fs::File;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use lottie::prelude::BlendMode;
+use lottie::{
+    CharsDB, Config, Error, FileResolver, FontKitDB, HeadlessConfig, Lottie, MockFont, Renderer,
+};
+use lottie_renderer_skia::{SkiaFrameRenderer, SkiaRenderer};
+use rstest::rstest;
+use smol::stream::StreamExt;
+
+/// Checked frames are captured from the GPU renderer with MSAA, so shape edges
+/// differ slightly. A pixel counts as different if any channel is off by more
+/// than this value
+const CHANNEL_TOLERANCE: i32 = 32;
+/// Maximum ratio of differing pixels in a frame
+const PIXEL_TOLERANCE: f32 = 0.02;
+
+#[rstest]
+fn check_fixture(
+    #[files("../../fixtures/ui/checked/**/*.json")]
+    // Text without embedded `chars` needs installed fonts
+    #[exclude("Text_NoGlyph|verifyLineHeight")]
+    path: PathBuf,
+) -> Result<(), Error> {
+    let mut f = File::open(&path)?;
+    let lottie = Lottie::<CharsDB>::from_reader(&mut f, "../../")?;
+    let (mut renderer, frame_stream) = SkiaRenderer::new();
+    renderer.load_lottie(
+        lottie,
+        Config::Headless(HeadlessConfig {
+            target: lottie::Target::Default,
+            filename: String::from("test.webp"),
+            frame: None,
+        }),
+    );
+    <SkiaRenderer as Renderer<CharsDB>>::render(&mut renderer);
+    let filename = path.file_stem().unwrap().to_str().unwrap_or_default();
+    let mut checked_frames = glob::glob(
+        &path
+            .with_file_name(format!("{}_*.png", filename))
+            .to_string_lossy(),
+    )
+    .unwrap()
+    .filter_map(|entry| {
+        let path = entry.ok()?;
+        let index = path.file_stem()?.to_str()?.rsplit('_').next()?;
+        index.parse::<u32>().ok()
+    })
+    .collect::<Vec<_>>();
+    checked_frames.sort();
+    smol::block_on(async {
+        smol::pin!(frame_stream);
+        let mut i = 0;
+        while let Some(frame) = frame_stream.next().await {
+            if !checked_frames.contains(&i) {
+                i += 1;
+                continue;
+            }
+            let p = path.with_file_name(format!("{}_{}.png", filename, i));
+            assert_similar(&p, &frame.data);
+            i += 1;
+        }
+    });
+    Ok(())
+}
+
+#[test]
+fn render_single_frame() {
+    let path = Path::new("../../fixtures/ui/checked/LottieFiles/bounce_strokes.json");
+    let mut f = File::open(path).unwrap();
+    let lottie = Lottie::<MockFont>::from_reader(&mut f, "../../").unwrap();
+    let image = lottie.render_frame(
+        &mut SkiaFrameRenderer,
+        60.0,
+        lottie.model.width,
+        lottie.model.height,
+    );
+    assert_similar(&path.with_file_name("bounce_strokes_60.png"), &image.data);
+}
+
+#[test]
+fn render_empty_model() {
+    let path = Path::new("../../fixtures/ui/checked/LottieFiles/bounce_strokes.json");
+    let mut f = File::open(path).unwrap();
+    let mut lottie = Lottie::<MockFont>::from_reader(&mut f, "../../").unwrap();
+    lottie.model.width = 0;
+    let image = lottie.render_frame(&mut SkiaFrameRenderer, 0.0, 20, 10);
+    assert_eq!((image.width, image.height), (20, 10));
+    assert!(image.data.iter().all(|c| *c == 0));
+}
+
+/// Colors are quantized to 8 bits before they are composited, so computed
+/// channels may be off by a few steps
+const QUANTIZATION_TOLERANCE: i32 = 3;
+
+/// First frame of a checked fixture, for features checked against values
+/// computed from their definition rather than frames of a renderer
+struct FirstFrame {
+    name: String,
+    data: Vec<u8>,
+    width: usize,
+}
+
+impl FirstFrame {
+    fn new(path: &Path) -> Self {
+        let mut f = File::open(path).unwrap();
+        let lottie = Lottie::<MockFont>::from_reader(&mut f, "../../").unwrap();
+        let (width, height) = (lottie.model.width, lottie.model.height);
+        let image = lottie.render_frame(&mut SkiaFrameRenderer, 0.0, width, height);
+        FirstFrame {
+            name: path.file_stem().unwrap().to_string_lossy().to_string(),
+            data: image.data,
+            width: width as usize,
+        }
+    }
+
+    /// Unpremultiplied RGBA at `(x, y)`
+    fn pixel(&self, (x, y): (usize, usize)) -> &[u8] {
+        &self.data[(y * self.width + x) * 4..][..4]
+    }
+
+    fn assert_alpha(&self, at: (usize, usize), expected: f32) {
+        let alpha = self.pixel(at)[3];
+        let expected = (expected * 255.0).round() as i32;
+        assert!(
+            (alpha as i32 - expected).abs() <= QUANTIZATION_TOLERANCE,
+            "{}: alpha {} at {:?} should be {}",
+            self.name,
+            alpha,
+            at,
+            expected
+        );
+    }
+
+    fn assert_color(&self, at: (usize, usize), expected: [f32; 3], tolerance: i32) {
+        let pixel = &self.pixel(at)[..3];
+        let expected = expected.map(|c| (c * 255.0).round() as i32);
+        assert!(
+            pixel
+                .iter()
+                .zip(expected)
+                .all(|(c, e)| (*c as i32 - e).abs() <= tolerance),
+            "{}: {:?} at {:?} should be {:?}",
+            self.name,
+            pixel,
+            at,
+            expected
+        );
+    }
+}
+
+/// Source colors of the columns and backdrop colors of the rows of the blend
+/// mode fixtures, cells are 30 pixels wide
+const BLEND_SOURCES: [[f32; 3]; 3] = [[0.9, 0.2, 0.2], [0.2, 0.8, 0.3], [0.3, 0.3, 0.9]];
+const BLEND_BACKDROPS: [[f32; 3]; 3] = [[0.15, 0.15, 0.15], [0.5, 0.5, 0.5], [0.95, 0.85, 0.3]];
+
+/// Blended cells are checked against the formulas of the W3C compositing
+/// specification
+#[rstest]
+fn check_blend_mode(#[files("../../fixtures/ui/checked/BlendModes/*.json")] path: PathBuf) {
+    let model = lottie::prelude::Model::from_reader(&mut File::open(&path).unwrap()).unwrap();
+    let mode = model.layers[0].blend_mode.unwrap();
+    let frame = FirstFrame::new(&path);
+    for (row, backdrop) in BLEND_BACKDROPS.into_iter().enumerate() {
+        for (column, source) in BLEND_SOURCES.into_iter().enumerate() {
+            let at = (column * 30 + 15, row * 30 + 15);
+            frame.assert_color(at, blend(mode, backdrop, source), QUANTIZATION_TOLERANCE);
+        }
+    }
+}
+
+/// Luma mattes are checked against the luminance of the strips of the matte,
+/// gray ones 20 pixels wide then a transparent one
+#[rstest]
+#[case("LumaMatte", [1.0, 0.75, 0.5, 0.0, 0.0])]
+#[case("InvertedLumaMatte", [0.0, 0.25, 0.5, 1.0, 1.0])]
+fn check_luma_matte(#[case] name: &str, #[case] alphas: [f32; 5]) {
+    let path = format!("../../fixtures/ui/checked/Mattes/{}.json", name);
+    let frame = FirstFrame::new(Path::new(&path));
+    // Above and below the colored bar across the strips
+    for y in [20, 80] {
+        for (strip, alpha) in alphas.into_iter().enumerate() {
+            let at = (strip * 20 + 10, y);
+            frame.assert_alpha(at, alpha);
+            // The red target shows through
+            if alpha > 0.0 {
+                frame.assert_color(at, [0.9, 0.2, 0.2], QUANTIZATION_TOLERANCE);
+            }
+        }
+    }
+}
+
+/// Background of the effect fixtures
+const EFFECT_BACKGROUND: [f32; 3] = [0.95, 0.95, 0.85];
+/// Gray levels of the strips of the color effect fixtures, 20 pixels wide
+const EFFECT_STRIPS: [f32; 5] = [1.0, 0.75, 0.5, 0.25, 0.0];
+
+fn effect_frame(name: &str) -> FirstFrame {
+    let path = format!("../../fixtures/ui/checked/Effects/{}.json", name);
+    FirstFrame::new(Path::new(&path))
+}
+
+/// Colors of the gray strips mapped by `f`, above and below the colored bar
+/// across them
+fn assert_strips(frame: &FirstFrame, f: impl Fn(f32) -> [f32; 3]) {
+    for y in [10, 90] {
+        for (strip, gray) in EFFECT_STRIPS.into_iter().enumerate() {
+            frame.assert_color((strip * 20 + 10, y), f(gray), QUANTIZATION_TOLERANCE);
+        }
+    }
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+/// Ratio of a gaussian blur with deviation `sigma` covering `x`, when it
+/// blurs the range from `start` to `end`
+fn coverage(x: f32, start: f32, end: f32, sigma: f32) -> f32 {
+    let cdf = |edge: f32| erf((edge - x) / (sigma * std::f32::consts::SQRT_2));
+    (cdf(end) - cdf(start)) / 2.0
+}
+
+/// Abramowitz and Stegun approximation of the error function
+fn erf(x: f32) -> f32 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254_829_6
+            + t * (-0.284_496_72 + t * (1.421_413_8 + t * (-1.453_152 + t * 1.061_405_4))));
+    (1.0 - poly * (-x * x).exp()).copysign(x)
+}
+
+/// Box blurs approximate a gaussian one
+const BLUR_TOLERANCE: i32 = 6;
+
+#[test]
+fn check_fill_effect() {
+    let frame = effect_frame("Fill");
+    // The fill replaces the colors of the layer, at 75% opacity
+    let fill = mix(EFFECT_BACKGROUND, [0.1, 0.4, 0.9], 0.75);
+    frame.assert_color((50, 50), fill, QUANTIZATION_TOLERANCE);
+    frame.assert_color((5, 5), EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+}
+
+#[test]
+fn check_tint_effect() {
+    // Black and white are mapped to the tint colors, mixed at 80% with the
+    // original
+    assert_strips(&effect_frame("Tint"), |gray| {
+        let mapped = mix([0.2, 0.0, 0.4], [1.0, 0.9, 0.5], gray);
+        mix([gray; 3], mapped, 0.8)
+    });
+}
+
+#[test]
+fn check_tritone_effect() {
+    // Shadows, midtones and highlights are mapped to their colors, blended
+    // with 20% of the original
+    let (highlights, midtones, shadows) = ([1.0, 1.0, 0.8], [0.9, 0.3, 0.3], [0.1, 0.0, 0.3]);
+    assert_strips(&effect_frame("Tritone"), |gray| {
+        let mapped = if gray < 0.5 {
+            mix(shadows, midtones, gray * 2.0)
+        } else {
+            mix(midtones, highlights, gray * 2.0 - 1.0)
+        };
+        mix(mapped, [gray; 3], 0.2)
+    });
+}
+
+#[test]
+fn check_drop_shadow_effect() {
+    let frame = effect_frame("DropShadow");
+    // 10 pixels away at 135 degrees, clockwise from the top, with a deviation
+    // of a quarter of the softness and an opacity of 160 over 255
+    let offset = 10.0 * std::f32::consts::FRAC_1_SQRT_2;
+    let shadow = |(x, y): (usize, usize)| {
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+        let alpha = 160.0 / 255.0
+            * coverage(x, 20.0 + offset, 70.0 + offset, 3.0)
+            * coverage(y, 25.0 + offset, 65.0 + offset, 3.0);
+        mix(EFFECT_BACKGROUND, [0.0; 3], alpha)
+    };
+    frame.assert_color((45, 45), [0.2, 0.6, 0.9], QUANTIZATION_TOLERANCE);
+    for at in [(5, 5), (17, 45), (45, 22), (74, 45), (45, 68), (74, 68)] {
+        frame.assert_color(at, shadow(at), BLUR_TOLERANCE);
+    }
+}
+
+#[test]
+fn check_gaussian_blur_effect() {
+    let frame = effect_frame("GaussianBlur");
+    // The green square is above the bar, far from its top and bottom edges
+    // the blur is horizontal, with a deviation of 0.3 times the blurriness
+    for x in [50, 66, 70, 74, 78, 82, 86] {
+        let alpha = coverage(x as f32 + 0.5, 25.0, 75.0, 4.5);
+        frame.assert_color(
+            (x, 50),
+            mix(EFFECT_BACKGROUND, [0.2, 0.6, 0.2], alpha),
+            BLUR_TOLERANCE,
+        );
+    }
+}
+
+#[test]
+fn check_stroke_effect() {
+    let frame = effect_frame("Stroke");
+    let stroke = [0.9, 0.1, 0.5];
+    // The stroke follows the triangle of the mask from its first point, up to
+    // 75% of its length, over the original layer
+    for at in [(32, 50), (67, 50), (80, 84)] {
+        frame.assert_color(at, stroke, QUANTIZATION_TOLERANCE);
+    }
+    frame.assert_color((40, 84), EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+    frame.assert_color((50, 50), [0.3, 0.7, 0.4], QUANTIZATION_TOLERANCE);
+}
+
+/// Colors of the rectangle and of the ellipse of the layer style fixtures,
+/// the rectangle is above the ellipse
+const STYLE_RECTANGLE: [f32; 3] = [0.2, 0.6, 0.9];
+const STYLE_ELLIPSE: [f32; 3] = [0.9, 0.5, 0.1];
+
+fn style_frame(name: &str) -> FirstFrame {
+    let path = format!("../../fixtures/ui/checked/LayerStyles/{}.json", name);
+    FirstFrame::new(Path::new(&path))
+}
+
+#[test]
+fn check_color_overlay_style() {
+    let frame = style_frame("ColorOverlay");
+    // The overlay color covers the shapes at 70% of its opacity
+    let overlay = [0.1, 0.7, 0.3];
+    frame.assert_color(
+        (40, 45),
+        mix(STYLE_RECTANGLE, overlay, 0.7),
+        QUANTIZATION_TOLERANCE,
+    );
+    frame.assert_color(
+        (72, 67),
+        mix(STYLE_ELLIPSE, overlay, 0.7),
+        QUANTIZATION_TOLERANCE,
+    );
+    frame.assert_color((90, 10), EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+}
+
+#[test]
+fn check_stroke_style() {
+    let frame = style_frame("Stroke");
+    // The stroke grows the outline of the shapes by its size of 4 pixels,
+    // below them
+    let stroke = [0.6, 0.1, 0.5];
+    for at in [(18, 45), (40, 22), (65, 77), (81, 60)] {
+        frame.assert_color(at, stroke, QUANTIZATION_TOLERANCE);
+    }
+    for at in [(14, 45), (40, 18), (65, 81), (86, 60)] {
+        frame.assert_color(at, EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+    }
+    frame.assert_color((22, 45), STYLE_RECTANGLE, QUANTIZATION_TOLERANCE);
+    frame.assert_color((70, 70), STYLE_ELLIPSE, QUANTIZATION_TOLERANCE);
+}
+
+#[test]
+fn check_drop_shadow_style() {
+    let frame = style_frame("DropShadow");
+    // The light comes from 120 degrees, the shadow of the rectangle is cast
+    // 8 pixels away with a deviation of half the size of 8. A choke of 20%
+    // divides the blurred alpha by 0.8, and the opacity is 60%
+    let angle = 120f32.to_radians();
+    let (dx, dy) = (-angle.cos() * 8.0, angle.sin() * 8.0);
+    let shadow = |(x, y): (usize, usize)| {
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+        let alpha = coverage(x, 20.0 + dx, 60.0 + dx, 4.0) * coverage(y, 25.0 + dy, 65.0 + dy, 4.0);
+        mix(EFFECT_BACKGROUND, [0.0; 3], (alpha / 0.8).min(1.0) * 0.6)
+    };
+    // Probes are far enough from the ellipse for its shadow to vanish
+    for at in [(40, 68), (40, 70), (40, 74), (62, 38), (64, 40), (66, 40)] {
+        frame.assert_color(at, shadow(at), BLUR_TOLERANCE);
+    }
+    frame.assert_color((40, 45), STYLE_RECTANGLE, QUANTIZATION_TOLERANCE);
+    frame.assert_color((70, 70), STYLE_ELLIPSE, QUANTIZATION_TOLERANCE);
+    frame.assert_color((10, 10), EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+}
+
+/// Matrix of a transform as lottie-web builds it, for row vectors. Each
+/// product applies the right hand matrix after the left hand one
+type Matrix = [[f32; 4]; 4];
+
+fn product(a: Matrix, b: Matrix) -> Matrix {
+    let mut result = [[0.0; 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    result
+}
+
+fn transforms(matrices: &[Matrix]) -> Matrix {
+    matrices
+        .iter()
+        .fold(translate(0.0, 0.0, 0.0), |m, n| product(m, *n))
+}
+
+fn translate(x: f32, y: f32, z: f32) -> Matrix {
+    [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [x, y, z, 1.0],
+    ]
+}
+
+fn rotate_x(degrees: f32) -> Matrix {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, cos, -sin, 0.0],
+        [0.0, sin, cos, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+fn rotate_y(degrees: f32) -> Matrix {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    [
+        [cos, 0.0, sin, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [-sin, 0.0, cos, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+fn rotate_z(degrees: f32) -> Matrix {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    [
+        [cos, -sin, 0.0, 0.0],
+        [sin, cos, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+/// Skew by `skew` degrees along the axis at `axis` degrees, as in
+/// `Matrix.skewFromAxis` with the negated skew of a transform
+fn skew(skew: f32, axis: f32) -> Matrix {
+    let shear = [
+        [1.0, 0.0, 0.0, 0.0],
+        [(-skew).to_radians().tan(), 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+    transforms(&[rotate_z(-axis), shear, rotate_z(axis)])
+}
+
+/// Point of the plane z = 0 of a layer at the center of the pixel `(x, y)`,
+/// when `matrix` maps the layer to the composition without a camera
+fn unproject(matrix: Matrix, (x, y): (usize, usize)) -> (f32, f32) {
+    let [[a, b, ..], [c, d, ..], _, [e, f, ..]] = matrix;
+    let (x, y) = (x as f32 + 0.5 - e, y as f32 + 0.5 - f);
+    let det = a * d - b * c;
+    ((x * d - y * c) / det, (y * a - x * b) / det)
+}
+
+/// Check pixels on a grid against squares of `size` centered in the layer
+/// space of each matrix, the first one drawn on top. Pixels closer than a
+/// few pixels to an edge are skipped
+fn check_squares(frame: &FirstFrame, squares: &[(Matrix, f32, [f32; 3])]) {
+    const MARGIN: f32 = 3.0;
+    let mut checked = [0; 2];
+    'pixels: for at in (0..100)
+        .step_by(3)
+        .flat_map(|y| (0..100).step_by(3).map(move |x| (x, y)))
+    {
+        for (matrix, size, color) in squares {
+            let (x, y) = unproject(*matrix, at);
+            let distance = x.abs().max(y.abs()) - size / 2.0;
+            if distance < -MARGIN {
+                frame.assert_color(at, *color, QUANTIZATION_TOLERANCE);
+                checked[0] += 1;
+                continue 'pixels;
+            } else if distance < MARGIN {
+                continue 'pixels;
+            }
+        }
+        frame.assert_color(at, EFFECT_BACKGROUND, QUANTIZATION_TOLERANCE);
+        checked[1] += 1;
+    }
+    assert!(checked.iter().all(|count| *count > 20), "{:?}", checked);
+}
+
+#[test]
+fn check_skew_transform() {
+    let frame = FirstFrame::new(Path::new("../../fixtures/ui/checked/Transforms/Skew.json"));
+    let layer = transforms(&[skew(20.0, 90.0), translate(50.0, 50.0, 0.0)]);
+    let blue = transforms(&[skew(30.0, 0.0), translate(0.0, -20.0, 0.0), layer]);
+    let orange = transforms(&[skew(-30.0, 45.0), translate(0.0, 20.0, 0.0), layer]);
+    check_squares(
+        &frame,
+        &[
+            (blue, 30.0, [0.2, 0.6, 0.9]),
+            (orange, 30.0, [0.9, 0.5, 0.1]),
+        ],
+    );
+}
+
+#[test]
+fn check_rotation_3d_transform() {
+    let frame = FirstFrame::new(Path::new(
+        "../../fixtures/ui/checked/Transforms/Rotation3D.json",
+    ));
+    // Rotations apply around z, y then x, before the orientation, which
+    // lottie-web maps to the plane without perspective when there's no
+    // camera
+    let layer = transforms(&[
+        rotate_z(-30.0),
+        rotate_y(60.0),
+        rotate_x(0.0),
+        rotate_z(-0.0),
+        rotate_y(0.0),
+        rotate_x(20.0),
+        translate(50.0, 50.0, 0.0),
+    ]);
+    let orange = transforms(&[translate(15.0, 0.0, 0.0), layer]);
+    check_squares(
+        &frame,
+        &[
+            (orange, 20.0, [0.9, 0.5, 0.1]),
+            (layer, 50.0, [0.2, 0.6, 0.9]),
+        ],
+    );
+}
+
+/// Text fixtures fall back to the bundled Fira Mono, whose characters all
+/// advance by 0.6 em
+const FIRA_MONO_ADVANCE: f32 = 0.6;
+
+fn text_frame(name: &str) -> FirstFrame {
+    let path = format!("../../fixtures/ui/text/{}.json", name);
+    let model = lottie::prelude::Model::from_reader(&mut File::open(&path).unwrap()).unwrap();
+    // Only the bundled font is loaded, so frames don't depend on installed fonts
+    let lottie = Lottie::new(model, FontKitDB::default(), &FileResolver::new("../../")).unwrap();
+    let (width, height) = (lottie.model.width, lottie.model.height);
+    let image = lottie.render_frame(&mut SkiaFrameRenderer, 0.0, width, height);
+    FirstFrame {
+        name: name.to_string(),
+        data: image.data,
+        width: width as usize,
+    }
+}
+
+/// A line of text starting at `x`, with `tracking` pixels added after each
+/// character and `word_spacing` more after each space
+struct TextLine<'a> {
+    text: &'a str,
+    x: f32,
+    baseline: f32,
+    size: f32,
+    tracking: f32,
+    word_spacing: f32,
+}
+
+impl TextLine<'_> {
+    /// Start of the character at `index`
+    fn start(&self, index: usize) -> f32 {
+        let advance = self.size * FIRA_MONO_ADVANCE;
+        let spaces = self.text.chars().take(index).filter(|c| *c == ' ').count();
+        self.x + (advance + self.tracking) * index as f32 + self.word_spacing * spaces as f32
+    }
+}
+
+/// Check the cells of the characters of `line` between its baseline and
+/// the top of its capitals. Cells of visible characters hold pixels of the
+/// color of the character and none of the other colors of the `palette`,
+/// while spaces and pixels around the line are `background`
+fn check_text_line(
+    frame: &FirstFrame,
+    line: &TextLine,
+    background: [f32; 3],
+    palette: &[[f32; 3]],
+    color: impl Fn(usize) -> [f32; 3],
+) {
+    const TOLERANCE: i32 = 8;
+    let matches = |pixel: &[u8], color: [f32; 3]| {
+        pixel
+            .iter()
+            .zip(color)
+            .all(|(c, e)| (*c as i32 - (e * 255.0).round() as i32).abs() <= TOLERANCE)
+    };
+    let height = frame.data.len() / 4 / frame.width;
+    let rows = (line.baseline - line.size * 0.7).max(0.0) as usize..line.baseline as usize;
+    let columns =
+        |start: f32, end: f32| start.max(0.0) as usize..(end.max(0.0) as usize).min(frame.width);
+    let advance = line.size * FIRA_MONO_ADVANCE;
+    let end = line.start(line.text.chars().count()) - line.tracking;
+    assert!(rows.end <= height);
+    // Pixels next to the ends of a cell may be covered by the edges of a
+    // glyph. Cells cut by the edges of the frame are skipped
+    for (index, c) in line.text.chars().enumerate() {
+        let start = line.start(index);
+        if start < 0.0 || start + advance > frame.width as f32 {
+            continue;
+        }
+        let cell = columns(start + 2.0, start + advance - 2.0);
+        let pixels = || {
+            rows.clone()
+                .flat_map(|y| cell.clone().map(move |x| frame.pixel((x, y))))
+        };
+        let expected = if c == ' ' { background } else { color(index) };
+        if c == ' ' {
+            assert!(
+                pixels().all(|pixel| matches(pixel, background)),
+                "{}: space {} of {:?} is not empty",
+                frame.name,
+                index,
+                line.text
+            );
+        } else {
+            let count = pixels().filter(|pixel| matches(pixel, expected)).count();
+            assert!(
+                count as f32 > line.size * line.size * 0.02,
+                "{}: {:?} at {} of {:?} has {} pixels of {:?}",
+                frame.name,
+                c,
+                index,
+                line.text,
+                count,
+                expected
+            );
+            for other in palette.iter().filter(|other| **other != expected) {
+                assert!(
+                    !pixels().any(|pixel| matches(pixel, *other)),
+                    "{}: {:?} at {} of {:?} is {:?}",
+                    frame.name,
+                    c,
+                    index,
+                    line.text,
+                    other
+                );
+            }
+        }
+    }
+    // Glyphs start and end within their side bearings from the ends of the
+    // line
+    let ink = (0..frame.width)
+        .filter(|x| {
+            rows.clone()
+                .any(|y| !matches(frame.pixel((*x, y)), background))
+        })
+        .collect::<Vec<_>>();
+    let (left, right) = (ink[0] as f32, *ink.last().unwrap() as f32 + 1.0);
+    let bearing = line.size * 0.15;
+    if line.x >= 0.0 {
+        assert!(
+            (line.x - 1.0..line.x + bearing).contains(&left),
+            "{}: {:?} starts at {} instead of {}",
+            frame.name,
+            line.text,
+            left,
+            line.x
+        );
+    }
+    if end <= frame.width as f32 {
+        assert!(
+            (end - bearing..end + 1.0).contains(&right),
+            "{}: {:?} ends at {} instead of {}",
+            frame.name,
+            line.text,
+            right,
+            end
+        );
+    }
+}
+
+#[test]
+fn check_font_local_text() {
+    let frame = text_frame("font-local");
+    let line = TextLine {
+        text: "Hello",
+        x: 5.0,
+        baseline: 80.0,
+        size: 100.0,
+        tracking: 0.0,
+        word_spacing: 0.0,
+    };
+    check_text_line(&frame, &line, [1.0; 3], &[], |_| [0.0; 3]);
+}
+
+#[test]
+fn check_text_range() {
+    let frame = text_frame("text_range");
+    let (black, blue) = ([0.0; 3], [0.196, 0.314, 0.69]);
+    // Characters from 6 - 3 up to 23 - 3 are filled with the `fc` color of
+    // the animator, line breaks are not counted. Lines are centered, 1.2 em
+    // apart
+    let mut first = 0;
+    for (index, text) in [
+        "Hello World",
+        "the quick brown",
+        "fox jumps over",
+        "the lazy dog",
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let count = text.chars().count();
+        let line = TextLine {
+            text,
+            x: 256.0 - count as f32 * 70.0 * FIRA_MONO_ADVANCE / 2.0,
+            baseline: 70.0 + 84.0 * index as f32,
+            size: 70.0,
+            tracking: 0.0,
+            word_spacing: 0.0,
+        };
+        let color = |i: usize| {
+            if (3..20).contains(&(first + i)) {
+                blue
+            } else {
+                black
+            }
+        };
+        check_text_line(&frame, &line, [1.0; 3], &[black, blue], color);
+        first += count;
+    }
+}
+
+#[test]
+fn check_text_letter_spacing() {
+    let frame = text_frame("text_range_letter_spacing");
+    // Tracking adds a thousandth of an em per unit after each character, the
+    // tracking animator starts at 0
+    let line = TextLine {
+        text: "Hello World",
+        x: 168.0,
+        baseline: 548.0,
+        size: 88.0,
+        tracking: 58.0 / 1000.0 * 88.0,
+        word_spacing: 0.0,
+    };
+    check_text_line(&frame, &line, [1.0; 3], &[], |_| [0.141; 3]);
+}
+
+#[test]
+fn check_text_layout() {
+    let frame = text_frame("text_layout");
+    let (white, black) = ([1.0; 3], [0.0; 3]);
+    let is_color = |pixel: &[u8], color: [f32; 3]| {
+        pixel
+            .iter()
+            .zip(color)
+            .all(|(c, e)| (*c as i32 - (e * 255.0).round() as i32).abs() <= 8)
+    };
+    // Points of the frame in `columns` and `rows` which aren't background
+    let ink = |columns: Range<f32>, rows: Range<usize>| {
+        rows.flat_map(|y| (columns.start as usize..columns.end as usize).map(move |x| (x, y)))
+            .filter(|point| !is_color(frame.pixel(*point), white))
+            .collect::<Vec<_>>()
+    };
+
+    // The same text in a box 130 wide with each justification. Lines break
+    // at the last space fitting in the box, their first baseline is an ascent
+    // of 0.75 em below the top of the box and the next ones are 26 apart
+    let (size, box_width) = (20.0, 130.0);
+    let lines = ["The quick", "brown fox", "naps"];
+    for justify in 0..7 {
+        let top = 10.0 + 86.0 * justify as f32;
+        for (index, text) in lines.into_iter().enumerate() {
+            let last = index == lines.len() - 1;
+            let count = text.chars().count();
+            let spaces = text.chars().filter(|c| *c == ' ').count();
+            let remaining = box_width - size * FIRA_MONO_ADVANCE * count as f32;
+            // Justified lines spread the remaining width over their spaces,
+            // or between their characters for single words. The last line of
+            // a paragraph is aligned by the last line mode instead, except
+            // with full justification
+            let justified = justify == 6 || (3..6).contains(&justify) && !last;
+            let align = match (justify, last) {
+                (1, _) | (4, true) => 1.0,
+                (2, _) | (5, true) => 0.5,
+                _ => 0.0,
+            };
+            let (x, tracking, word_spacing) = match (justified, spaces) {
+                (false, _) => (10.0 + remaining * align, 0.0, 0.0),
+                (true, 0) => (10.0, remaining / (count - 1) as f32, 0.0),
+                (true, spaces) => (10.0, 0.0, remaining / spaces as f32),
+            };
+            let line = TextLine {
+                text,
+                x,
+                baseline: top + size * 0.75 + 26.0 * index as f32,
+                size,
+                tracking,
+                word_spacing,
+            };
+            check_text_line(&frame, &line, white, &[], |_| black);
+            // First characters have no descender, their ink ends on the
+            // baseline, or just below it for round bottoms
+            let baseline = line.baseline as usize;
+            let advance = size * FIRA_MONO_ADVANCE;
+            let bottom = ink(x + 1.0..x + advance - 1.0, baseline - 20..baseline + 8)
+                .into_iter()
+                .map(|(_, y)| y + 1)
+                .max()
+                .unwrap();
+            assert!(
+                (baseline..=baseline + 1).contains(&bottom),
+                "{:?} justified by {} ends at {} instead of {}",
+                text,
+                justify,
+                bottom,
+                baseline
+            );
+        }
+    }
+
+    // Lowercase letters of small caps are capitals at 0.7 of the font size,
+    // which advance by 0.7 of the advance as well
+    let (baseline, size) = (640, 30.0);
+    let mut x = 10.0;
+    let mut heights = vec![];
+    for c in "Small Caps".chars() {
+        let scale = if c.is_lowercase() { 0.7 } else { 1.0 };
+        let advance = size * scale * FIRA_MONO_ADVANCE;
+        if c != ' ' {
+            let top = ink(x + 1.0..x + advance - 1.0, baseline - 40..baseline)
+                .into_iter()
+                .map(|(_, y)| y)
+                .min()
+                .unwrap();
+            heights.push((c, scale, (baseline - top) as f32));
+        }
+        x += advance;
+    }
+    let capitals = heights
+        .iter()
+        .filter(|(_, scale, _)| *scale == 1.0)
+        .map(|(_, _, height)| height)
+        .sum::<f32>()
+        / 2.0;
+    for (c, scale, height) in heights {
+        assert!(
+            (height - capitals * scale).abs() < 1.5,
+            "{:?} of small caps is {} high instead of {}",
+            c,
+            height,
+            capitals * scale
+        );
+    }
+    let right = ink(0.0..170.0, baseline - 40..baseline + 8)
+        .into_iter()
+        .map(|(x, _)| x as f32 + 1.0)
+        .fold(0.0, f32::max);
+    assert!(
+        (x - size * 0.7 * 0.15..x + 1.0).contains(&right),
+        "small caps end at {} instead of {}",
+        right,
+        x
+    );
+
+    // All caps are capitals at the font size, the line is right justified
+    let line = TextLine {
+        text: "ALL CAPS",
+        x: 160.0 - 8.0 * 20.0 * FIRA_MONO_ADVANCE,
+        baseline: 680.0,
+        size: 20.0,
+        tracking: 0.0,
+        word_spacing: 0.0,
+    };
+    check_text_line(&frame, &line, white, &[], |_| black);
+
+    // Strokes 4 wide are centered on the outline of the glyphs. The stroke
+    // of the left "O" is drawn over its fill, while the fill of the right one
+    // hides the inner half of its stroke
+    let (fill, stroke) = ([1.0, 0.8, 0.2], [0.0, 0.0, 0.8]);
+    let count = |columns: Range<f32>, color: [f32; 3]| {
+        ink(columns, 700..800)
+            .into_iter()
+            .filter(|point| is_color(frame.pixel(*point), color))
+            .count() as f32
+    };
+    let above = (count(0.0..85.0, stroke), count(0.0..85.0, fill));
+    let below = (count(85.0..170.0, stroke), count(85.0..170.0, fill));
+    assert!(
+        above.0 > below.0 * 1.5 && above.1 < below.1,
+        "stroke and fill pixels {:?} with the stroke above, {:?} below",
+        above,
+        below
+    );
+    assert!(((above.0 + above.1) / (below.0 + below.1) - 1.0).abs() < 0.05);
+}
+
+/// Cubic segments of a lottie bezier with vertices `v` and tangents `i` and
+/// `o`, moved by `origin`
+fn cubics(
+    origin: [f32; 2],
+    v: &[[f32; 2]],
+    i: &[[f32; 2]],
+    o: &[[f32; 2]],
+    closed: bool,
+) -> Vec<[[f32; 2]; 4]> {
+    let add = |a: [f32; 2], b: [f32; 2]| [a[0] + b[0], a[1] + b[1]];
+    let count = if closed { v.len() } else { v.len() - 1 };
+    (0..count)
+        .map(|k| {
+            let next = (k + 1) % v.len();
+            [v[k], add(v[k], o[k]), add(v[next], i[next]), v[next]].map(|p| add(p, origin))
+        })
+        .collect()
+}
+
+/// Points along `segments`, close enough to measure distances along them
+fn flatten(segments: &[[[f32; 2]; 4]]) -> Vec<[f32; 2]> {
+    const STEPS: usize = 2000;
+    let point = |[p0, p1, p2, p3]: [[f32; 2]; 4], t: f32| {
+        let u = 1.0 - t;
+        [0, 1].map(|k| {
+            u * u * u * p0[k]
+                + 3.0 * u * u * t * p1[k]
+                + 3.0 * u * t * t * p2[k]
+                + t * t * t * p3[k]
+        })
+    };
+    segments
+        .iter()
+        .flat_map(|segment| (0..STEPS).map(move |step| point(*segment, step as f32 / STEPS as f32)))
+        .chain(segments.last().map(|segment| segment[3]))
+        .collect()
+}
+
+/// Length of `segments`
+fn cubics_length(segments: &[[[f32; 2]; 4]]) -> f32 {
+    flatten(segments)
+        .windows(2)
+        .map(|pair| (pair[1][0] - pair[0][0]).hypot(pair[1][1] - pair[0][1]))
+        .sum()
+}
+
+/// Point and unit tangent at `distance` along `segments`
+fn sample_cubics(segments: &[[[f32; 2]; 4]], distance: f32) -> ([f32; 2], [f32; 2]) {
+    let mut travelled = 0.0;
+    for pair in flatten(segments).windows(2) {
+        let delta = [pair[1][0] - pair[0][0], pair[1][1] - pair[0][1]];
+        let length = delta[0].hypot(delta[1]);
+        if travelled + length >= distance {
+            let t = (distance - travelled) / length;
+            let at = [0, 1].map(|k| pair[0][k] + delta[k] * t);
+            return (at, delta.map(|d| d / length));
+        }
+        travelled += length;
+    }
+    panic!("{} is past the end of the path", distance);
+}
+
+/// Check glyphs of `text` drawn in `color` along a path, the middle of the
+/// baseline of each character is at a point of `placements`, which also
+/// gives the direction of the baseline. Every pixel of the color belongs to
+/// the cell of the closest visible character, between its baseline and the
+/// top of its capitals
+fn check_text_on_path(
+    frame: &FirstFrame,
+    text: &str,
+    size: f32,
+    color: [f32; 3],
+    placements: &[([f32; 2], [f32; 2])],
+) {
+    const TOLERANCE: i32 = 8;
+    let advance = size * FIRA_MONO_ADVANCE;
+    let height = frame.data.len() / 4 / frame.width;
+    let chars = text.chars().collect::<Vec<_>>();
+    // Baseline middle, tangent and normal of each character, the normal
+    // points from the top of the glyph to its baseline
+    let glyphs = placements
+        .iter()
+        .map(|(point, tangent)| (*point, *tangent, [-tangent[1], tangent[0]]))
+        .collect::<Vec<_>>();
+    let mut counts = vec![0; chars.len()];
+    for y in 0..height {
+        for x in 0..frame.width {
+            let pixel = frame.pixel((x, y));
+            if !pixel
+                .iter()
+                .zip(color)
+                .all(|(c, e)| (*c as i32 - (e * 255.0).round() as i32).abs() <= TOLERANCE)
+            {
+                continue;
+            }
+            let p = [x as f32 + 0.5, y as f32 + 0.5];
+            let local = |(point, tangent, normal): &([f32; 2], [f32; 2], [f32; 2])| {
+                let d = [p[0] - point[0], p[1] - point[1]];
+                let u = d[0] * tangent[0] + d[1] * tangent[1] + advance / 2.0;
+                let v = d[0] * normal[0] + d[1] * normal[1];
+                (u, v)
+            };
+            let (index, (u, v)) = glyphs
+                .iter()
+                .map(local)
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    let distance = |(u, v): (f32, f32)| (u - advance / 2.0).hypot(v + size * 0.35);
+                    distance(*a).total_cmp(&distance(*b))
+                })
+                .unwrap();
+            assert!(
+                chars[index] != ' '
+                    && (-1.5..advance + 1.5).contains(&u)
+                    && (-size * 0.8..1.5).contains(&v),
+                "{}: pixel at {:?} is at {:?} from {:?} at {} of {:?}",
+                frame.name,
+                (x, y),
+                (u, v),
+                chars[index],
+                index,
+                text
+            );
+            counts[index] += 1;
+        }
+    }
+    for (index, c) in chars.iter().enumerate().filter(|(_, c)| **c != ' ') {
+        assert!(
+            counts[index] as f32 > size * size * 0.02,
+            "{}: {:?} at {} of {:?} has {} pixels",
+            frame.name,
+            c,
+            index,
+            text,
+            counts[index]
+        );
+    }
+}
+
+#[test]
+fn check_text_path() {
+    let frame = text_frame("text_path");
+    // Characters are placed by the middle of their baseline, and turned
+    // along the path
+    let place = |segments: &[[[f32; 2]; 4]], distances: Vec<f32>| {
+        distances
+            .into_iter()
+            .map(|distance| sample_cubics(segments, distance))
+            .collect::<Vec<_>>()
+    };
+
+    // Centered text on a circle of radius 100 starting from its bottom
+    let circle = cubics(
+        [150.0, 130.0],
+        &[[0.0, 100.0], [-100.0, 0.0], [0.0, -100.0], [100.0, 0.0]],
+        &[[55.23, 0.0], [0.0, 55.23], [-55.23, 0.0], [0.0, -55.23]],
+        &[[-55.23, 0.0], [0.0, -55.23], [55.23, 0.0], [0.0, 55.23]],
+        true,
+    );
+    let (text, size) = ("WELCOME ON BOARD", 20.0);
+    let advance = size * FIRA_MONO_ADVANCE;
+    let width = advance * text.len() as f32;
+    let start = (cubics_length(&circle) - width) / 2.0;
+    let distances = (0..text.len())
+        .map(|index| start + advance * (index as f32 + 0.5))
+        .collect();
+    let placements = place(&circle, distances);
+    check_text_on_path(&frame, text, size, [0.1, 0.2, 0.6], &placements);
+
+    // Forced alignment spreads the characters between margins of 10 on the
+    // reversed arch
+    let mut arch = cubics(
+        [150.0, 270.0],
+        &[[120.0, 0.0], [0.0, -40.0], [-120.0, 0.0]],
+        &[[0.0, 0.0], [40.0, 0.0], [40.0, 0.0]],
+        &[[-40.0, 0.0], [-40.0, 0.0], [0.0, 0.0]],
+        false,
+    );
+    arch.reverse();
+    for segment in &mut arch {
+        segment.reverse();
+    }
+    let (text, size) = ("FOLLOW", 24.0);
+    let advance = size * FIRA_MONO_ADVANCE;
+    let count = text.len() as f32;
+    let spacing = (cubics_length(&arch) - 20.0 - advance * count) / (count - 1.0);
+    let distances = (0..text.len())
+        .map(|index| 10.0 + (advance + spacing) * index as f32 + advance / 2.0)
+        .collect();
+    let placements = place(&arch, distances);
+    check_text_on_path(&frame, text, size, [0.8, 0.2, 0.1], &placements);
+}
+
+/// Result of an opaque `source` blended over an opaque `backdrop`
+fn blend(mode: BlendMode, backdrop: [f32; 3], source: [f32; 3]) -> [f32; 3] {
+    let separable =
+        |f: fn(f32, f32) -> f32| [0, 1, 2].map(|i| f(backdrop[i], source[i]).clamp(0.0, 1.0));
+    match mode {
+        BlendMode::Normal | BlendMode::HardMix => source,
+        BlendMode::Multiply => separable(|b, s| b * s),
+        BlendMode::Screen => separable(screen),
+        BlendMode::Overlay => separable(|b, s| hard_light(s, b)),
+        BlendMode::Darken => separable(f32::min),
+        BlendMode::Lighten => separable(f32::max),
+        BlendMode::ColorDodge => separable(|b, s| {
+            if b == 0.0 {
+                0.0
+            } else if s == 1.0 {
+                1.0
+            } else {
+                (b / (1.0 - s)).min(1.0)
+            }
+        }),
+        BlendMode::ColorBurn => separable(|b, s| {
+            if b == 1.0 {
+                1.0
+            } else if s == 0.0 {
+                0.0
+            } else {
+                1.0 - ((1.0 - b) / s).min(1.0)
+            }
+        }),
+        BlendMode::HighLight => separable(hard_light),
+        BlendMode::SoftLight => separable(|b, s| {
+            if s <= 0.5 {
+                b - (1.0 - 2.0 * s) * b * (1.0 - b)
+            } else {
+                let d = if b <= 0.25 {
+                    ((16.0 * b - 12.0) * b + 4.0) * b
+                } else {
+                    b.sqrt()
+                };
+                b + (2.0 * s - 1.0) * (d - b)
+            }
+        }),
+        BlendMode::Difference => separable(|b, s| (b - s).abs()),
+        BlendMode::Exclusion => separable(|b, s| b + s - 2.0 * b * s),
+        BlendMode::Add => separable(|b, s| b + s),
+        BlendMode::Hue => set_lum(set_sat(source, sat(backdrop)), lum(backdrop)),
+        BlendMode::Saturation => set_lum(set_sat(backdrop, sat(source)), lum(backdrop)),
+        BlendMode::Color => set_lum(source, lum(backdrop)),
+        BlendMode::Luminosity => set_lum(backdrop, lum(source)),
+    }
+}
+
+fn screen(b: f32, s: f32) -> f32 {
+    b + s - b * s
+}
+
+fn hard_light(b: f32, s: f32) -> f32 {
+    if s <= 0.5 {
+        b * 2.0 * s
+    } else {
+        screen(b, 2.0 * s - 1.0)
+    }
+}
+
+fn lum(c: [f32; 3]) -> f32 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(c);
+    let c = c.map(|x| x + d);
+    let l = lum(c);
+    let (n, x) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    c.map(|v| {
+        if n < 0.0 {
+            l + (v - l) * l / (l - n)
+        } else if x > 1.0 {
+            l + (v - l) * (1.0 - l) / (x - l)
+        } else {
+            v
+        }
+    })
+}
+
+fn sat(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let (min, max) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    if max > min {
+        c.map(|v| (v - min) * s / (max - min))
+    } else {
+        [0.0; 3]
+    }
+}
+
+/// Compare RGBA8 data with a checked png, within tolerance
+fn assert_similar(path: &Path, data: &[u8]) {
+    let decoder = png::Decoder::new(File::open(path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    let correct = &buf[..info.buffer_size()];
+
+    assert_eq!(
+        correct.len(),
+        data.len(),
+        "{}: size mismatch",
+        path.display()
+    );
+    let differing = correct
+        .chunks_exact(4)
+        .zip(data.chunks_exact(4))
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(b.iter())
+                .any(|(x, y)| (*x as i32 - *y as i32).abs() > CHANNEL_TOLERANCE)
+        })
+        .count();
+    let ratio = differing as f32 / (correct.len() / 4) as f32;
+    assert!(
+        ratio < PIXEL_TOLERANCE,
+        "{}: {:.2}% pixels differ",
+        path.display(),
+        ratio * 100.0
+    );
+}